@@ -0,0 +1,49 @@
+program Array;
+const n := 5;
+var a[5], b[1 .. 5], i, j;
+
+procedure swap(var x, var y);
+var t;
+begin
+  t := x;
+  x := y;
+  y := t
+end;
+
+function sum();
+var k, s;
+begin
+  k := 0;
+  s := 0;
+  while k < n do
+  begin
+    s := s + a[k];
+    k := k + 1
+  end;
+  sum := s
+end
+
+begin
+  i := 0;
+  while i < n do
+  begin
+    read(a[i]);
+    b[i + 1] := a[i] * a[i];
+    i := i + 1
+  end;
+  { bubble sort }
+  i := 0;
+  while i < n - 1 do
+  begin
+    j := 0;
+    while j < n - 1 - i do
+    begin
+      if a[j] > a[j + 1] then call swap(a[j], a[j + 1]);
+      j := j + 1
+    end;
+    i := i + 1
+  end;
+  write(a[0], a[1], a[2], a[3], a[4]);
+  write(b[1], b[5]);
+  write(sum())
+end
@@ -0,0 +1,29 @@
+program BoolOps;
+var
+  n, i, calls;
+
+function touch(x);
+begin
+  calls := calls + 1;
+  touch := x
+end
+
+begin
+  read(n);
+  calls := 0;
+  { the division is skipped if `n` is 0 }
+  if (n <> 0) and (10 / n > 1) then write(1) else write(0);
+  if (n = 0) or (10 / n < 5) then write(1) else write(0);
+  if not (n > 0) then write(1) else write(0);
+  if not odd n and (n < 10) then write(1) else write(0);
+  if (n < 0) or (n > 5) and (n < 100) then write(1) else write(0);
+  if ((n < 0) or (n > 5)) and (n < 100) then write(1) else write(0);
+
+  { `touch` is called only if needed }
+  if (touch(n) > 0) or (touch(n) > 1) then write(calls);
+  if (touch(n) < 0) and (touch(n) < 1) then write(0) else write(calls);
+
+  i := 0;
+  while (i < 10) and not (i * i > n) do i := i + 1;
+  write(i)
+end
@@ -0,0 +1,31 @@
+program fibonacci;
+const index := 20;
+var i;
+
+function fib(x);
+begin
+  if x<2 then
+    fib := x
+  else
+    fib := fib(x-1)+fib(x-2)
+end;
+
+function max(a,b);
+  procedure check();
+  begin
+    if a>=b then max := a
+  end
+begin
+  max := b;
+  call check()
+end
+
+begin
+  i := 1;
+  while i<=index do
+    begin
+      write(fib(i), max(i, 10));
+      i := i+1
+    end;
+  call fib(3)
+end
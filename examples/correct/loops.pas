@@ -0,0 +1,48 @@
+program Loops;
+var
+  n, i, j, sum, fact;
+
+procedure triangle(k);
+var i, j;
+begin
+  for i := 1 to k do
+  begin
+    for j := i downto 1 do write(j);
+    { `j` is `1` after the loop }
+    write(j * 0)
+  end
+end
+
+begin
+  read(n);
+
+  { the bound is evaluated once }
+  sum := 0;
+  for i := 1 to n do
+  begin
+    n := n - 1;
+    sum := sum + i
+  end;
+  write(sum, n);
+
+  { empty range, never runs }
+  for i := 5 to 1 do write(-1);
+  for i := 1 downto 5 do write(-1);
+
+  { runs at least once }
+  i := 10;
+  repeat
+    write(i);
+    i := i + 1
+  until i > 3;
+
+  fact := 1;
+  j := 0;
+  repeat
+    j := j + 1;
+    fact := fact * j
+  until (fact > 100) or (j = 10);
+  write(j, fact);
+
+  call triangle(3)
+end
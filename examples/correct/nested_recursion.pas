@@ -0,0 +1,33 @@
+program nestedRecursion;
+var n;
+
+function fact(x);
+  function prev();
+  begin
+    prev := fact(x-1)
+  end
+begin
+  if x<=1 then
+    fact := 1
+  else
+    fact := x*prev()
+end;
+
+procedure countdown(x);
+  procedure next();
+  begin
+    call countdown(x-1)
+  end
+begin
+  if x>0 then
+    begin
+      write(x);
+      call next()
+    end
+end
+
+begin
+  read(n);
+  write(fact(n));
+  call countdown(n)
+end
@@ -0,0 +1,41 @@
+program VarParam;
+var a, b, n;
+
+procedure swap(var x, var y);
+var t;
+begin
+  t := x;
+  x := y;
+  y := t
+end;
+
+procedure sort3(var x, var y, var z);
+begin
+  if x > y then call swap(x, y);
+  if y > z then call swap(y, z);
+  if x > y then call swap(x, y)
+end;
+
+function divmod(x, y, var r);
+begin
+  divmod := x / y;
+  r := x - x / y * y
+end;
+
+procedure readTwice(var x);
+begin
+  read(x);
+  x := x * 2
+end
+
+begin
+  a := 1;
+  b := 2;
+  call swap(a, b);
+  write(a, b);
+  call readTwice(n);
+  write(divmod(n, 7, a), a);
+  b := 3;
+  call sort3(n, a, b);
+  write(a, b, n)
+end
@@ -0,0 +1,12 @@
+{ Wirth-style comment }
+program Comments;
+(* a block comment
+   spanning lines, could contain anything: 注释, { and (* *)
+var a, b; // a line comment
+begin
+  a := 1; { a := 2; }
+  b := a (* + 1 *) * 2 // * 3
+  ;
+  write(a / 1); // division is not a comment
+  write(b)
+end
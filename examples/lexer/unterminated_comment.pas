@@ -0,0 +1,6 @@
+program Unterminated;
+var a;
+begin
+  (* never closed
+  a := 1
+end
@@ -0,0 +1,13 @@
+program ConstFold;
+const k := 3;
+var x;
+procedure shadow(k);
+begin
+  write(k * 2)
+end
+begin
+  x := 1 + k * 2;
+  write(x * 1 + 0);
+  write(-(k - 4) * (7 / 2));
+  call shadow(5)
+end
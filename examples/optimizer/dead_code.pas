@@ -0,0 +1,17 @@
+program DeadCode;
+const debug := 0;
+var x;
+begin
+  x := 1;
+  if debug = 1 then write(100) else write(x);
+  if debug = 0 then write(2);
+  if odd debug then write(200);
+  while debug <> 0 do x := x + 1;
+  while 1 = 1 do
+  begin
+    x := x + 1;
+    write(x)
+  end;
+  write(300);
+  x := 0
+end
@@ -0,0 +1,8 @@
+program DivByConstZero;
+const zero := 0;
+var a;
+begin
+  a := 10;
+  write(a / 0);
+  write(a / zero)
+end
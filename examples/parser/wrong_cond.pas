@@ -0,0 +1,9 @@
+program WrongCond;
+var x;
+begin
+  if x > 0 and then write(1);
+  if not then write(2);
+  while (x > 0) or (x < do x := x - 1;
+  if (x > 0 and odd x then write(3);
+  write(x)
+end
@@ -0,0 +1,7 @@
+program DivByZero;
+var a, b;
+begin
+  a := 1;
+  b := 0;
+  write(a / b)
+end
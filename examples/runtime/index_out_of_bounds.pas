@@ -0,0 +1,10 @@
+program IndexOutOfBounds;
+var a[1 .. 10], i;
+begin
+  i := 1;
+  while i <= 11 do
+  begin
+    a[i] := i;
+    i := i + 1
+  end
+end
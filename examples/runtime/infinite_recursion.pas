@@ -0,0 +1,8 @@
+program InfiniteRecursion;
+procedure forever();
+begin
+  call forever()
+end
+begin
+  call forever()
+end
@@ -0,0 +1,8 @@
+program overflow;
+
+var a;
+
+begin
+  read(a);
+  write(a * 2)
+end
@@ -0,0 +1,16 @@
+program MisuseArray;
+var a[10], b[5 .. 1], c[0], x;
+
+procedure inc(var y);
+begin
+  y := y + 1
+end
+
+begin
+  x[1] := 2;
+  a := 1;
+  read(a, x[0], d);
+  write(a + 1, x[0], d[0]);
+  call inc(a);
+  call inc(a[x])
+end
@@ -0,0 +1,25 @@
+program MisuseFor;
+const c := 10;
+var i, j, a[3];
+
+procedure inc(var x);
+begin
+  x := x + 1;
+  for x := 1 to 2 do write(x)
+end
+
+begin
+  for i := 1 to c do
+  begin
+    i := i + 1;
+    read(i);
+    call inc(i);
+    for j := i to c do
+      for i := 1 to j do write(i, j)
+  end;
+  { fine after the loop }
+  i := 0;
+  for c := 1 to 2 do write(c);
+  for a := 1 to 2 do write(1);
+  for k := 1 to 2 do write(2)
+end
@@ -0,0 +1,15 @@
+program MisuseFunc;
+var a;
+procedure p();
+begin
+  a := 1
+end;
+function f(x);
+begin
+  f := x
+end
+begin
+  a := a() + p() + f + f(1, 2) + g(1);
+  f := 2;
+  call f(1)
+end
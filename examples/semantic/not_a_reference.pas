@@ -0,0 +1,17 @@
+program NotAReference;
+const one := 1;
+var a;
+
+procedure inc(var x);
+begin
+  x := x + 1
+end
+
+begin
+  call inc(one);
+  call inc(a * 1);
+  call inc((a));
+  call inc(inc);
+  call inc(b);
+  call inc(a)
+end
@@ -2,12 +2,62 @@ use crate::lexer::Lexer;
 use derive_more::From;
 
 /// - format = (line_number, colon_number)
-#[derive(Debug, Clone, Copy)]
+/// - `Location(0, 0)` (default) means unknown
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
 pub struct Location(pub usize, pub usize);
 
+/// Start of the last token taken out of the lexer
 impl From<&Lexer<'_>> for Location {
   fn from(lexer: &Lexer<'_>) -> Self {
-    Self(lexer.line_num, lexer.col_num)
+    lexer.span().location
+  }
+}
+
+/// - start / end: byte offsets in the source, `start..end`
+/// - location / end_location: (line, col) of the first / last char
+///
+/// `Span::default()` means unknown.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Span {
+  pub start: usize,
+  pub end: usize,
+  pub location: Location,
+  pub end_location: Location,
+}
+
+impl Span {
+  pub fn is_unknown(&self) -> bool {
+    self.location == Location::default()
+  }
+
+  /// From the start of `self` to the end of `other`, unknown spans are
+  /// ignored
+  pub fn to(self, other: Span) -> Span {
+    if self.is_unknown() {
+      return other;
+    }
+    if other.is_unknown() {
+      return self;
+    }
+    Span {
+      start: self.start,
+      end: other.end,
+      location: self.location,
+      end_location: other.end_location,
+    }
+  }
+}
+
+impl From<Span> for Location {
+  fn from(span: Span) -> Self {
+    span.location
+  }
+}
+
+/// The last token taken out of the lexer
+impl From<&Lexer<'_>> for Span {
+  fn from(lexer: &Lexer<'_>) -> Self {
+    lexer.span()
   }
 }
 
@@ -18,6 +68,7 @@ pub enum AstExpr {
   ConstDeclExpr(Box<ConstDeclExpr>),
   ConstExpr(Box<ConstExpr>),
   VarDeclExpr(Box<VarDeclExpr>),
+  BoundExpr(Box<BoundExpr>),
   ProcExpr(Box<ProcExpr>),
   BodyExpr(Box<BodyExpr>),
   StatementExpr(Box<StatementExpr>),
@@ -32,10 +83,13 @@ pub enum AstExpr {
   IntegerExpr(Box<IntegerExpr>),
 }
 
+/// Every node carries its full span (from its first token to its last one),
+/// `Span::default()` if it's created by the optimizer.
 #[derive(Debug, Clone)]
 pub struct ProgramExpr {
   pub id: Box<IdExpr>,
   pub block: Box<BlockExpr>,
+  pub span: Span,
 }
 
 #[derive(Debug, Clone)]
@@ -44,64 +98,127 @@ pub struct BlockExpr {
   pub var_decl: Option<Box<VarDeclExpr>>,
   pub proc: Option<Box<ProcExpr>>,
   pub body: Box<BodyExpr>,
+  pub span: Span,
 }
 
 #[derive(Debug, Clone)]
 pub struct ConstDeclExpr {
   pub constants: Vec<Box<ConstExpr>>,
+  pub span: Span,
 }
 
 #[derive(Debug, Clone)]
 pub struct ConstExpr {
   pub id: Box<IdExpr>,
   pub integer: Box<IntegerExpr>,
+  pub span: Span,
 }
 
 #[derive(Debug, Clone)]
 pub struct VarDeclExpr {
   pub id_list: Vec<Box<IdExpr>>,
+  /// `Some` if the `<id>` at the same position is an array
+  pub bounds: Vec<Option<Box<BoundExpr>>>,
+  pub span: Span,
+}
+
+/// Bounds of an array, both inclusive
+#[derive(Debug, Clone)]
+pub enum BoundExpr {
+  /// `[n]`, the same as `[0 .. n - 1]`
+  Len(Box<IntegerExpr>, Span),
+  /// `[lo .. hi]`
+  Range(Box<IntegerExpr>, Box<IntegerExpr>, Span),
+}
+
+impl BoundExpr {
+  pub fn span(&self) -> Span {
+    match self {
+      Self::Len(_, span) | Self::Range(_, _, span) => *span,
+    }
+  }
+
+  /// `(lo, hi)`, `None` if `hi` overflows
+  pub fn range(&self) -> Option<(i64, i64)> {
+    match self {
+      Self::Len(len, _) => Some((0, len.0.checked_sub(1)?)),
+      Self::Range(lo, hi, _) => Some((lo.0, hi.0)),
+    }
+  }
 }
 
 #[derive(Debug, Clone)]
 pub struct ProcExpr {
   pub id: Box<IdExpr>,
+  /// `function` (with a return value) or `procedure`
+  pub is_function: bool,
   pub args: Vec<Box<IdExpr>>,
+  /// whether each of `args` is passed by reference (`var <id>`)
+  pub by_ref: Vec<bool>,
   pub block: Box<BlockExpr>,
   pub procs: Vec<Box<ProcExpr>>,
+  pub span: Span,
 }
 
 #[derive(Debug, Clone)]
 pub struct BodyExpr {
   pub statements: Vec<Box<StatementExpr>>,
+  pub span: Span,
 }
 
 #[derive(Debug, Clone)]
 pub enum StatementExpr {
+  /// `index` is `Some` for an element of an array
   Id {
     id: Box<IdExpr>,
+    index: Option<Box<ExpExpr>>,
     exp: Box<ExpExpr>,
+    span: Span,
   },
   If {
     l_exp: Box<LExpExpr>,
     then_statement: Box<StatementExpr>,
     else_statement: Option<Box<StatementExpr>>,
+    span: Span,
   },
   While {
     l_exp: Box<LExpExpr>,
     statement: Box<StatementExpr>,
+    span: Span,
+  },
+  /// `statements` run at least once, until `l_exp` is true
+  Repeat {
+    statements: Vec<Box<StatementExpr>>,
+    l_exp: Box<LExpExpr>,
+    span: Span,
+  },
+  /// `is_downto` for `downto` (`id` decreases), `to` otherwise
+  For {
+    id: Box<IdExpr>,
+    from: Box<ExpExpr>,
+    is_downto: bool,
+    to: Box<ExpExpr>,
+    statement: Box<StatementExpr>,
+    span: Span,
   },
   Call {
     id: Box<IdExpr>,
     args: Vec<Box<ExpExpr>>,
+    span: Span,
   },
   Body {
     body: Box<BodyExpr>,
+    span: Span,
   },
+  /// `index_list[i]` is `Some` if `id_list[i]` is indexed
   Read {
     id_list: Vec<Box<IdExpr>>,
+    index_list: Vec<Option<Box<ExpExpr>>>,
+    span: Span,
   },
   Write {
     exps: Vec<Box<ExpExpr>>,
+    span: Span,
   },
 }
 
@@ -111,10 +228,68 @@ pub enum LExpExpr {
     l_exp: Box<ExpExpr>,
     lop: Box<LopExpr>,
     r_exp: Box<ExpExpr>,
+    span: Span,
   },
   Odd {
     exp: Box<ExpExpr>,
+    span: Span,
+  },
+  Not {
+    l_exp: Box<LExpExpr>,
+    span: Span,
   },
+  /// `r_exp` is only evaluated if `l_exp` is true
+  And {
+    l_exp: Box<LExpExpr>,
+    r_exp: Box<LExpExpr>,
+    span: Span,
+  },
+  /// `r_exp` is only evaluated if `l_exp` is false
+  Or {
+    l_exp: Box<LExpExpr>,
+    r_exp: Box<LExpExpr>,
+    span: Span,
+  },
+  /// only produced by `AstOptimizer` (constant condition)
+  Const(bool, Span),
+}
+
+impl StatementExpr {
+  pub fn span(&self) -> Span {
+    match self {
+      Self::Id { span, .. }
+      | Self::If { span, .. }
+      | Self::While { span, .. }
+      | Self::Repeat { span, .. }
+      | Self::For { span, .. }
+      | Self::Call { span, .. }
+      | Self::Body { span, .. }
+      | Self::Read { span, .. }
+      | Self::Write { span, .. } => *span,
+    }
+  }
+
+  /// Location of the first token (`Location(0, 0)` if unknown)
+  pub fn location(&self) -> Location {
+    self.span().location
+  }
+}
+
+impl LExpExpr {
+  pub fn span(&self) -> Span {
+    match self {
+      Self::Exp { span, .. }
+      | Self::Odd { span, .. }
+      | Self::Not { span, .. }
+      | Self::And { span, .. }
+      | Self::Or { span, .. }
+      | Self::Const(_, span) => *span,
+    }
+  }
+
+  pub fn location(&self) -> Location {
+    self.span().location
+  }
 }
 
 #[derive(Debug, Clone)]
@@ -122,45 +297,131 @@ pub struct ExpExpr {
   pub is_negative: bool,
   pub term: Box<TermExpr>,
   pub aop_terms: Vec<(Box<AopExpr>, Box<TermExpr>)>,
+  pub span: Span,
 }
 
 #[derive(Debug, Clone)]
 pub struct TermExpr {
   pub factor: Box<FactorExpr>,
   pub mop_factors: Vec<(Box<MopExpr>, Box<FactorExpr>)>,
+  pub span: Span,
+}
+
+impl ExpExpr {
+  pub fn location(&self) -> Location {
+    self.span.location
+  }
+
+  /// The `<factor>` if it's nothing but a single one
+  pub fn as_factor(&self) -> Option<&FactorExpr> {
+    if self.is_negative || !self.aop_terms.is_empty() || !self.term.mop_factors.is_empty() {
+      return None;
+    }
+    Some(&self.term.factor)
+  }
 }
 
+impl TermExpr {
+  pub fn location(&self) -> Location {
+    self.span.location
+  }
+}
+
+/// `Exp` carries the span of `(<exp>)`, parentheses included
 #[derive(Debug, Clone)]
 pub enum FactorExpr {
   Id(Box<IdExpr>),
   Integer(Box<IntegerExpr>),
-  Exp(Box<ExpExpr>),
+  Exp(Box<ExpExpr>, Span),
+  /// call of a function, its return value is the value of the factor
+  Call {
+    id: Box<IdExpr>,
+    args: Vec<Box<ExpExpr>>,
+    span: Span,
+  },
+  /// an element of an array
+  Index {
+    id: Box<IdExpr>,
+    index: Box<ExpExpr>,
+    span: Span,
+  },
+}
+
+impl FactorExpr {
+  pub fn span(&self) -> Span {
+    match self {
+      Self::Id(id) => id.1,
+      Self::Integer(integer) => integer.1,
+      Self::Exp(_, span) | Self::Call { span, .. } | Self::Index { span, .. } => *span,
+    }
+  }
+
+  pub fn location(&self) -> Location {
+    self.span().location
+  }
 }
 
 #[derive(Debug, Clone, Copy)]
 pub enum LopExpr {
-  Eq(Location),
-  Ne(Location),
-  Lt(Location),
-  Le(Location),
-  Gt(Location),
-  Ge(Location),
+  Eq(Span),
+  Ne(Span),
+  Lt(Span),
+  Le(Span),
+  Gt(Span),
+  Ge(Span),
+}
+
+impl LopExpr {
+  pub fn span(&self) -> Span {
+    match self {
+      Self::Eq(loc) | Self::Ne(loc) | Self::Lt(loc) => *loc,
+      Self::Le(loc) | Self::Gt(loc) | Self::Ge(loc) => *loc,
+    }
+  }
+
+  pub fn location(&self) -> Location {
+    self.span().location
+  }
 }
 
 #[derive(Debug, Clone, Copy)]
 pub enum AopExpr {
-  Add(Location),
-  Sub(Location),
+  Add(Span),
+  Sub(Span),
+}
+
+impl AopExpr {
+  pub fn span(&self) -> Span {
+    match self {
+      Self::Add(loc) | Self::Sub(loc) => *loc,
+    }
+  }
+
+  pub fn location(&self) -> Location {
+    self.span().location
+  }
 }
 
 #[derive(Debug, Clone, Copy)]
 pub enum MopExpr {
-  Mul(Location),
-  Div(Location),
+  Mul(Span),
+  Div(Span),
+}
+
+impl MopExpr {
+  pub fn span(&self) -> Span {
+    match self {
+      Self::Mul(loc) | Self::Div(loc) => *loc,
+    }
+  }
+
+  pub fn location(&self) -> Location {
+    self.span().location
+  }
 }
 
 #[derive(Debug, Clone)]
-pub struct IdExpr(pub String, pub Location);
+pub struct IdExpr(pub String, pub Span);
 
 #[derive(Debug, Clone, Copy)]
-pub struct IntegerExpr(pub i64, pub Location);
+pub struct IntegerExpr(pub i64, pub Span);
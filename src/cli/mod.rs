@@ -0,0 +1,632 @@
+use std::{
+  cell::RefCell,
+  fmt::Display,
+  fs::{self, File},
+  io::{self, BufRead, BufReader, BufWriter, Write},
+  str::FromStr,
+};
+
+use pl_0::{
+  ast::ProgramExpr,
+  error::{
+    code::ErrorCode,
+    compile_error::CompileError,
+    diagnostic::{to_sarif, Diagnostic},
+    render::{ColorChoice, Renderer},
+    runtime_error::RuntimeError,
+    traits::ErrorTrait,
+    PL0Error,
+  },
+  lexer::{token_def::Token, Lexer},
+  optimizer::{peephole::PeepholeOptimizer, AstOptimizer},
+  parser::Parser,
+  pcode::{
+    object::{ObjectFile, MAGIC},
+    PCodeManager,
+  },
+  pest_parser::PestParser,
+  translator::Translator,
+  vm::{
+    arith::{IntSemantics, IntWidth, Overflow},
+    basic::VM,
+    io::{BufIo, StdIo, VmIo},
+  },
+};
+
+pub const EXIT_OK: i32 = 0;
+/// lexical / syntax / semantic errors
+pub const EXIT_COMPILE_ERROR: i32 = 1;
+pub const EXIT_RUNTIME_ERROR: i32 = 2;
+/// unreadable / unwritable file, malformed object, pcode rejected by the
+/// verifier
+pub const EXIT_IO_ERROR: i32 = 3;
+pub const EXIT_USAGE: i32 = 64;
+
+pub const USAGE: &str = "\
+Usage: pl_0 <COMMAND> [OPTIONS] <FILE>
+       pl_0 <FILE>                       (same as `pl_0 run <FILE>`)
+
+Commands:
+  lex     Print tokens of the source code
+  parse   Print the AST of the source code
+  check   Run lexical / syntax / semantic checks only
+  emit    Compile the source code into pcode (text or binary object)
+  run     Run source code, textual pcode (`.pcode`) or a binary object
+  explain Print the long-form description of an error code (`pl_0 explain E0202`)
+  help    Print this message
+
+Options:
+  -O<LEVEL>, --opt-level <LEVEL>  0: none, 1: AST, 2: AST + peephole (default: 2)
+  -f, --format <text|binary>      Format of `emit` (default: text)
+  -i, --input <FILE>              Read the input of `run` from FILE instead of stdin
+  -o, --output <FILE>             Write the output to FILE instead of stdout
+      --no-prompt                 Don't prompt before `read` (`run`)
+      --front-end <NAME>          Parser of the source code (default: handwritten)
+                                  handwritten: recursive descent, with recovery
+                                  pest:        generated from the pest grammar
+      --int-width <i16|i32|i64>   Width of integers (default: i64)
+      --overflow <BEHAVIOR>       On integer overflow (default: checked)
+                                  checked:    a runtime error
+                                  wrapping:   wraps around
+                                  saturating: clamped to the min / max
+      --color <auto|always|never> Colorize diagnostics (default: auto)
+      --error-format <FORMAT>     Format of diagnostics on stderr (default: human)
+                                  human: with source snippets
+                                  json:  one JSON object per line
+                                  sarif: a SARIF 2.1 log, after all stages
+  -h, --help                      Print this message
+
+Exit codes:
+  0: success, 1: compile error, 2: runtime error, 3: I/O error / invalid pcode,
+  64: wrong usage
+";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Command {
+  Lex,
+  Parse,
+  Check,
+  Emit,
+  Run,
+  Explain,
+  Help,
+}
+
+/// - O0: no optimization
+/// - O1: `AstOptimizer`
+/// - O2: `AstOptimizer` + `PeepholeOptimizer`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Default)]
+pub enum OptLevel {
+  O0,
+  O1,
+  #[default]
+  O2,
+}
+
+/// Both build the same AST, only the handwritten one recovers from syntax
+/// errors
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum FrontEnd {
+  #[default]
+  Handwritten,
+  Pest,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum EmitFormat {
+  #[default]
+  Text,
+  Binary,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ErrorFormat {
+  #[default]
+  Human,
+  Json,
+  Sarif,
+}
+
+/// Result of `Cli::compile`
+#[derive(Debug, Clone)]
+pub struct Compiled {
+  pub object: ObjectFile,
+  /// pcode removed by `PeepholeOptimizer`, always 0 below O2
+  pub n_removed: usize,
+}
+
+/// Loaded by `Cli::load`, ready to run
+struct Program {
+  code: PCodeManager,
+  int: IntSemantics,
+  /// to render runtime errors, if it's compiled from source code
+  source: Option<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Cli {
+  pub command: Command,
+  /// or the error code of `explain`
+  pub file: String,
+  pub opt_level: OptLevel,
+  pub format: EmitFormat,
+  pub input: Option<String>,
+  pub output: Option<String>,
+  pub prompt: bool,
+  pub front_end: FrontEnd,
+  /// `--int-width` / `--overflow`, used by both compiling and running. `None`
+  /// if not given: the default one, or what's kept in the binary object
+  pub int_width: Option<IntWidth>,
+  pub overflow: Option<Overflow>,
+  pub color: ColorChoice,
+  pub error_format: ErrorFormat,
+  /// collected for `ErrorFormat::Sarif`
+  diagnostics: RefCell<Vec<Diagnostic>>,
+}
+
+impl Cli {
+  /// Parse the args (without the name of the executable)
+  pub fn parse(args: &[String]) -> Result<Self, String> {
+    let mut args = args.iter();
+    let mut command = None;
+    let mut file = None;
+    let mut cli = Self {
+      command: Command::Run,
+      file: String::new(),
+      opt_level: OptLevel::default(),
+      format: EmitFormat::default(),
+      input: None,
+      output: None,
+      prompt: true,
+      front_end: FrontEnd::default(),
+      int_width: None,
+      overflow: None,
+      color: ColorChoice::default(),
+      error_format: ErrorFormat::default(),
+      diagnostics: RefCell::new(vec![]),
+    };
+
+    while let Some(arg) = args.next() {
+      let mut value_of = |name: &str| {
+        args
+          .next()
+          .cloned()
+          .ok_or_else(|| format!("`{}` expects a value", name))
+      };
+      match arg.as_str() {
+        "-h" | "--help" => command = Some(Command::Help),
+        "-O" | "--opt-level" => cli.opt_level = parse_opt_level(&value_of(arg)?)?,
+        opt if opt.starts_with("-O") => cli.opt_level = parse_opt_level(&opt[2..])?,
+        "-f" | "--format" => {
+          cli.format = match value_of(arg)?.as_str() {
+            "text" => EmitFormat::Text,
+            "binary" => EmitFormat::Binary,
+            format => return Err(format!("unknown format `{}`", format)),
+          }
+        }
+        "-i" | "--input" => cli.input = Some(value_of(arg)?),
+        "-o" | "--output" => cli.output = Some(value_of(arg)?),
+        "--no-prompt" => cli.prompt = false,
+        "--front-end" => {
+          cli.front_end = match value_of(arg)?.as_str() {
+            "handwritten" => FrontEnd::Handwritten,
+            "pest" => FrontEnd::Pest,
+            front_end => return Err(format!("unknown front-end `{}`", front_end)),
+          }
+        }
+        "--int-width" => cli.int_width = Some(value_of(arg)?.parse()?),
+        "--overflow" => cli.overflow = Some(value_of(arg)?.parse()?),
+        "--color" => {
+          cli.color = match value_of(arg)?.as_str() {
+            "auto" => ColorChoice::Auto,
+            "always" => ColorChoice::Always,
+            "never" => ColorChoice::Never,
+            color => return Err(format!("unknown color choice `{}`", color)),
+          }
+        }
+        "--error-format" => {
+          cli.error_format = match value_of(arg)?.as_str() {
+            "human" => ErrorFormat::Human,
+            "json" => ErrorFormat::Json,
+            "sarif" => ErrorFormat::Sarif,
+            format => return Err(format!("unknown error format `{}`", format)),
+          }
+        }
+        opt if opt.starts_with('-') && opt.len() > 1 => {
+          return Err(format!("unknown option `{}`", opt))
+        }
+        word if command.is_none() && file.is_none() => {
+          command = Some(match word {
+            "lex" => Command::Lex,
+            "parse" => Command::Parse,
+            "check" => Command::Check,
+            "emit" => Command::Emit,
+            "run" => Command::Run,
+            "explain" => Command::Explain,
+            "help" => Command::Help,
+            // `pl_0 <FILE>`
+            _ => {
+              file = Some(word.to_string());
+              Command::Run
+            }
+          })
+        }
+        word if file.is_none() => file = Some(word.to_string()),
+        word => return Err(format!("unexpected argument `{}`", word)),
+      }
+    }
+
+    cli.command = command.unwrap_or(Command::Help);
+    match file {
+      Some(file) => cli.file = file,
+      None if cli.command == Command::Help => (),
+      None => return Err("missing <FILE>".to_string()),
+    }
+    Ok(cli)
+  }
+
+  /// Collected so far, only for `ErrorFormat::Sarif`
+  pub fn diagnostics(&self) -> Vec<Diagnostic> {
+    self.diagnostics.borrow().clone()
+  }
+
+  /// Given by `--int-width` / `--overflow`, or the default one
+  pub fn int_semantics(&self) -> IntSemantics {
+    IntSemantics::new(
+      self.int_width.unwrap_or_default(),
+      self.overflow.unwrap_or_default(),
+    )
+  }
+
+  /// Execute the command, returns the exit code
+  pub fn execute(&self) -> i32 {
+    let result = match self.command {
+      Command::Help => {
+        print!("{}", USAGE);
+        Ok(())
+      }
+      Command::Lex => self.lex(),
+      Command::Parse => self.parse_only(),
+      Command::Check => self.check(),
+      Command::Emit => self.emit(),
+      Command::Run => self.run(),
+      Command::Explain => self.explain(),
+    };
+    let has_diagnostics = !matches!(self.command, Command::Help | Command::Explain);
+    if self.error_format == ErrorFormat::Sarif && has_diagnostics {
+      eprintln!("{}", to_sarif(&self.diagnostics()));
+    }
+    match result {
+      Ok(()) => EXIT_OK,
+      Err(code) => code,
+    }
+  }
+}
+
+impl Cli {
+  fn renderer<'a>(&'a self, source: &'a str) -> Renderer<'a> {
+    Renderer::new(source, &self.file).with_color(self.color)
+  }
+
+  /// Report a diagnostic in `error_format`, rendered with `source` (if any)
+  fn report(&self, source: Option<&str>, err: &(impl ErrorTrait + Display)) {
+    match self.error_format {
+      ErrorFormat::Human => match source {
+        Some(source) => eprintln!("{}", self.renderer(source).render(err)),
+        None => eprintln!("{}", err),
+      },
+      ErrorFormat::Json => eprintln!("{}", Diagnostic::new(err, &self.file).to_json()),
+      ErrorFormat::Sarif => self
+        .diagnostics
+        .borrow_mut()
+        .push(Diagnostic::new(err, &self.file)),
+    }
+  }
+
+  /// `|> ... <|`, only for humans
+  fn report_stopped(&self, message: &str) {
+    if self.error_format == ErrorFormat::Human {
+      eprintln!("|> {} <|\n", message);
+    }
+  }
+
+  /// Print the errors of a failed stage
+  fn stage<T>(
+    &self,
+    name: &str,
+    source: &str,
+    result: Result<T, Vec<CompileError>>,
+  ) -> Result<T, i32> {
+    result.map_err(|errors| {
+      errors.iter().for_each(|err| self.report(Some(source), err));
+      self.report_stopped(&format!(
+        "Errors above occurred (during `{}`), compiling stopped ...",
+        name
+      ));
+      EXIT_COMPILE_ERROR
+    })
+  }
+
+  /// Verify and run, returns the IO back
+  fn interpret_with<IO: VmIo>(&self, program: Program, io: IO) -> Result<IO, i32> {
+    let Program { code, int, source } = program;
+    let source = source.as_deref();
+    let show = |err: &RuntimeError| self.report(source, err);
+    let mut vm = match VM::try_with_io(code, io) {
+      Ok(vm) => vm.with_int_semantics(int),
+      Err(errors) => {
+        errors.iter().for_each(show);
+        self.report_stopped("Errors above occurred (during `verifying`), execution stopped ...");
+        return Err(EXIT_IO_ERROR);
+      }
+    };
+    if let Err(err) = vm.interpret() {
+      show(&err);
+      self.report_stopped("Error above occurred (during `interpreting`), execution stopped ...");
+      return Err(EXIT_RUNTIME_ERROR);
+    }
+    Ok(vm.into_io())
+  }
+
+  fn read_source(&self) -> Result<String, i32> {
+    fs::read_to_string(&self.file).map_err(|err| {
+      eprintln!("Failed to read `{}`: {}", self.file, err);
+      EXIT_IO_ERROR
+    })
+  }
+
+  fn write_output(&self, bytes: &[u8]) -> Result<(), i32> {
+    let result = match &self.output {
+      Some(output) => fs::write(output, bytes),
+      None => io::stdout().write_all(bytes),
+    };
+    result.map_err(|err| {
+      eprintln!("Failed to write the output: {}", err);
+      EXIT_IO_ERROR
+    })
+  }
+
+  fn explain(&self) -> Result<(), i32> {
+    match ErrorCode::from_str(&self.file) {
+      Ok(code) => {
+        let text = format!("{}: {}\n\n{}", code, code.summary(), code.explain());
+        self.write_output(text.as_bytes())
+      }
+      Err(err) => {
+        eprintln!("{}", err);
+        Err(EXIT_USAGE)
+      }
+    }
+  }
+
+  fn lex(&self) -> Result<(), i32> {
+    let source = self.read_source()?;
+    let mut has_error = false;
+    let mut output = String::new();
+    for token in Lexer::new(&source).with_int_width(self.int_semantics().width) {
+      match token {
+        Token::LexicalError(err) => {
+          has_error = true;
+          self.report(Some(&source), &err);
+        }
+        token => output += &format!("{:?}\n", token),
+      }
+    }
+    self.write_output(output.as_bytes())?;
+    if has_error {
+      Err(EXIT_COMPILE_ERROR)
+    } else {
+      Ok(())
+    }
+  }
+
+  fn parse_only(&self) -> Result<(), i32> {
+    let source = self.read_source()?;
+    let ast_entry = self.stage("parsing", &source, self.parse_source(&source))?;
+    self.write_output(format!("{:#?}\n", ast_entry).as_bytes())
+  }
+
+  /// Parse with `front_end`
+  fn parse_source(&self, source: &str) -> Result<Box<ProgramExpr>, Vec<CompileError>> {
+    let width = self.int_semantics().width;
+    match self.front_end {
+      FrontEnd::Handwritten => Parser::new(source).with_int_width(width).parse(),
+      FrontEnd::Pest => PestParser::new(source).with_int_width(width).parse(),
+    }
+  }
+
+  /// Parse and translate only, whatever `opt_level` is
+  fn check(&self) -> Result<(), i32> {
+    let source = self.read_source()?;
+    let ast_entry = self.stage("parsing", &source, self.parse_source(&source))?;
+    self.stage(
+      "translation/codegen",
+      &source,
+      Translator::default().translate(&ast_entry),
+    )?;
+    Ok(())
+  }
+
+  /// Compile with `opt_level`, diagnostics are printed to stderr
+  pub fn compile(&self, source: &str) -> Result<Compiled, i32> {
+    let mut ast_entry = self.stage("parsing", source, self.parse_source(source))?;
+
+    if self.opt_level >= OptLevel::O1 {
+      // check the whole program first, dead code would be removed unchecked
+      self.stage(
+        "translation/codegen",
+        source,
+        Translator::default().translate(&ast_entry),
+      )?;
+      let mut optimizer = AstOptimizer::new(ast_entry).with_int_semantics(self.int_semantics());
+      ast_entry = self.stage("optimizing", source, optimizer.optimize())?;
+      optimizer
+        .warnings
+        .iter()
+        .for_each(|warning| self.report(Some(source), warning));
+    }
+
+    let mut translator = Translator::default();
+    let mut code = self.stage(
+      "translation/codegen",
+      source,
+      translator.translate(&ast_entry),
+    )?;
+    let mut sym_table = translator.sym_table;
+
+    let mut n_removed = 0;
+    if self.opt_level >= OptLevel::O2 {
+      let mut peephole = PeepholeOptimizer::new(code);
+      code = peephole.optimize();
+      sym_table.relocate_procs(|pc| peephole.relocate(pc));
+      n_removed = peephole.n_removed;
+    }
+
+    Ok(Compiled {
+      object: ObjectFile::new(code, Some(sym_table)).with_int_semantics(self.int_semantics()),
+      n_removed,
+    })
+  }
+
+  fn emit(&self) -> Result<(), i32> {
+    let source = self.read_source()?;
+    let Compiled { object, n_removed } = self.compile(&source)?;
+    if self.opt_level >= OptLevel::O2 && self.error_format == ErrorFormat::Human {
+      eprintln!("peephole: removed {} instructions", n_removed);
+    }
+    match self.format {
+      EmitFormat::Text => self.write_output(object.code.to_asm().as_bytes()),
+      EmitFormat::Binary => self.write_output(&object.to_bytes()),
+    }
+  }
+
+  /// The integer semantics kept in `object`, `--int-width` / `--overflow`
+  /// must agree with it
+  fn int_semantics_of(&self, object: &ObjectFile) -> Result<IntSemantics, i32> {
+    let Some(int) = object.int_semantics else {
+      return Ok(self.int_semantics());
+    };
+    let conflict = match (self.int_width, self.overflow) {
+      (Some(width), _) if width != int.width => {
+        Some(("--int-width", width.to_string(), int.width.to_string()))
+      }
+      (_, Some(overflow)) if overflow != int.overflow => {
+        Some(("--overflow", overflow.to_string(), int.overflow.to_string()))
+      }
+      _ => None,
+    };
+    match conflict {
+      Some((option, given, kept)) => {
+        eprintln!(
+          "`{} {}` conflicts with `{}`, which `{}` has been compiled for",
+          option, given, kept, self.file
+        );
+        Err(EXIT_USAGE)
+      }
+      None => Ok(int),
+    }
+  }
+
+  /// Source code, textual pcode or binary object
+  fn load(&self) -> Result<Program, i32> {
+    let bytes = fs::read(&self.file).map_err(|err| {
+      eprintln!("Failed to read `{}`: {}", self.file, err);
+      EXIT_IO_ERROR
+    })?;
+
+    if bytes.starts_with(&MAGIC) {
+      return match ObjectFile::from_bytes(&bytes) {
+        Ok(object) => Ok(Program {
+          int: self.int_semantics_of(&object)?,
+          code: object.code,
+          source: None,
+        }),
+        Err(err) => {
+          match self.error_format {
+            ErrorFormat::Human => eprintln!("{}", err),
+            ErrorFormat::Json => {
+              eprintln!(
+                "{}",
+                PL0Error::from(err).to_diagnostic(&self.file).to_json()
+              )
+            }
+            ErrorFormat::Sarif => self
+              .diagnostics
+              .borrow_mut()
+              .push(PL0Error::from(err).to_diagnostic(&self.file)),
+          }
+          Err(EXIT_IO_ERROR)
+        }
+      };
+    }
+
+    let Ok(source) = String::from_utf8(bytes) else {
+      eprintln!("`{}` is neither source code nor pcode", self.file);
+      return Err(EXIT_IO_ERROR);
+    };
+    if self.file.ends_with(".pcode") {
+      let code = self.stage("assembling", &source, PCodeManager::from_asm(&source))?;
+      Ok(Program {
+        code,
+        int: self.int_semantics(),
+        source: None,
+      })
+    } else {
+      let object = self.compile(&source)?.object;
+      Ok(Program {
+        code: object.code,
+        int: self.int_semantics(),
+        source: Some(source),
+      })
+    }
+  }
+
+  fn run(&self) -> Result<(), i32> {
+    let program = self.load()?;
+
+    if self.input.is_none() && self.output.is_none() {
+      let io = match self.prompt {
+        true => StdIo::default(),
+        false => StdIo::default().with_prompt(None),
+      };
+      return self.interpret_with(program, io).map(|_| ());
+    }
+
+    let reader: Box<dyn BufRead> = match &self.input {
+      Some(input) => match File::open(input) {
+        Ok(file) => Box::new(BufReader::new(file)),
+        Err(err) => {
+          eprintln!("Failed to read `{}`: {}", input, err);
+          return Err(EXIT_IO_ERROR);
+        }
+      },
+      None => Box::new(io::stdin().lock()),
+    };
+    let writer: Box<dyn Write> = match &self.output {
+      Some(output) => match File::create(output) {
+        Ok(file) => Box::new(BufWriter::new(file)),
+        Err(err) => {
+          eprintln!("Failed to write `{}`: {}", output, err);
+          return Err(EXIT_IO_ERROR);
+        }
+      },
+      None => Box::new(io::stdout()),
+    };
+    let (_, mut writer) = self
+      .interpret_with(program, BufIo::new(reader, writer))?
+      .into_inner();
+    writer.flush().map_err(|err| {
+      eprintln!("Failed to write the output: {}", err);
+      EXIT_IO_ERROR
+    })
+  }
+}
+
+fn parse_opt_level(level: &str) -> Result<OptLevel, String> {
+  match level {
+    "0" => Ok(OptLevel::O0),
+    "1" => Ok(OptLevel::O1),
+    "2" => Ok(OptLevel::O2),
+    _ => Err(format!("unknown optimization level `{}`", level)),
+  }
+}
@@ -0,0 +1,843 @@
+use std::{fmt::Display, str::FromStr};
+
+use super::runtime_error::RuntimeErrorType;
+
+/// Stable code of each kind of diagnostic, match on it instead of the wording.
+///
+/// - `E00xx`: lexical errors
+/// - `E01xx`: syntax errors
+/// - `E02xx`: semantic errors
+/// - `E10xx`: runtime errors
+/// - `W00xx`: warnings
+///
+/// Codes are never reused or renumbered, new kinds get new codes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum ErrorCode {
+  NonAsciiChar,
+  UnexpectedChar,
+  UndefinedSign,
+  UnderscoreInIdentifier,
+  InvalidDigit,
+  UnterminatedComment,
+  IntegerOverflow,
+
+  ExpectedToken,
+  ExpectedIdentifier,
+  ExpectedInteger,
+  ExpectedStatement,
+  ExpectedFactor,
+  ExpectedLop,
+  ExpectedAop,
+  ExpectedMop,
+  InvalidProgram,
+  InvalidAssembly,
+
+  Undefined,
+  Redefined,
+  NotAVariable,
+  NotAProcedure,
+  ArgsCountMismatch,
+  NotAnRValue,
+  DivisionByConstZero,
+  NotAFunction,
+  NotAReference,
+  InvalidBounds,
+  NotAnArray,
+  AssignToLoopVar,
+
+  DivisionByZero,
+  StackOverflow,
+  StackUnderflow,
+  InvalidInput,
+  ArithmeticOverflow,
+  InvalidInstruction,
+  IoError,
+  InvalidJumpTarget,
+  InvalidLevel,
+  StackImbalance,
+  IndexOutOfBounds,
+
+  UnreachableStatement,
+}
+
+impl ErrorCode {
+  pub const ALL: [ErrorCode; 41] = [
+    Self::NonAsciiChar,
+    Self::UnexpectedChar,
+    Self::UndefinedSign,
+    Self::UnderscoreInIdentifier,
+    Self::InvalidDigit,
+    Self::UnterminatedComment,
+    Self::IntegerOverflow,
+    Self::ExpectedToken,
+    Self::ExpectedIdentifier,
+    Self::ExpectedInteger,
+    Self::ExpectedStatement,
+    Self::ExpectedFactor,
+    Self::ExpectedLop,
+    Self::ExpectedAop,
+    Self::ExpectedMop,
+    Self::InvalidProgram,
+    Self::InvalidAssembly,
+    Self::Undefined,
+    Self::Redefined,
+    Self::NotAVariable,
+    Self::NotAProcedure,
+    Self::ArgsCountMismatch,
+    Self::NotAnRValue,
+    Self::DivisionByConstZero,
+    Self::NotAFunction,
+    Self::NotAReference,
+    Self::InvalidBounds,
+    Self::NotAnArray,
+    Self::AssignToLoopVar,
+    Self::DivisionByZero,
+    Self::StackOverflow,
+    Self::StackUnderflow,
+    Self::InvalidInput,
+    Self::ArithmeticOverflow,
+    Self::InvalidInstruction,
+    Self::IoError,
+    Self::InvalidJumpTarget,
+    Self::InvalidLevel,
+    Self::StackImbalance,
+    Self::IndexOutOfBounds,
+    Self::UnreachableStatement,
+  ];
+
+  pub fn as_str(self) -> &'static str {
+    match self {
+      Self::NonAsciiChar => "E0001",
+      Self::UnexpectedChar => "E0002",
+      Self::UndefinedSign => "E0003",
+      Self::UnderscoreInIdentifier => "E0004",
+      Self::InvalidDigit => "E0005",
+      Self::UnterminatedComment => "E0006",
+      Self::IntegerOverflow => "E0007",
+      Self::ExpectedToken => "E0101",
+      Self::ExpectedIdentifier => "E0102",
+      Self::ExpectedInteger => "E0103",
+      Self::ExpectedStatement => "E0104",
+      Self::ExpectedFactor => "E0105",
+      Self::ExpectedLop => "E0106",
+      Self::ExpectedAop => "E0107",
+      Self::ExpectedMop => "E0108",
+      Self::InvalidProgram => "E0109",
+      Self::InvalidAssembly => "E0110",
+      Self::Undefined => "E0201",
+      Self::Redefined => "E0202",
+      Self::NotAVariable => "E0203",
+      Self::NotAProcedure => "E0204",
+      Self::ArgsCountMismatch => "E0205",
+      Self::NotAnRValue => "E0206",
+      Self::DivisionByConstZero => "E0207",
+      Self::NotAFunction => "E0208",
+      Self::NotAReference => "E0209",
+      Self::InvalidBounds => "E0210",
+      Self::NotAnArray => "E0211",
+      Self::AssignToLoopVar => "E0212",
+      Self::DivisionByZero => "E1001",
+      Self::StackOverflow => "E1002",
+      Self::StackUnderflow => "E1003",
+      Self::InvalidInput => "E1004",
+      Self::ArithmeticOverflow => "E1005",
+      Self::InvalidInstruction => "E1006",
+      Self::IoError => "E1007",
+      Self::InvalidJumpTarget => "E1008",
+      Self::InvalidLevel => "E1009",
+      Self::StackImbalance => "E1010",
+      Self::IndexOutOfBounds => "E1011",
+      Self::UnreachableStatement => "W0001",
+    }
+  }
+
+  /// One line description
+  pub fn summary(self) -> &'static str {
+    match self {
+      Self::NonAsciiChar => "non-ASCII character",
+      Self::UnexpectedChar => "unexpected character",
+      Self::UndefinedSign => "undefined sign",
+      Self::UnderscoreInIdentifier => "`_` in an identifier",
+      Self::InvalidDigit => "invalid digit",
+      Self::UnterminatedComment => "unterminated comment",
+      Self::IntegerOverflow => "integer literal out of range",
+      Self::ExpectedToken => "expected a specific token",
+      Self::ExpectedIdentifier => "expected an identifier",
+      Self::ExpectedInteger => "expected an integer",
+      Self::ExpectedStatement => "expected a statement",
+      Self::ExpectedFactor => "expected an identifier, an integer or `(<exp>)`",
+      Self::ExpectedLop => "expected a relational operator",
+      Self::ExpectedAop => "expected `+` or `-`",
+      Self::ExpectedMop => "expected `*` or `/`",
+      Self::InvalidProgram => "failed to parse the program",
+      Self::InvalidAssembly => "invalid textual pcode",
+      Self::Undefined => "use of an undefined identifier",
+      Self::Redefined => "identifier defined more than once in the same scope",
+      Self::NotAVariable => "assignment / `read` to something which is not a variable",
+      Self::NotAProcedure => "`call` of something which is not a procedure",
+      Self::ArgsCountMismatch => "wrong number of arguments",
+      Self::NotAnRValue => "procedure or function used as a value",
+      Self::DivisionByConstZero => "division by constant zero",
+      Self::NotAFunction => "call of something which is not a function in an expression",
+      Self::NotAReference => "argument passed by reference is not a variable",
+      Self::InvalidBounds => "invalid bounds of an array",
+      Self::NotAnArray => "index of something which is not an array",
+      Self::AssignToLoopVar => "assignment to the control variable of a `for` loop",
+      Self::DivisionByZero => "division by zero",
+      Self::StackOverflow => "stack overflow",
+      Self::StackUnderflow => "stack underflow",
+      Self::InvalidInput => "invalid input of `read`",
+      Self::ArithmeticOverflow => "arithmetic overflow",
+      Self::InvalidInstruction => "invalid instruction",
+      Self::IoError => "I/O error",
+      Self::InvalidJumpTarget => "jump out of the pcode list",
+      Self::InvalidLevel => "invalid static level",
+      Self::StackImbalance => "unbalanced data stack",
+      Self::IndexOutOfBounds => "array index out of bounds",
+      Self::UnreachableStatement => "unreachable statement",
+    }
+  }
+
+  /// Long-form description, with an example in PL/0
+  pub fn explain(self) -> &'static str {
+    match self {
+      Self::NonAsciiChar => {
+        "\
+Only ASCII characters are allowed in the source code (comments excluded).
+
+Erroneous example:
+
+    var 变量;
+
+Use ASCII letters and digits for identifiers instead:
+
+    var a;
+"
+      }
+      Self::UnexpectedChar => {
+        "\
+A character which could not start any token has been found.
+
+Erroneous example:
+
+    a := 1 % 2;
+
+PL/0 only has `+`, `-`, `*` and `/` as arithmetic operators.
+"
+      }
+      Self::UndefinedSign => {
+        "\
+A single `:` is not a sign of PL/0, it's only valid as a part of `:=`.
+
+Erroneous example:
+
+    a : 1;
+
+Use `:=` for assignments:
+
+    a := 1;
+"
+      }
+      Self::UnderscoreInIdentifier => {
+        "\
+Identifiers consist of letters and digits only, `_` is not supported.
+
+Erroneous example:
+
+    var my_var;
+
+Rename it:
+
+    var myVar;
+"
+      }
+      Self::InvalidDigit => {
+        "\
+An integer literal must start with a decimal digit, only decimal integers
+are supported.
+
+Example:
+
+    const a := 16;
+"
+      }
+      Self::UnterminatedComment => {
+        "\
+A block comment (`{ ... }` or `(* ... *)`) is opened, but never closed before
+the end of the file. Comments are not nested.
+
+Erroneous example:
+
+    { read the input
+    read(a);
+
+Close the comment:
+
+    { read the input }
+    read(a);
+"
+      }
+      Self::IntegerOverflow => {
+        "\
+An integer literal doesn't fit in the integer width of the target machine
+(`--int-width`, `i64` by default).
+
+Erroneous example (with `--int-width i16`):
+
+    const big := 40000;
+
+Literals are never negative, so the minimum value has to be computed, e.g.
+`-32767 - 1` for `i16`.
+"
+      }
+      Self::ExpectedToken => {
+        "\
+A specific token (e.g. `;`, `:=`, `then`, `)`) is required here, but another
+one (or the end of the file) has been found.
+
+Erroneous example:
+
+    if a = 1
+      write(a)
+
+`if` must be followed by `then`:
+
+    if a = 1 then
+      write(a)
+"
+      }
+      Self::ExpectedIdentifier => {
+        "\
+An identifier is required here, e.g. after `program`, `var` or `call`.
+
+Erroneous example:
+
+    program ;
+
+Name the program:
+
+    program main;
+"
+      }
+      Self::ExpectedInteger => {
+        "\
+An integer literal is required here, e.g. as the value of a constant.
+
+Erroneous example:
+
+    const a := b;
+
+Constants could only be defined by integers:
+
+    const a := 1;
+"
+      }
+      Self::ExpectedStatement => {
+        "\
+A statement is required here, but the token found could not start one.
+
+Erroneous example:
+
+    begin
+      1 := a
+    end
+
+Statements start with an identifier (assignment), `if`, `while`, `call`,
+`begin`, `read` or `write`:
+
+    begin
+      a := 1
+    end
+"
+      }
+      Self::ExpectedFactor => {
+        "\
+An operand of an expression (an identifier, an integer or a parenthesized
+expression) is required here.
+
+Erroneous example:
+
+    b := ;
+
+Give the assignment a value:
+
+    b := 1;
+"
+      }
+      Self::ExpectedLop => {
+        "\
+A relational operator (`=`, `<>`, `<`, `<=`, `>`, `>=`) is required between
+both sides of a condition.
+
+Erroneous example:
+
+    if a + b then write(a)
+
+Compare both sides explicitly:
+
+    if a <> b then write(a)
+"
+      }
+      Self::ExpectedAop => {
+        "\
+`+` or `-` is required here, e.g. between the terms of an expression.
+
+The parser only expects one after seeing it, so a missing operator (e.g.
+`a := b c`) is usually reported as E0101 instead. Terms are joined like:
+
+    a := b + c;
+"
+      }
+      Self::ExpectedMop => {
+        "\
+`*` or `/` is required here, e.g. between the factors of a term.
+
+The parser only expects one after seeing it, so a missing operator (e.g.
+`a := b (c)`) is usually reported as E0101 instead. Factors are joined like:
+
+    a := b * (c);
+"
+      }
+      Self::InvalidProgram => {
+        "\
+The program could not be parsed, and no more specific error could be given.
+
+A program consists of `program <id>;`, then a block (declarations followed by
+a statement):
+
+    program main;
+    var a;
+    begin
+      read(a);
+      write(a)
+    end
+"
+      }
+      Self::InvalidAssembly => {
+        "\
+Textual pcode could not be assembled, e.g. an unknown mnemonic, a missing
+operand or an undefined label.
+
+Erroneous example:
+
+    PUSH 0 1
+
+Each line is `<mnemonic> <level> <address/value>`:
+
+    LIT 0 1
+"
+      }
+      Self::Undefined => {
+        "\
+An identifier is used without being defined in the current scope or any
+enclosing one.
+
+Erroneous example:
+
+    program main;
+    begin
+      a := 1
+    end
+
+Define it first:
+
+    program main;
+    var a;
+    begin
+      a := 1
+    end
+"
+      }
+      Self::Redefined => {
+        "\
+An identifier is defined more than once in the same scope (procedure).
+
+Erroneous example:
+
+    var a, a;
+
+Every constant, variable and procedure in a scope needs a distinct name:
+
+    var a, b;
+"
+      }
+      Self::NotAVariable => {
+        "\
+Only variables could be assigned to or read into, constants and procedures
+could not.
+
+Erroneous example:
+
+    const a := 1;
+    begin
+      a := 2
+    end
+
+Use a variable instead:
+
+    var a;
+    begin
+      a := 2
+    end
+"
+      }
+      Self::NotAProcedure => {
+        "\
+Only procedures and functions could be called.
+
+Erroneous example:
+
+    var a;
+    begin
+      call a()
+    end
+"
+      }
+      Self::ArgsCountMismatch => {
+        "\
+A procedure or function is called with a different number of arguments than
+it's declared with.
+
+Erroneous example:
+
+    procedure add(a, b);
+    begin
+      write(a + b)
+    end
+    begin
+      call add(1)
+    end
+
+Pass every argument:
+
+    call add(1, 2)
+"
+      }
+      Self::NotAnRValue => {
+        "\
+A procedure is used as a value in an expression, only variables, constants and
+calls of functions could be.
+
+Erroneous example:
+
+    procedure p();
+    begin
+    end
+    begin
+      write(p + 1)
+    end
+
+A function must be called to get its value:
+
+    function f();
+    begin
+      f := 1
+    end
+    begin
+      write(f() + 1)
+    end
+"
+      }
+      Self::DivisionByConstZero => {
+        "\
+The divisor is zero after constant folding, so the division would always
+fail at runtime.
+
+Erroneous example:
+
+    const zero := 0;
+    var a;
+    begin
+      a := 1 / zero
+    end
+"
+      }
+      Self::NotAFunction => {
+        "\
+Only functions could be called in an expression, variables and constants are
+used without `()`.
+
+Erroneous example:
+
+    var a;
+    begin
+      write(a() + 1)
+    end
+
+Use the variable directly:
+
+    write(a + 1)
+"
+      }
+      Self::NotAReference => {
+        "\
+An argument passed by reference (`var` in the declaration) must be a variable,
+so that the callee could assign to it. Constants and expressions could not be.
+
+Erroneous example:
+
+    const one := 1;
+    var a;
+    procedure inc(var x);
+    begin
+      x := x + 1
+    end
+    begin
+      call inc(one);
+      call inc(a + 1)
+    end
+
+Pass a variable instead:
+
+    call inc(a)
+"
+      }
+      Self::InvalidBounds => {
+        "\
+The upper bound of an array is less than its lower bound, so it has no
+elements.
+
+Erroneous example:
+
+    var a[0], b[5 .. 1];
+
+Both bounds are inclusive, `[n]` is the same as `[0 .. n - 1]`:
+
+    var a[1], b[1 .. 5];
+"
+      }
+      Self::NotAnArray => {
+        "\
+Only arrays could be indexed with `[...]`.
+
+Erroneous example:
+
+    var a;
+    begin
+      a[1] := 2
+    end
+
+Declare it as an array:
+
+    var a[10];
+"
+      }
+      Self::AssignToLoopVar => {
+        "\
+The control variable of a `for` loop is read-only inside the loop, it
+couldn't be assigned, read into, or passed by reference.
+
+Erroneous example:
+
+    for i := 1 to 10 do
+      i := i + 1
+
+Use a `while` loop to step in other ways:
+
+    i := 1;
+    while i <= 10 do
+      i := i + 2
+"
+      }
+      Self::DivisionByZero => {
+        "\
+The divisor is zero at runtime.
+
+Erroneous example:
+
+    var a;
+    begin
+      read(a);
+      write(1 / a)
+    end
+
+Check the divisor first:
+
+    if a <> 0 then write(1 / a)
+"
+      }
+      Self::StackOverflow => {
+        "\
+The runtime stack is exhausted, usually by unbounded recursion.
+
+Erroneous example:
+
+    procedure p();
+    begin
+      call p()
+    end
+    begin
+      call p()
+    end
+"
+      }
+      Self::StackUnderflow => {
+        "\
+A value is popped from an empty stack. This is never caused by compiled
+source code, only by hand-written or corrupted pcode.
+
+Erroneous example:
+
+    OPR 0 2
+"
+      }
+      Self::InvalidInput => {
+        "\
+The input of `read` is not an integer.
+
+Example:
+
+    var a;
+    begin
+      read(a)
+    end
+
+with the input `abc`.
+"
+      }
+      Self::ArithmeticOverflow => {
+        "\
+The result of an arithmetic operation doesn't fit in the integer width of the
+target machine (`--int-width`, `i64` by default). Only reported with
+`--overflow checked` (the default), results wrap around or saturate with
+`--overflow wrapping` / `--overflow saturating` instead.
+
+Example:
+
+    var a;
+    begin
+      read(a);
+      write(a * a)
+    end
+
+with the input `9223372036854775807`.
+"
+      }
+      Self::InvalidInstruction => {
+        "\
+The pcode contains an instruction which could not be executed, e.g. `NIL`,
+an `OPR` other than `0 ~ 18` or a negative address. This is never caused by
+compiled source code, only by hand-written or corrupted pcode.
+
+Erroneous example:
+
+    OPR 0 42
+"
+      }
+      Self::IoError => {
+        "\
+Reading the input or writing the output failed, e.g. the output file is not
+writable.
+
+Example:
+
+    begin
+      write(1)
+    end
+
+run with `pl_0 run -o /dev/full`.
+"
+      }
+      Self::InvalidJumpTarget => {
+        "\
+A `JMP` / `JPC` / `CAL` jumps out of the pcode list, or execution falls off
+the end of it. This is never caused by compiled source code, only by
+hand-written or corrupted pcode.
+
+Erroneous example:
+
+    INT 0 3
+    JMP 0 100
+"
+      }
+      Self::InvalidLevel => {
+        "\
+A `LOD` / `STO` / `RED` / `CAL` / `LDA` / `LDX` / `STX` goes up further than the static chain, or a
+pcode is reached from different static levels. This is never caused by
+compiled source code, only by hand-written or corrupted pcode.
+
+Erroneous example:
+
+    INT 0 3
+    LOD 1 3
+"
+      }
+      Self::StackImbalance => {
+        "\
+The data stack doesn't have the same depth on every path reaching a pcode,
+or a pcode pops more values than pushed. This is never caused by compiled
+source code, only by hand-written or corrupted pcode.
+
+Erroneous example:
+
+    INT 0 3
+    OPR 0 2
+"
+      }
+      Self::IndexOutOfBounds => {
+        "\
+The index of an array is out of its bounds at runtime.
+
+Erroneous example:
+
+    var a[1 .. 10], i;
+    begin
+      i := 11;
+      a[i] := 1
+    end
+
+Check the index first:
+
+    if i <= 10 then a[i] := 1
+"
+      }
+      Self::UnreachableStatement => {
+        "\
+A statement could never be executed, e.g. it's in a branch whose condition is
+always false, or after a loop which never ends. It's removed by the optimizer.
+
+Example:
+
+    if 1 = 2 then
+      write(1)
+"
+      }
+    }
+  }
+}
+
+impl Display for ErrorCode {
+  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    write!(f, "{}", self.as_str())
+  }
+}
+
+impl FromStr for ErrorCode {
+  type Err = String;
+
+  /// Case-insensitive, e.g. `E0202` / `e0202`
+  fn from_str(s: &str) -> Result<Self, Self::Err> {
+    Self::ALL
+      .into_iter()
+      .find(|code| code.as_str().eq_ignore_ascii_case(s))
+      .ok_or_else(|| format!("`{}` is not a valid error code", s))
+  }
+}
+
+impl RuntimeErrorType {
+  pub fn code(self) -> ErrorCode {
+    match self {
+      Self::DivisionByZero => ErrorCode::DivisionByZero,
+      Self::StackOverflow => ErrorCode::StackOverflow,
+      Self::StackUnderflow => ErrorCode::StackUnderflow,
+      Self::InvalidInput => ErrorCode::InvalidInput,
+      Self::ArithmeticOverflow => ErrorCode::ArithmeticOverflow,
+      Self::InvalidInstruction => ErrorCode::InvalidInstruction,
+      Self::IoError => ErrorCode::IoError,
+      Self::InvalidJumpTarget => ErrorCode::InvalidJumpTarget,
+      Self::InvalidLevel => ErrorCode::InvalidLevel,
+      Self::StackImbalance => ErrorCode::StackImbalance,
+      Self::IndexOutOfBounds => ErrorCode::IndexOutOfBounds,
+    }
+  }
+}
@@ -1,12 +1,15 @@
+use serde::Serialize;
 use std::fmt::Display;
 
-use super::traits::ErrorTrait;
+use super::{code::ErrorCode, traits::ErrorTrait};
+use crate::ast::Span;
 
 #[derive(Debug, Clone, Copy, PartialEq, PartialOrd, Eq, Ord, Hash)]
 pub enum CompileErrorType {
   LexicalError,
   SyntaxError,
   SemanticError,
+  Warning,
 }
 
 impl Display for CompileErrorType {
@@ -15,17 +18,37 @@ impl Display for CompileErrorType {
       Self::LexicalError => "LexicalError",
       Self::SyntaxError => "SyntaxError",
       Self::SemanticError => "SemanticError",
+      Self::Warning => "Warning",
     };
     write!(f, "{}", error_type)
   }
 }
 
+/// Secondary location of a diagnostic, e.g. `first defined here`
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize)]
+pub struct Label {
+  pub line: usize,
+  pub col: usize,
+  pub text: String,
+}
+
+/// - span: the whole range of the error (`Span::default()` if only `line`
+///   and `col` are known)
+/// - code: stable code of the kind of diagnostic (e.g. `E0202`)
+/// - labels: secondary locations, rendered under the source line they point to
+/// - notes: extra information, e.g. why it's an error
+/// - help: how to fix it, e.g. `did you mean ':='?`
 #[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
 pub struct CompileError {
   pub line: usize,
   pub col: usize,
+  pub span: Span,
   pub info: String,
   pub error_type: CompileErrorType,
+  pub code: Option<ErrorCode>,
+  pub labels: Vec<Label>,
+  pub notes: Vec<String>,
+  pub help: Option<String>,
 }
 
 impl CompileError {
@@ -43,7 +66,12 @@ impl CompileError {
     Self {
       line: 1,
       col: 0,
+      span: Span::default(),
       info: String::new(),
+      code: None,
+      labels: vec![],
+      notes: vec![],
+      help: None,
       error_type: CompileErrorType::LexicalError,
     }
   }
@@ -52,7 +80,12 @@ impl CompileError {
     Self {
       line: 1,
       col: 0,
+      span: Span::default(),
       info: String::new(),
+      code: None,
+      labels: vec![],
+      notes: vec![],
+      help: None,
       error_type: CompileErrorType::SyntaxError,
     }
   }
@@ -61,10 +94,29 @@ impl CompileError {
     Self {
       line: 1,
       col: 0,
+      span: Span::default(),
       info: String::new(),
+      code: None,
+      labels: vec![],
+      notes: vec![],
+      help: None,
       error_type: CompileErrorType::SemanticError,
     }
   }
+
+  pub fn warning_template() -> Self {
+    Self {
+      line: 1,
+      col: 0,
+      span: Span::default(),
+      info: String::new(),
+      code: None,
+      labels: vec![],
+      notes: vec![],
+      help: None,
+      error_type: CompileErrorType::Warning,
+    }
+  }
 }
 
 impl Display for CompileError {
@@ -89,4 +141,28 @@ impl ErrorTrait for CompileError {
   fn col(&self) -> usize {
     self.col
   }
+
+  fn span(&self) -> Span {
+    self.span
+  }
+
+  fn code(&self) -> Option<ErrorCode> {
+    self.code
+  }
+
+  fn is_warning(&self) -> bool {
+    self.error_type == CompileErrorType::Warning
+  }
+
+  fn labels(&self) -> Vec<Label> {
+    self.labels.to_owned()
+  }
+
+  fn notes(&self) -> Vec<String> {
+    self.notes.to_owned()
+  }
+
+  fn help(&self) -> Option<String> {
+    self.help.to_owned()
+  }
 }
@@ -0,0 +1,209 @@
+use serde::Serialize;
+use serde_json::{json, Value};
+
+use std::str::FromStr;
+
+use crate::ast::Location;
+
+use super::{code::ErrorCode, compile_error::Label, traits::ErrorTrait, PL0Error};
+
+pub const SARIF_SCHEMA: &str = "https://json.schemastore.org/sarif-2.1.0.json";
+pub const SARIF_VERSION: &str = "2.1.0";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Severity {
+  Error,
+  Warning,
+}
+
+/// Machine-readable form of a diagnostic (`CompileError` / `RuntimeError` /
+/// `ObjectError`), one JSON object each:
+///
+/// ```json
+/// {
+///   "file": "multi_def.pas",
+///   "line": 3, "column": 8, "end_line": 3, "end_column": 8,
+///   "severity": "error",
+///   "code": "E0202",
+///   "error_type": "SemanticError",
+///   "message": "`a` is defined before",
+///   "labels": [{ "line": 3, "col": 5, "text": "first defined here" }],
+///   "notes": [],
+///   "help": null
+/// }
+/// ```
+///
+/// Lines / columns are 1-based (`end_*` included), `0` if unknown (e.g.
+/// `ObjectError`).
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Diagnostic {
+  pub file: String,
+  pub line: usize,
+  pub column: usize,
+  pub end_line: usize,
+  pub end_column: usize,
+  pub severity: Severity,
+  pub code: Option<String>,
+  pub error_type: String,
+  pub message: String,
+  pub labels: Vec<Label>,
+  pub notes: Vec<String>,
+  pub help: Option<String>,
+}
+
+impl Diagnostic {
+  pub fn new(err: &impl ErrorTrait, file: &str) -> Self {
+    let span = err.span();
+    let end = if span.is_unknown() {
+      Location(err.line(), err.col())
+    } else {
+      span.end_location
+    };
+    Self {
+      file: file.to_string(),
+      line: err.line(),
+      column: err.col(),
+      end_line: end.0,
+      end_column: end.1,
+      severity: if err.is_warning() {
+        Severity::Warning
+      } else {
+        Severity::Error
+      },
+      code: err.code().map(|code| code.to_string()),
+      error_type: err.error_type(),
+      message: err.info(),
+      labels: err.labels(),
+      notes: err.notes(),
+      help: err.help(),
+    }
+  }
+
+  /// One line of JSON
+  pub fn to_json(&self) -> String {
+    serde_json::to_string(self).unwrap()
+  }
+}
+
+impl PL0Error {
+  pub fn to_diagnostic(&self, file: &str) -> Diagnostic {
+    match self {
+      Self::CompileError(err) => Diagnostic::new(err, file),
+      Self::RuntimeError(err) => Diagnostic::new(err, file),
+      Self::ObjectError(err) => Diagnostic {
+        file: file.to_string(),
+        line: 0,
+        column: 0,
+        end_line: 0,
+        end_column: 0,
+        severity: Severity::Error,
+        code: None,
+        error_type: err.error_type.to_string(),
+        message: format!("{} (offset: {})", err.info, err.offset),
+        labels: vec![],
+        notes: vec![],
+        help: None,
+      },
+    }
+  }
+}
+
+/// A SARIF 2.1 log with a single run of `pl_0`
+pub fn to_sarif(diagnostics: &[Diagnostic]) -> String {
+  let mut rule_ids = diagnostics.iter().map(rule_id).collect::<Vec<_>>();
+  rule_ids.sort_unstable();
+  rule_ids.dedup();
+
+  let results = diagnostics
+    .iter()
+    .map(|diagnostic| {
+      let mut message = diagnostic.message.to_owned();
+      for note in &diagnostic.notes {
+        message += &format!("\nnote: {}", note);
+      }
+      if let Some(help) = &diagnostic.help {
+        message += &format!("\nhelp: {}", help);
+      }
+      let related_locations = diagnostic
+        .labels
+        .iter()
+        .map(|label| {
+          let mut location = sarif_location(
+            &diagnostic.file,
+            label.line,
+            label.col,
+            label.line,
+            label.col,
+          );
+          location["message"] = json!({ "text": label.text });
+          location
+        })
+        .collect::<Vec<_>>();
+      json!({
+        "ruleId": rule_id(diagnostic),
+        "level": diagnostic.severity,
+        "message": { "text": message },
+        "locations": [sarif_location(
+          &diagnostic.file,
+          diagnostic.line,
+          diagnostic.column,
+          diagnostic.end_line,
+          diagnostic.end_column,
+        )],
+        "relatedLocations": related_locations,
+      })
+    })
+    .collect::<Vec<_>>();
+
+  let log = json!({
+    "$schema": SARIF_SCHEMA,
+    "version": SARIF_VERSION,
+    "runs": [{
+      "tool": {
+        "driver": {
+          "name": env!("CARGO_PKG_NAME"),
+          "version": env!("CARGO_PKG_VERSION"),
+          "informationUri": env!("CARGO_PKG_HOMEPAGE"),
+          "rules": rule_ids.iter().map(|id| sarif_rule(id)).collect::<Vec<_>>(),
+        }
+      },
+      "results": results,
+    }]
+  });
+  serde_json::to_string_pretty(&log).unwrap()
+}
+
+/// Error code if any, otherwise the error type
+fn rule_id(diagnostic: &Diagnostic) -> String {
+  diagnostic
+    .code
+    .to_owned()
+    .unwrap_or_else(|| diagnostic.error_type.to_owned())
+}
+
+/// Descriptions are given if `id` is an error code
+fn sarif_rule(id: &str) -> Value {
+  match ErrorCode::from_str(id) {
+    Ok(code) => json!({
+      "id": id,
+      "shortDescription": { "text": code.summary() },
+      "fullDescription": { "text": code.explain() },
+    }),
+    Err(_) => json!({ "id": id }),
+  }
+}
+
+/// `region` is omitted if the location is unknown (SARIF lines are 1-based)
+fn sarif_location(file: &str, line: usize, col: usize, end_line: usize, end_col: usize) -> Value {
+  let mut physical_location = json!({ "artifactLocation": { "uri": file } });
+  if line > 0 {
+    physical_location["region"] = json!({
+      "startLine": line,
+      "startColumn": col.max(1),
+      "endLine": end_line.max(line),
+      "endColumn": end_col.max(col).max(1) + 1,
+    });
+  }
+  json!({ "physicalLocation": physical_location })
+}
@@ -1,13 +1,24 @@
-use crate::{ast::Location, lexer::Lexer};
+use crate::{
+  ast::{Location, Span},
+  lexer::Lexer,
+};
 
-use super::compile_error::{CompileError, CompileErrorType};
+use super::{
+  code::ErrorCode,
+  compile_error::{CompileError, CompileErrorType, Label},
+};
 
 #[derive(Debug, Clone, Default)]
 pub struct CompileErrorBuilder {
   line: Option<usize>,
   col: Option<usize>,
+  span: Option<Span>,
   info: Option<String>,
   error_type: Option<CompileErrorType>,
+  code: Option<ErrorCode>,
+  labels: Vec<Label>,
+  notes: Vec<String>,
+  help: Option<String>,
 }
 
 impl From<Location> for CompileErrorBuilder {
@@ -18,6 +29,12 @@ impl From<Location> for CompileErrorBuilder {
   }
 }
 
+impl From<Span> for CompileErrorBuilder {
+  fn from(value: Span) -> Self {
+    CompileErrorBuilder::semantic_error_template().with_span(value)
+  }
+}
+
 impl CompileErrorBuilder {
   pub fn lexical_error_template() -> Self {
     Self::template(CompileErrorType::LexicalError)
@@ -31,12 +48,17 @@ impl CompileErrorBuilder {
     Self::template(CompileErrorType::SemanticError)
   }
 
+  pub fn warning_template() -> Self {
+    Self::template(CompileErrorType::Warning)
+  }
+
   pub fn template(error_type: CompileErrorType) -> Self {
     Self {
       line: Some(1),
       col: Some(0),
       info: Some(String::new()),
       error_type: Some(error_type),
+      ..Default::default()
     }
   }
 }
@@ -56,12 +78,19 @@ impl CompileErrorBuilder {
     self
   }
 
-  pub fn with_lexer_ref(mut self, lexer: &Lexer) -> Self {
-    self.line = Some(lexer.line_num);
-    self.col = Some(lexer.col_num);
+  /// Also sets `line` and `col` to the start of `span`
+  pub fn with_span(mut self, span: Span) -> Self {
+    self.line = Some(span.location.0);
+    self.col = Some(span.location.1);
+    self.span = Some(span);
     self
   }
 
+  /// The peeked token of `lexer` if any, otherwise its current position
+  pub fn with_lexer_ref(self, lexer: &Lexer) -> Self {
+    self.with_span(lexer.error_span())
+  }
+
   pub fn with_info(mut self, info: String) -> Self {
     self.info = Some(info);
     self
@@ -72,12 +101,42 @@ impl CompileErrorBuilder {
     self
   }
 
+  pub fn with_code(mut self, code: ErrorCode) -> Self {
+    self.code = Some(code);
+    self
+  }
+
+  /// Secondary location, e.g. `first defined here`
+  pub fn with_label(mut self, location: Location, text: String) -> Self {
+    self.labels.push(Label {
+      line: location.0,
+      col: location.1,
+      text,
+    });
+    self
+  }
+
+  pub fn with_note(mut self, note: String) -> Self {
+    self.notes.push(note);
+    self
+  }
+
+  pub fn with_help(mut self, help: String) -> Self {
+    self.help = Some(help);
+    self
+  }
+
   pub fn build(self) -> CompileError {
     CompileError {
       line: self.line.unwrap_or(1),
       col: self.col.unwrap_or(0),
+      span: self.span.unwrap_or_default(),
       info: self.info.unwrap_or_default(),
       error_type: self.error_type.unwrap_or(CompileErrorType::LexicalError),
+      code: self.code,
+      labels: self.labels,
+      notes: self.notes,
+      help: self.help,
     }
   }
 }
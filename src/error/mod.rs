@@ -1,15 +1,20 @@
+pub mod code;
 pub mod compile_error;
+pub mod diagnostic;
 pub mod error_builder;
+pub mod object_error;
+pub mod render;
 pub mod runtime_error;
 pub mod traits;
 
-use self::{compile_error::CompileError, runtime_error::RuntimeError};
+use self::{compile_error::CompileError, object_error::ObjectError, runtime_error::RuntimeError};
 use std::{error::Error, fmt::Display};
 
 #[derive(Debug, Clone)]
 pub enum PL0Error {
   CompileError(CompileError),
   RuntimeError(RuntimeError),
+  ObjectError(ObjectError),
 }
 
 impl From<CompileError> for PL0Error {
@@ -24,11 +29,18 @@ impl From<RuntimeError> for PL0Error {
   }
 }
 
+impl From<ObjectError> for PL0Error {
+  fn from(err: ObjectError) -> Self {
+    Self::ObjectError(err)
+  }
+}
+
 impl Display for PL0Error {
   fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
     match self {
       Self::CompileError(e) => write!(f, "CompileError.{}", e),
       Self::RuntimeError(e) => write!(f, "RuntimeError.{}", e),
+      Self::ObjectError(e) => write!(f, "ObjectError.{}", e),
     }
   }
 }
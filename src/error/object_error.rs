@@ -0,0 +1,48 @@
+use std::fmt::Display;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum ObjectErrorType {
+  BadMagic,
+  UnsupportedVersion,
+  Truncated,
+  Malformed,
+  IoError,
+}
+
+impl Display for ObjectErrorType {
+  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    write!(f, "{:?}", self)
+  }
+}
+
+/// - offset: byte offset (in the object file) where the error is found
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct ObjectError {
+  pub offset: usize,
+  pub info: String,
+  pub error_type: ObjectErrorType,
+}
+
+impl ObjectError {
+  pub fn new(error_type: ObjectErrorType, offset: usize, info: String) -> Self {
+    Self {
+      offset,
+      info,
+      error_type,
+    }
+  }
+
+  pub fn show(&self) {
+    println!("{}", self)
+  }
+}
+
+impl Display for ObjectError {
+  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    write!(
+      f,
+      "{}{{ Offset: {} }}\n  | ~~ {}\n",
+      self.error_type, self.offset, self.info
+    )
+  }
+}
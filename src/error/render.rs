@@ -0,0 +1,176 @@
+use std::{
+  env,
+  io::{self, IsTerminal},
+};
+
+use super::traits::ErrorTrait;
+
+const RED: &str = "\x1b[1;31m";
+const YELLOW: &str = "\x1b[1;33m";
+const BLUE: &str = "\x1b[1;34m";
+const BOLD: &str = "\x1b[1m";
+const RESET: &str = "\x1b[0m";
+
+/// - Auto: colored if stderr is a terminal and `NO_COLOR` is not set
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ColorChoice {
+  #[default]
+  Auto,
+  Always,
+  Never,
+}
+
+impl ColorChoice {
+  pub fn should_color(self) -> bool {
+    match self {
+      Self::Auto => env::var_os("NO_COLOR").is_none() && io::stderr().is_terminal(),
+      Self::Always => true,
+      Self::Never => false,
+    }
+  }
+}
+
+/// Render diagnostics with the source line and a caret, like:
+///
+/// ```text
+/// SemanticError[E0202]: `a` is defined before
+///  --> multi_def.pas:3:5
+///   |
+/// 2 | var a;
+///   |     - first defined here
+/// 3 | var a;
+///   |     ^
+///   = help: ...
+/// ```
+///
+/// The primary mark covers the whole span if it's on a single line, e.g.
+///
+/// ```text
+/// 5 |   x := count + 1
+///   |        ^^^^^
+/// ```
+///
+/// Diagnostics without a location (line `0`) only have the header and notes.
+#[derive(Debug, Clone)]
+pub struct Renderer<'a> {
+  source: &'a str,
+  file_name: &'a str,
+  color: bool,
+}
+
+impl<'a> Renderer<'a> {
+  pub fn new(source: &'a str, file_name: &'a str) -> Self {
+    Self {
+      source,
+      file_name,
+      color: false,
+    }
+  }
+
+  pub fn with_color(mut self, color: ColorChoice) -> Self {
+    self.color = color.should_color();
+    self
+  }
+
+  pub fn render(&self, err: &impl ErrorTrait) -> String {
+    let level_color = if err.is_warning() { YELLOW } else { RED };
+    let header = match err.code() {
+      Some(code) => format!("{}[{}]", err.error_type(), code),
+      None => err.error_type(),
+    };
+    let mut output = format!(
+      "{}: {}\n",
+      self.paint(level_color, &header),
+      self.paint(BOLD, &err.info())
+    );
+
+    // the primary mark covers the span if it's on a single line
+    let span = err.span();
+    let width = if !span.is_unknown() && span.end_location.0 == err.line() {
+      (span.end_location.1 + 1).saturating_sub(err.col()).max(1)
+    } else {
+      1
+    };
+    // (line, col, width, text, is_primary), in the order of lines
+    let mut marks = vec![(err.line(), err.col(), width, String::new(), true)];
+    for label in err.labels() {
+      marks.push((label.line, label.col, 1, label.text, false));
+    }
+    marks.retain(|&(line, ..)| line > 0);
+    marks.sort_by_key(|&(line, col, ..)| (line, col));
+
+    let gutter_width = marks
+      .iter()
+      .map(|(line, ..)| line.to_string().len())
+      .max()
+      .unwrap_or(0);
+    let gutter = " ".repeat(gutter_width);
+
+    if err.line() > 0 {
+      output += &format!(
+        "{}{} {}:{}:{}\n",
+        gutter,
+        self.paint(BLUE, "-->"),
+        self.file_name,
+        err.line(),
+        err.col()
+      );
+    }
+    if !marks.is_empty() {
+      output += &format!("{} {}\n", gutter, self.paint(BLUE, "|"));
+    }
+    let mut last_line = None;
+    for (line, col, width, text, is_primary) in marks {
+      let src_line = self.source.lines().nth(line - 1).unwrap_or_default();
+      if last_line.is_some_and(|last_line| line > last_line + 1) {
+        output += &format!("{}\n", self.paint(BLUE, "..."));
+      }
+      if last_line != Some(line) {
+        output += &format!(
+          "{} {}\n",
+          self.paint(BLUE, &format!("{:>1$} |", line, gutter_width)),
+          src_line.trim_end()
+        );
+        last_line = Some(line);
+      }
+      // keep tabs, so that the mark is aligned with the source line
+      let indent = src_line
+        .chars()
+        .chain(std::iter::repeat(' '))
+        .take(col.saturating_sub(1))
+        .map(|c| if c == '\t' { '\t' } else { ' ' })
+        .collect::<String>();
+      let (mark, color) = if is_primary {
+        ("^", level_color)
+      } else {
+        ("-", BLUE)
+      };
+      let pointer = format!("{} {}", mark.repeat(width), text);
+      output += &format!(
+        "{} {} {}{}\n",
+        gutter,
+        self.paint(BLUE, "|"),
+        indent,
+        self.paint(color, pointer.trim_end())
+      );
+    }
+
+    for note in err.notes() {
+      output += &format!("{} {} note: {}\n", gutter, self.paint(BLUE, "="), note);
+    }
+    if let Some(help) = err.help() {
+      output += &format!("{} {} help: {}\n", gutter, self.paint(BLUE, "="), help);
+    }
+    output
+  }
+}
+
+impl Renderer<'_> {
+  fn paint(&self, color: &str, text: &str) -> String {
+    if self.color {
+      format!("{}{}{}", color, text, RESET)
+    } else {
+      text.to_string()
+    }
+  }
+}
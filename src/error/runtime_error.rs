@@ -1,9 +1,23 @@
 use std::fmt::Display;
 
-use super::traits::ErrorTrait;
+use crate::pcode::line_table::LineInfo;
 
-#[derive(Debug, Clone, Copy)]
-pub enum RuntimeErrorType {}
+use super::{code::ErrorCode, traits::ErrorTrait};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum RuntimeErrorType {
+  DivisionByZero,
+  StackOverflow,
+  StackUnderflow,
+  InvalidInput,
+  ArithmeticOverflow,
+  InvalidInstruction,
+  IoError,
+  InvalidJumpTarget,
+  InvalidLevel,
+  StackImbalance,
+  IndexOutOfBounds,
+}
 
 impl Display for RuntimeErrorType {
   fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
@@ -11,14 +25,45 @@ impl Display for RuntimeErrorType {
   }
 }
 
-#[derive(Debug, Clone)]
+/// - pc: index of the faulting pcode
+/// - line / col: source location the faulting pcode maps back to
+///   (`0` if unknown)
+/// - proc_name: procedure the faulting pcode belongs to (empty if unknown)
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
 pub struct RuntimeError {
+  pub pc: usize,
+  pub proc_name: String,
   pub line: usize,
   pub col: usize,
   pub info: String,
   pub error_type: RuntimeErrorType,
 }
 
+impl RuntimeError {
+  pub fn new(
+    error_type: RuntimeErrorType,
+    pc: usize,
+    line_info: Option<LineInfo>,
+    info: String,
+  ) -> Self {
+    let (location, proc_name) = line_info
+      .map(|line_info| (line_info.location, line_info.proc_name))
+      .unwrap_or_default();
+    Self {
+      pc,
+      proc_name: proc_name.to_string(),
+      line: location.0,
+      col: location.1,
+      info,
+      error_type,
+    }
+  }
+
+  pub fn show(&self) {
+    println!("{}", self)
+  }
+}
+
 impl Display for RuntimeError {
   fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
     write!(f, "{}", self.as_string())
@@ -26,6 +71,10 @@ impl Display for RuntimeError {
 }
 
 impl ErrorTrait for RuntimeError {
+  fn code(&self) -> Option<ErrorCode> {
+    Some(self.error_type.code())
+  }
+
   fn line(&self) -> usize {
     self.line
   }
@@ -1,15 +1,44 @@
+use super::{code::ErrorCode, compile_error::Label};
+use crate::ast::Span;
+
 pub trait ErrorTrait {
   fn line(&self) -> usize;
   fn col(&self) -> usize;
   fn info(&self) -> String;
   fn error_type(&self) -> String;
+  /// Unknown by default, `line` and `col` are used instead
+  fn span(&self) -> Span {
+    Span::default()
+  }
+  fn code(&self) -> Option<ErrorCode> {
+    None
+  }
+  fn is_warning(&self) -> bool {
+    false
+  }
+  fn labels(&self) -> Vec<Label> {
+    vec![]
+  }
+  fn notes(&self) -> Vec<String> {
+    vec![]
+  }
+  fn help(&self) -> Option<String> {
+    None
+  }
   fn as_string(&self) -> String {
-    format!(
+    let mut string = format!(
       "{}{{ Line: {}, Col: {} }}\n  | ~~ {}\n",
       self.error_type(),
       self.line(),
       self.col(),
       self.info()
-    )
+    );
+    for note in self.notes() {
+      string += &format!("  | = note: {}\n", note);
+    }
+    if let Some(help) = self.help() {
+      string += &format!("  | = help: {}\n", help);
+    }
+    string
   }
 }
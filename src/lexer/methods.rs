@@ -9,9 +9,15 @@ static KEYWORDS: Lazy<HashMap<&str, Token>> = Lazy::new(|| {
     ("else", Token::Else),
     ("while", Token::While),
     ("do", Token::Do),
+    ("repeat", Token::Repeat),
+    ("until", Token::Until),
+    ("for", Token::For),
+    ("to", Token::To),
+    ("downto", Token::Downto),
     ("const", Token::Const),
     ("var", Token::Var),
     ("procedure", Token::Procedure),
+    ("function", Token::Function),
     ("program", Token::Program),
     ("begin", Token::Begin),
     ("end", Token::End),
@@ -19,6 +25,9 @@ static KEYWORDS: Lazy<HashMap<&str, Token>> = Lazy::new(|| {
     ("read", Token::Read),
     ("write", Token::Write),
     ("odd", Token::Odd),
+    ("and", Token::And),
+    ("or", Token::Or),
+    ("not", Token::Not),
   ]
   .into_iter()
   .collect()
@@ -28,12 +37,10 @@ impl<'a> Lexer<'a> {
   pub(super) fn lexing_identifier(&mut self, first: char) -> Option<Token> {
     let mut identifier = format!("{first}");
     loop {
-      let c = self.peek_char();
-      match c.is_err() {
-        true => return c.unwrap_err().into(),
-        false => (),
-      }
-      let c = c.unwrap();
+      let c = match self.peek_char() {
+        Ok(c) => c,
+        Err(err_token) => return Some(err_token),
+      };
       if c.is_alphabetic() || c.is_ascii_digit() {
         self.next_char();
         identifier.push(c);
@@ -41,6 +48,7 @@ impl<'a> Lexer<'a> {
         return Some(Token::LexicalError(
           CompileErrorBuilder::lexical_error_template()
             .with_lexer_ref(self)
+            .with_code(ErrorCode::UnderscoreInIdentifier)
             .with_info("'_' is not supported for identifier declaration".to_string())
             .build(),
         ));
@@ -61,25 +69,89 @@ impl<'a> Lexer<'a> {
       return Some(Token::LexicalError(
         CompileErrorBuilder::lexical_error_template()
           .with_lexer_ref(self)
+          .with_code(ErrorCode::InvalidDigit)
           .with_info(format!("'{first}' is not a digit"))
           .build(),
       ));
     }
-    let mut scanned = first.to_digit(10).unwrap() as i64;
+    // `None` once it's out of range, the rest digits are still consumed
+    let mut scanned = Some(first.to_digit(10).unwrap() as i64);
     loop {
-      let c = self.peek_char();
-      match c.is_err() {
-        true => return c.unwrap_err().into(),
-        false => (),
-      }
-      let c = c.unwrap();
+      let c = match self.peek_char() {
+        Ok(c) => c,
+        Err(err_token) => return Some(err_token),
+      };
       if c.is_ascii_digit() {
         self.next_char();
-        scanned = scanned * 10 + c.to_digit(10).unwrap() as i64;
+        scanned = scanned
+          .and_then(|scanned| scanned.checked_mul(10))
+          .and_then(|scanned| scanned.checked_add(c.to_digit(10).unwrap() as i64))
+          .filter(|&scanned| self.int_width.contains(scanned));
       } else {
         break;
       }
     }
-    Some(Token::Integer(scanned))
+    match scanned {
+      Some(scanned) => Some(Token::Integer(scanned)),
+      None => Some(Token::LexicalError(
+        CompileErrorBuilder::lexical_error_template()
+          .with_span(self.token_span())
+          .with_code(ErrorCode::IntegerOverflow)
+          .with_info(format!(
+            "integer literal is out of range for `{}`",
+            self.int_width
+          ))
+          .with_help(format!(
+            "the maximum value of `{}` is {}",
+            self.int_width,
+            self.int_width.max()
+          ))
+          .build(),
+      )),
+    }
+  }
+}
+
+impl<'a> Lexer<'a> {
+  /// `{ ... }` / `(* ... *)`, called right after the first char of the
+  /// opening, which is where an unterminated comment is reported.
+  ///
+  /// Comments are not nested, and could contain anything (non-ASCII included).
+  pub(super) fn skipping_block_comment(&mut self, close: &str) -> Option<Token> {
+    let (open_line, open_col) = (self.line_num, self.col_num);
+    if close == "*)" {
+      self.next_char();
+    }
+    let mut prev = '\0';
+    loop {
+      let Some(c) = self.next_char() else {
+        let open = if close == "}" { "{" } else { "(*" };
+        return Some(Token::LexicalError(
+          CompileErrorBuilder::lexical_error_template()
+            .with_line(open_line)
+            .with_col(open_col)
+            .with_code(ErrorCode::UnterminatedComment)
+            .with_info(format!("unterminated comment, `{}` is never closed", open))
+            .with_help(format!("close it with `{}`", close))
+            .build(),
+        ));
+      };
+      match c {
+        c if self.ends_line(c) => self.new_line(),
+        '}' if close == "}" => break,
+        ')' if close == "*)" && prev == '*' => break,
+        _ => (),
+      }
+      prev = c;
+    }
+    self.do_next()
+  }
+
+  /// `// ...` until the end of the line
+  pub(super) fn skipping_line_comment(&mut self) -> Option<Token> {
+    while self.source.peek().is_some_and(|&c| c != '\n' && c != '\r') {
+      self.next_char();
+    }
+    self.do_next()
   }
 }
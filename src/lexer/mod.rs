@@ -1,5 +1,9 @@
 use self::token_def::Token;
-use crate::error::error_builder::CompileErrorBuilder;
+use crate::{
+  ast::{Location, Span},
+  error::{code::ErrorCode, error_builder::CompileErrorBuilder},
+  vm::arith::IntWidth,
+};
 use std::{iter::Peekable, str::Chars};
 
 pub mod methods;
@@ -13,9 +17,17 @@ pub trait LexerIterator {
 #[derive(Debug, Clone)]
 pub struct Lexer<'a> {
   source: Peekable<Chars<'a>>,
-  ahead: Option<Token>,
+  ahead: Option<(Token, Span)>,
   pub(super) line_num: usize,
   pub(super) col_num: usize,
+  /// byte offset of the next char
+  offset: usize,
+  /// (byte offset, location) of the first char of the token being lexed
+  token_start: (usize, Location),
+  /// span of the last token taken out
+  span: Span,
+  /// integer literals out of its range are lexical errors
+  int_width: IntWidth,
 }
 
 impl<'a> std::convert::AsRef<Lexer<'a>> for Lexer<'a> {
@@ -28,11 +40,7 @@ impl<'a> Iterator for Lexer<'a> {
   type Item = Token;
 
   fn next(&mut self) -> Option<Self::Item> {
-    if self.ahead.is_none() {
-      self.do_next()
-    } else {
-      self.ahead.take()
-    }
+    self.next_spanned().map(|(token, _)| token)
   }
 }
 
@@ -41,12 +49,73 @@ impl<'a> LexerIterator for Lexer<'a> {
 
   fn peek(&mut self) -> Option<&Self::Item> {
     if self.ahead.is_none() {
-      self.ahead = self.do_next();
+      self.ahead = self.do_next_spanned();
+    }
+    self.ahead.as_ref().map(|(token, _)| token)
+  }
+}
+
+impl<'a> Lexer<'a> {
+  /// Take out the next token with its span.
+  pub fn next_spanned(&mut self) -> Option<(Token, Span)> {
+    let next = match self.ahead.take() {
+      Some(ahead) => Some(ahead),
+      None => self.do_next_spanned(),
+    };
+    if let Some((_, span)) = &next {
+      self.span = *span;
+    }
+    next
+  }
+
+  /// Span of the next token (`None` if there's no more token)
+  pub fn peek_span(&mut self) -> Option<Span> {
+    self.peek();
+    self.ahead.as_ref().map(|(_, span)| *span)
+  }
+
+  /// Span of the last token taken out (`Span::default()` if none)
+  pub fn span(&self) -> Span {
+    self.span
+  }
+
+  /// Where an error should be reported: the peeked token if any, otherwise
+  /// the current position (e.g. while lexing, or at the end of input). Right
+  /// after a line break, it's the 1st column of the new line.
+  pub fn error_span(&self) -> Span {
+    match &self.ahead {
+      Some((_, span)) => *span,
+      None => {
+        let location = Location(self.line_num, self.col_num.max(1));
+        Span {
+          start: self.offset,
+          end: self.offset,
+          location,
+          end_location: location,
+        }
+      }
+    }
+  }
+
+  fn do_next_spanned(&mut self) -> Option<(Token, Span)> {
+    let token = self.do_next()?;
+    Some((token, self.token_span()))
+  }
+
+  /// From the start of the token being lexed to the current position
+  fn token_span(&self) -> Span {
+    let (start, location) = self.token_start;
+    Span {
+      start,
+      end: self.offset,
+      location,
+      end_location: Location(self.line_num, self.col_num),
     }
-    self.ahead.as_ref()
   }
 }
 
+// `Err` is the `Token::LexicalError` to be returned by `do_next` directly
+#[allow(clippy::result_large_err)]
 impl<'a> Lexer<'a> {
   fn ascii_char_handler(&mut self, c: char) -> Result<char, Token> {
     if c.is_ascii() {
@@ -56,6 +125,7 @@ impl<'a> Lexer<'a> {
       Err(
         CompileErrorBuilder::lexical_error_template()
           .with_lexer_ref(self)
+          .with_code(ErrorCode::NonAsciiChar)
           .with_info(format!("'{}' is not an ASCII character", c))
           .build()
           .into(),
@@ -71,6 +141,7 @@ impl<'a> Lexer<'a> {
       Err(
         CompileErrorBuilder::lexical_error_template()
           .with_lexer_ref(self)
+          .with_code(ErrorCode::NonAsciiChar)
           .with_info(format!("'{}' is not an ASCII character", c))
           .build()
           .into(),
@@ -95,11 +166,22 @@ impl<'a> Lexer<'a> {
 
   fn next_char(&mut self) -> Option<char> {
     let char = self.source.next();
-    if char.is_some() {
+    if let Some(c) = char {
       self.col_num += 1;
+      self.offset += c.len_utf8();
     }
     char
   }
+
+  /// `\n`, `\r\n` or a lone `\r`, called right after `c` is taken out
+  fn ends_line(&mut self, c: char) -> bool {
+    c == '\n' || (c == '\r' && self.source.peek() != Some(&'\n'))
+  }
+
+  fn new_line(&mut self) {
+    self.line_num += 1;
+    self.col_num = 0;
+  }
 }
 
 impl<'a> Lexer<'a> {
@@ -124,24 +206,44 @@ impl<'a> Lexer<'a> {
 
   /// Take out the next token.
   fn do_next(&mut self) -> Option<Token> {
+    self.token_start = (self.offset, Location(self.line_num, self.col_num + 1));
     if let Some(c) = self.next_char() {
       match c {
         c if c.is_whitespace() => {
-          if c.is_control() {
-            self.line_num += 1;
-            self.col_num = 0;
+          if self.ends_line(c) {
+            self.new_line();
           }
           self.do_next()
         }
         '+' => Some(Token::Add),
         '-' => Some(Token::Sub),
         '*' => Some(Token::Mul),
+        '/' if self.source.peek() == Some(&'/') => self.skipping_line_comment(),
         '/' => Some(Token::Div),
+        '{' => self.skipping_block_comment("}"),
+        '(' if self.source.peek() == Some(&'*') => self.skipping_block_comment("*)"),
         '(' => Some(Token::ParL),
         ')' => Some(Token::ParR),
         '=' => Some(Token::Eq),
         ';' => Some(Token::Semicolon),
         ',' => Some(Token::Comma),
+        '[' => Some(Token::BracketL),
+        ']' => Some(Token::BracketR),
+        '.' => match self.peek_char() {
+          Ok('.') => {
+            self.next_char();
+            Some(Token::Range)
+          }
+          Ok(_) => Some(Token::LexicalError(
+            CompileErrorBuilder::lexical_error_template()
+              .with_lexer_ref(self)
+              .with_code(ErrorCode::UndefinedSign)
+              .with_info("'.' is an undefined sign".to_string())
+              .with_help("did you mean '..'?".to_string())
+              .build(),
+          )),
+          Err(err_token) => Some(err_token),
+        },
         '<' => Some(self.check_ahead(vec!['=', '>'], vec![Token::Le, Token::Ne], Token::Lt)),
         '>' => Some(self.check_ahead(vec!['='], vec![Token::Ge], Token::Gt)),
         ':' => match self.peek_char() {
@@ -153,7 +255,9 @@ impl<'a> Lexer<'a> {
             _ => Some(Token::LexicalError(
               CompileErrorBuilder::lexical_error_template()
                 .with_lexer_ref(self)
-                .with_info("':' is an undefined sign, did you mean ':='?".to_string())
+                .with_code(ErrorCode::UndefinedSign)
+                .with_info("':' is an undefined sign".to_string())
+                .with_help("did you mean ':='?".to_string())
                 .build(),
             )),
           },
@@ -164,12 +268,14 @@ impl<'a> Lexer<'a> {
         c if !c.is_ascii() => Some(Token::LexicalError(
           CompileErrorBuilder::lexical_error_template()
             .with_lexer_ref(self)
+            .with_code(ErrorCode::NonAsciiChar)
             .with_info(format!("'{}' is not an ASCII character", c))
             .build(),
         )),
         _ => Some(Token::LexicalError(
           CompileErrorBuilder::lexical_error_template()
             .with_lexer_ref(self)
+            .with_code(ErrorCode::UnexpectedChar)
             .with_info(format!("'{}' is an unexpected character", c))
             .build(),
         )),
@@ -187,8 +293,17 @@ impl<'a> Lexer<'a> {
       ahead: None,
       line_num: 1,
       col_num: 0, // MUST be zero!
+      offset: 0,
+      token_start: (0, Location(1, 1)),
+      span: Span::default(),
+      int_width: IntWidth::default(),
     }
   }
+
+  pub fn with_int_width(mut self, int_width: IntWidth) -> Self {
+    self.int_width = int_width;
+    self
+  }
 }
 
 impl<'a> Lexer<'a> {
@@ -10,9 +10,15 @@ pub enum Token {
   Else,
   While,
   Do,
+  Repeat,
+  Until,
+  For,
+  To,
+  Downto,
   Const,
   Var,
   Procedure,
+  Function,
   Program,
   Begin,
   End,
@@ -20,6 +26,9 @@ pub enum Token {
   Read,
   Write,
   Odd,
+  And,
+  Or,
+  Not,
   /* symbols */
   Add,       // +
   Sub,       // -
@@ -36,6 +45,9 @@ pub enum Token {
   ParR,      // )
   Semicolon, // ;
   Comma,     // ,
+  BracketL,  // [
+  BracketR,  // ]
+  Range,     // ..
   /* Identifier */
   Identifier(String),
   /* constant values */
@@ -90,6 +102,9 @@ impl Display for Token {
       Self::ParR => write!(f, ")"),
       Self::Semicolon => write!(f, ";"),
       Self::Comma => write!(f, ","),
+      Self::BracketL => write!(f, "["),
+      Self::BracketR => write!(f, "]"),
+      Self::Range => write!(f, ".."),
       _ => write!(f, "{:?}", self),
     }
   }
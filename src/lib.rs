@@ -1,4 +1,10 @@
+use error::PL0Error;
 use once_cell::sync::Lazy;
+use optimizer::{peephole::PeepholeOptimizer, AstOptimizer};
+use parser::Parser;
+use pcode::{object::ObjectFile, PCodeManager};
+use translator::Translator;
+use vm::arith::IntSemantics;
 
 pub mod ast;
 pub mod error;
@@ -14,3 +20,45 @@ pub mod vm;
 
 pub static SEP: Lazy<String> = Lazy::new(|| "=".repeat(70));
 pub static LINE: Lazy<String> = Lazy::new(|| "-".repeat(70));
+
+/// Result of `compile_object`
+#[derive(Debug, Clone)]
+pub struct Compiled {
+  pub object: ObjectFile,
+  /// e.g. unreachable statements
+  pub warnings: Vec<PL0Error>,
+}
+
+/// Compile the source code into pcode.
+///
+/// `lexer` -> `parser` -> `optimizer` -> `translator` -> `peephole`, all
+/// diagnostics of the first failed stage will be returned, nothing will be
+/// printed. Warnings are discarded, see `compile_object` for them.
+pub fn compile(source: &str) -> Result<PCodeManager, Vec<PL0Error>> {
+  compile_object(source).map(|compiled| compiled.object.code)
+}
+
+/// Same as `compile`, but the symbol table and the warnings are kept as well.
+pub fn compile_object(source: &str) -> Result<Compiled, Vec<PL0Error>> {
+  let into_pl0_errors = |errors: Vec<_>| errors.into_iter().map(PL0Error::from).collect::<Vec<_>>();
+
+  let ast_entry = Parser::new(source).parse().map_err(into_pl0_errors)?;
+  // check the whole program first, dead code would be removed unchecked
+  Translator::default()
+    .translate(&ast_entry)
+    .map_err(into_pl0_errors)?;
+  let mut optimizer = AstOptimizer::new(ast_entry);
+  let ast_entry = optimizer.optimize().map_err(into_pl0_errors)?;
+  let warnings = into_pl0_errors(optimizer.warnings);
+  let mut translator = Translator::default();
+  let code = translator.translate(&ast_entry).map_err(into_pl0_errors)?;
+
+  let mut peephole = PeepholeOptimizer::new(code);
+  let code = peephole.optimize();
+  let mut sym_table = translator.sym_table;
+  sym_table.relocate_procs(|pc| peephole.relocate(pc));
+  Ok(Compiled {
+    object: ObjectFile::new(code, Some(sym_table)).with_int_semantics(IntSemantics::default()),
+    warnings,
+  })
+}
@@ -1,42 +1,28 @@
-use once_cell::sync::Lazy;
-use pl_0::{optimizer::AstOptimizer, parser::Parser, translator::Translator, vm::basic::VM};
-use project_root::get_project_root;
-use std::{env::args, fs::File, io::Read};
-
-static ARGS: Lazy<Vec<String>> = Lazy::new(|| args().collect::<Vec<_>>());
-static PROJECT_ROOT: Lazy<String> =
-  Lazy::new(|| get_project_root().unwrap().to_str().unwrap().to_string());
-
-fn compile_from_file(src: &str) {
-  let mut string_buf = String::new();
-  File::open(src)
-    .unwrap()
-    .read_to_string(&mut string_buf)
-    .unwrap();
-
-  let mut parser = Parser::new(&string_buf);
-  parser.parse();
-
-  let optimizer = AstOptimizer::new(parser.take_ast_entry());
-  let ast_entry = optimizer.optimize();
-
-  let mut translator = Translator::default();
-  let code = translator.translate(&ast_entry);
-  code.show_pcode_list();
-  translator.show_sym_table();
+use cli::{Cli, EXIT_USAGE, USAGE};
+use std::{env::args, process::exit};
 
-  let mut vm = VM::new(code);
-  vm.interpret();
-}
+mod cli;
 
 fn main() {
-  if let [_, source, ..] = &ARGS[..] {
-    compile_from_file((PROJECT_ROOT.to_string() + source.as_str()).as_str());
-  } else {
-    println!("Usage: {} <source_path>", ARGS[0]);
+  let args = args().skip(1).collect::<Vec<_>>();
+  match Cli::parse(&args) {
+    Ok(cli) => exit(cli.execute()),
+    Err(err) => {
+      eprintln!("{}\n\n{}", err, USAGE);
+      exit(EXIT_USAGE);
+    }
   }
 }
 
+#[cfg(test)]
+static PROJECT_ROOT: once_cell::sync::Lazy<String> = once_cell::sync::Lazy::new(|| {
+  project_root::get_project_root()
+    .unwrap()
+    .to_str()
+    .unwrap()
+    .to_string()
+});
+
 #[cfg(test)]
 mod dbg {
   use super::*;
@@ -44,14 +30,19 @@ mod dbg {
   #[test]
   fn dbg() {
     let filename = PROJECT_ROOT.to_string() + "/examples/correct/fib.pas";
-    compile_from_file(&filename);
+    let cli = Cli::parse(&["run".to_string(), filename]).unwrap();
+    assert_eq!(cli.execute(), cli::EXIT_OK);
   }
 }
 
 #[cfg(test)]
 mod demo {
   use super::*;
-  use pl_0::lexer::Lexer;
+  use pl_0::{
+    error::runtime_error::RuntimeErrorType, lexer::Lexer, optimizer::AstOptimizer, parser::Parser,
+    translator::Translator, vm::basic::VM, vm::io::BufIo,
+  };
+  use std::{fs::File, io::Read};
 
   fn file_to_string(filename: String) -> String {
     let mut string_buf = String::new();
@@ -73,107 +64,2060 @@ mod demo {
   fn parser_demo() {
     let ctx = &file_to_string(PROJECT_ROOT.to_string() + "/examples/lexer/one_plus_two.pas");
     let mut parser = Parser::new(ctx);
-    parser.parse();
+    assert!(parser.parse().is_ok());
     parser.show_ast();
   }
 
   #[test]
-  #[should_panic]
   fn chinese_character_demo() {
     let content =
       file_to_string(PROJECT_ROOT.to_string() + "/examples/lexer/chinese_programming.pas");
-    let mut parser = Parser::new(&content);
-    parser.parse();
-    parser.show_ast();
+    assert!(Parser::new(&content).parse().is_err());
   }
 
   #[test]
-  #[should_panic]
   fn japanese_character_demo() {
     let content =
       file_to_string(PROJECT_ROOT.to_string() + "/examples/lexer/japanese_programming.pas");
-    let mut parser = Parser::new(&content);
-    parser.parse();
-    parser.show_ast();
+    assert!(Parser::new(&content).parse().is_err());
   }
 
   #[test]
-  #[should_panic]
   fn chinese_in_keyword_demo() {
     let content =
       file_to_string(PROJECT_ROOT.to_string() + "/examples/parser/chinese_in_keyword.pas");
-    let mut parser = Parser::new(&content);
-    parser.parse();
-    parser.show_ast();
+    assert!(Parser::new(&content).parse().is_err());
   }
 
   #[test]
-  #[should_panic]
   fn single_colon_demo() {
-    Parser::new(&file_to_string(
+    assert!(Parser::new(&file_to_string(
       PROJECT_ROOT.to_string() + "/examples/lexer/single_colon.pas",
     ))
     .parse()
-    .show_ast();
+    .is_err());
+  }
+
+  #[test]
+  fn comments_demo() {
+    use pl_0::error::code::ErrorCode;
+
+    assert_eq!(
+      run_with_input("/examples/lexer/comments.pas", ""),
+      Ok("1  \n2  \n".to_string())
+    );
+
+    // lines / columns are tracked across comments
+    let source = "program p;\n{ 1\n 2 }  (* 3\n *) begin // 4\n  a := 1 end";
+    let ast_entry = Parser::new(source).parse().unwrap();
+    let errors = Translator::default().translate(&ast_entry).unwrap_err();
+    assert_eq!((errors[0].line, errors[0].col), (5, 3));
+
+    // reported where it's opened
+    let content =
+      file_to_string(PROJECT_ROOT.to_string() + "/examples/lexer/unterminated_comment.pas");
+    let errors = Parser::new(&content).parse().unwrap_err();
+    assert_eq!(
+      (errors[0].line, errors[0].col, errors[0].code),
+      (4, 3, Some(ErrorCode::UnterminatedComment))
+    );
+    let errors = Parser::new("program p; begin write(1) end { ")
+      .parse()
+      .unwrap_err();
+    assert_eq!((errors[0].line, errors[0].col), (1, 31));
+  }
+
+  #[test]
+  fn bnf_demo() {
+    use pl_0::{
+      lexer::token_def::Token,
+      util::bnf::{ConflictKind, ExElemBuilder, FirstFollow, Grammar, BNF, FIRST_FOLLOW, VN},
+    };
+
+    // all conflicts are resolved by the parser: `; <proc>` belongs to the
+    // nearest `procedure` / `function`, `else` to the nearest `if`, and `(`
+    // starts an `<l-exp>` only if one is found before the matching `)`
+    let conflicts = FIRST_FOLLOW.conflicts(&BNF);
+    conflicts
+      .iter()
+      .for_each(|conflict| println!("{}", conflict));
+    let conflicts = conflicts
+      .into_iter()
+      .map(|conflict| {
+        (
+          conflict.vn,
+          conflict.kind,
+          conflict.tokens.into_iter().collect(),
+        )
+      })
+      .collect::<Vec<(_, _, Vec<_>)>>();
+    assert_eq!(
+      conflicts,
+      [
+        (
+          VN::Proc,
+          ConflictKind::Optional(0, 8),
+          vec![Token::Semicolon]
+        ),
+        (
+          VN::Proc,
+          ConflictKind::Optional(1, 8),
+          vec![Token::Semicolon]
+        ),
+        (
+          VN::Statement,
+          ConflictKind::Optional(1, 4),
+          vec![Token::Else]
+        ),
+        (
+          VN::LFactor,
+          ConflictKind::Alternatives(1, 2),
+          vec![Token::ParL]
+        ),
+      ]
+    );
+
+    assert!(VN::ALL.iter().all(|vn| !FIRST_FOLLOW.first[vn].is_empty()));
+    assert!(FIRST_FOLLOW.nullable.is_empty());
+    assert!(FIRST_FOLLOW.follow[&VN::Program].is_empty());
+    let follow_of = |vn| {
+      let mut follow = FIRST_FOLLOW.follow[&vn].iter().cloned().collect::<Vec<_>>();
+      follow.sort();
+      follow
+    };
+    assert_eq!(
+      follow_of(VN::Statement),
+      [Token::Else, Token::Until, Token::End, Token::Semicolon]
+    );
+    // `to` / `downto` is followed by the bound of `for`, which ends at `do`
+    assert_eq!(FIRST_FOLLOW.follow[&VN::Dir], FIRST_FOLLOW.first[&VN::Exp]);
+    assert!(FIRST_FOLLOW.follow[&VN::Exp].contains(&Token::Do));
+    assert_eq!(follow_of(VN::Block), [Token::Begin, Token::Semicolon]);
+    assert!(
+      FIRST_FOLLOW.token_follow[&Token::Identifier(Default::default())].contains(&Token::EqSign)
+    );
+
+    // <exp> -> <exp> <aop> <term> | <term>
+    let one = |elem| ExElemBuilder::new().with_elem(elem).build();
+    let grammar: Grammar = vec![
+      (
+        VN::Exp,
+        vec![
+          vec![
+            one(VN::Exp.into()),
+            one(VN::Aop.into()),
+            one(VN::Term.into()),
+          ],
+          vec![one(VN::Term.into())],
+        ],
+      ),
+      (VN::Aop, vec![vec![one(Token::Add.into())]]),
+      (VN::Term, vec![vec![one(Token::Integer(0).into())]]),
+    ]
+    .into_iter()
+    .collect();
+    let kinds = FirstFollow::new(&grammar)
+      .conflicts(&grammar)
+      .into_iter()
+      .map(|conflict| conflict.kind)
+      .collect::<Vec<_>>();
+    assert_eq!(
+      kinds,
+      [
+        ConflictKind::LeftRecursion,
+        ConflictKind::Alternatives(0, 1)
+      ]
+    );
+  }
+
+  #[test]
+  fn pest_parser_demo() {
+    use pl_0::{error::code::ErrorCode, pest_parser::PestParser};
+
+    fn pas_files_of(dir: std::path::PathBuf, files: &mut Vec<std::path::PathBuf>) {
+      for entry in std::fs::read_dir(dir).unwrap() {
+        let path = entry.unwrap().path();
+        if path.is_dir() {
+          pas_files_of(path, files);
+        } else if path.extension().is_some_and(|ext| ext == "pas") {
+          files.push(path);
+        }
+      }
+    }
+    let mut files = vec![];
+    pas_files_of(PROJECT_ROOT.to_string().into(), &mut files);
+    files.sort();
+    assert!(files.len() > 30);
+
+    // both front-ends agree on every example, spans included
+    for file in &files {
+      let content = std::fs::read_to_string(file).unwrap();
+      let handwritten = Parser::new(&content).parse();
+      let pest = PestParser::new(&content).parse();
+      match (handwritten, pest) {
+        (Ok(handwritten), Ok(pest)) => assert_eq!(
+          format!("{:?}", handwritten),
+          format!("{:?}", pest),
+          "{:?}",
+          file
+        ),
+        (Err(_), Err(_)) => (),
+        (handwritten, pest) => panic!(
+          "{:?}: handwritten: {}, pest: {}",
+          file,
+          handwritten.is_ok(),
+          pest.is_ok()
+        ),
+      }
+    }
+
+    let errors = PestParser::parse_content("program p;\nbegin\n  a := \nend").unwrap_err();
+    assert_eq!(errors.len(), 1);
+    assert_eq!(
+      (errors[0].line, errors[0].col, errors[0].code),
+      (4, 1, Some(ErrorCode::ExpectedToken))
+    );
+    assert!(errors[0].info.starts_with("Expected"));
+    assert!(errors[0].info.ends_with("but got `e`"));
+  }
+
+  #[test]
+  fn spans_demo() {
+    use pl_0::{
+      ast::{Location, Span, StatementExpr},
+      lexer::{token_def::Token, Lexer},
+    };
+
+    // byte offsets count non-ASCII chars in comments by their UTF-8 length,
+    // `\r\n` is a single line break and `\t` is not a line break at all
+    let source = "program p; { é }\r\nvar\tabc;\r\nbegin abc := 10 end";
+    let mut lexer = Lexer::new(source);
+    let spanned = std::iter::from_fn(|| lexer.next_spanned()).collect::<Vec<_>>();
+    assert_eq!(
+      spanned[4],
+      (
+        Token::Identifier("abc".to_string()),
+        Span {
+          start: 23,
+          end: 26,
+          location: Location(2, 5),
+          end_location: Location(2, 7),
+        }
+      )
+    );
+    for (token, span) in &spanned {
+      if let Token::Identifier(id) = token {
+        assert_eq!(&source[span.start..span.end], id);
+      }
+    }
+
+    let ast_entry = Parser::new(source).parse().unwrap();
+    assert_eq!(ast_entry.span.location, Location(1, 1));
+    assert_eq!(ast_entry.span.end, source.len());
+    let body = &ast_entry.block.body;
+    assert_eq!(
+      (body.span.location, body.span.end_location),
+      (Location(3, 1), Location(3, 19))
+    );
+    let StatementExpr::Id { id, exp, span, .. } = body.statements[0].as_ref() else {
+      unreachable!()
+    };
+    // identifiers are located at their first char
+    assert_eq!(id.1.location, Location(3, 7));
+    assert_eq!(&source[span.start..span.end], "abc := 10");
+    assert_eq!(&source[exp.span.start..exp.span.end], "10");
+
+    let source = "program p;\nprocedure q(a);\n  begin write((a + 1) * 2) end\nbegin call q(1) end";
+    let ast_entry = Parser::new(source).parse().unwrap();
+    let proc = ast_entry.block.proc.as_ref().unwrap();
+    assert_eq!(
+      &source[proc.span.start..proc.span.end],
+      "procedure q(a);\n  begin write((a + 1) * 2) end"
+    );
+    assert_eq!(
+      &source[proc.block.span.start..proc.block.span.end],
+      "begin write((a + 1) * 2) end"
+    );
+    let StatementExpr::Write { exps, .. } = proc.block.body.statements[0].as_ref() else {
+      unreachable!()
+    };
+    assert_eq!(&source[exps[0].span.start..exps[0].span.end], "(a + 1) * 2");
+    assert_eq!(exps[0].term.factor.span().location, Location(3, 15));
   }
 
   #[test]
-  #[should_panic]
   fn unsupported_ascii_char_demo() {
-    Parser::new(&file_to_string(
+    assert!(Parser::new(&file_to_string(
       PROJECT_ROOT.to_string() + "/examples/lexer/unsupported_ascii_char.pas",
     ))
     .parse()
-    .show_ast();
+    .is_err());
   }
 
   #[test]
-  #[should_panic]
   fn malformed_char_demo() {
-    Parser::new(&file_to_string(
+    assert!(Parser::new(&file_to_string(
       PROJECT_ROOT.to_string() + "/examples/lexer/japanese_programming.pas",
     ))
     .parse()
-    .show_ast();
+    .is_err());
   }
 
   #[test]
-  #[should_panic]
   fn losing_prog_id_demo() {
-    Parser::new(&file_to_string(
+    assert!(Parser::new(&file_to_string(
       PROJECT_ROOT.to_string() + "/examples/parser/losing_prog_id.pas",
     ))
     .parse()
-    .show_ast();
+    .is_err());
   }
 
   #[test]
-  #[should_panic]
   fn losing_eqsign_demo() {
-    Parser::new(&file_to_string(
+    assert!(Parser::new(&file_to_string(
       PROJECT_ROOT.to_string() + "/examples/parser/losing_eqsign.pas",
     ))
     .parse()
-    .show_ast();
+    .is_err());
   }
 
   #[test]
-  #[should_panic]
   fn multi_err_demo() {
-    Parser::new(&file_to_string(
+    use pl_0::error::compile_error::CompileErrorType::{LexicalError, SyntaxError};
+
+    let errors = Parser::new(&file_to_string(
       PROJECT_ROOT.to_string() + "/examples/parser/multi_err.pas",
     ))
     .parse()
-    .show_ast();
+    .unwrap_err();
+    let got = errors
+      .iter()
+      .map(|err| (err.line, err.col, err.error_type))
+      .collect::<Vec<_>>();
+    let expected = vec![
+      (1, 9, SyntaxError),
+      (4, 8, SyntaxError),
+      (5, 9, SyntaxError),
+      (6, 3, LexicalError),
+      (6, 5, LexicalError),
+      (6, 7, SyntaxError),
+      (6, 7, SyntaxError),
+      (9, 3, SyntaxError),
+      (10, 11, SyntaxError),
+      (11, 9, SyntaxError),
+      (11, 18, SyntaxError),
+      (12, 5, LexicalError),
+      (12, 6, SyntaxError),
+      (12, 6, SyntaxError),
+    ];
+    assert_eq!(got, expected);
   }
 
   #[test]
-  #[should_panic]
   fn wrong_if_demo() {
-    Parser::new(&file_to_string(
+    assert!(Parser::new(&file_to_string(
       PROJECT_ROOT.to_string() + "/examples/parser/wrong_if.pas",
     ))
     .parse()
-    .show_ast();
+    .is_err());
+  }
+
+  #[test]
+  fn wrong_cond_demo() {
+    use pl_0::error::code::ErrorCode;
+
+    // a missing `<l-factor>` / `)` is reported once, and parsing goes on
+    // from the `then` / `do` after it
+    let errors = Parser::new(&file_to_string(
+      PROJECT_ROOT.to_string() + "/examples/parser/wrong_cond.pas",
+    ))
+    .parse()
+    .unwrap_err();
+    let got = errors
+      .iter()
+      .map(|err| (err.line, err.col, err.code))
+      .collect::<Vec<_>>();
+    assert_eq!(
+      got,
+      vec![
+        (4, 16, Some(ErrorCode::ExpectedFactor)),
+        (5, 10, Some(ErrorCode::ExpectedFactor)),
+        (6, 25, Some(ErrorCode::ExpectedFactor)),
+        (6, 25, Some(ErrorCode::ExpectedToken)),
+        (7, 23, Some(ErrorCode::ExpectedToken)),
+      ]
+    );
+  }
+
+  fn semantic_errors_of(filename: &str) -> Vec<(usize, usize, String)> {
+    let content = file_to_string(PROJECT_ROOT.to_string() + filename);
+    let ast_entry = Parser::new(&content).parse().unwrap();
+    Translator::default()
+      .translate(&ast_entry)
+      .unwrap_err()
+      .into_iter()
+      .map(|err| (err.line, err.col, err.info))
+      .collect()
+  }
+
+  #[test]
+  fn correct_programs_compile_demo() {
+    for name in [
+      "arith_test",
+      "array",
+      "bool_ops",
+      "cond_test",
+      "fib",
+      "fib_func",
+      "loops",
+      "multi_arg",
+      "nested_proc",
+      "nested_recursion",
+      "no_arg_proc",
+      "simple_proc",
+      "sum_a_to_b",
+      "test",
+      "var_param",
+    ] {
+      let content = file_to_string(PROJECT_ROOT.to_string() + "/examples/correct/" + name + ".pas");
+      assert!(
+        pl_0::compile(&content).is_ok(),
+        "`{}` failed to compile",
+        name
+      );
+    }
+  }
+
+  #[test]
+  fn undef_demo() {
+    assert_eq!(
+      semantic_errors_of("/examples/semantic/undef.pas"),
+      vec![
+        (3, 3, "`a` is undefined".to_string()),
+        (4, 3, "`b` is undefined".to_string()),
+        (5, 9, "`c` is undefined".to_string()),
+      ]
+    );
+  }
+
+  #[test]
+  fn multi_def_demo() {
+    assert_eq!(
+      semantic_errors_of("/examples/semantic/multi_def.pas"),
+      vec![
+        (3, 8, "`a` is defined before".to_string()),
+        (3, 11, "`a` is defined before".to_string()),
+        (3, 14, "`a` is defined before".to_string()),
+        (10, 11, "`proc` is defined before".to_string()),
+      ]
+    );
+  }
+
+  #[test]
+  fn error_code_demo() {
+    use pl_0::error::{code::ErrorCode, traits::ErrorTrait};
+    use std::{collections::HashSet, str::FromStr};
+
+    // stable, unique, and could be parsed back
+    let codes = ErrorCode::ALL
+      .iter()
+      .map(|code| code.as_str())
+      .collect::<HashSet<_>>();
+    assert_eq!(codes.len(), ErrorCode::ALL.len());
+    for code in ErrorCode::ALL {
+      assert_eq!(ErrorCode::from_str(code.as_str()), Ok(code));
+      // with an example
+      assert!(code.explain().contains("\n\n    "));
+    }
+    assert_eq!(ErrorCode::Redefined.as_str(), "E0202");
+    assert_eq!(ErrorCode::from_str("e0001"), Ok(ErrorCode::NonAsciiChar));
+    assert!(ErrorCode::from_str("E9999").is_err());
+
+    let codes_of = |filename: &str| {
+      let content = file_to_string(PROJECT_ROOT.to_string() + filename);
+      let errors = match Parser::new(&content).parse() {
+        Ok(ast_entry) => Translator::default().translate(&ast_entry).unwrap_err(),
+        Err(errors) => errors,
+      };
+      errors.into_iter().map(|err| err.code).collect::<Vec<_>>()
+    };
+    use ErrorCode::*;
+    assert_eq!(
+      codes_of("/examples/semantic/undef.pas"),
+      vec![Some(Undefined); 3]
+    );
+    assert_eq!(
+      codes_of("/examples/semantic/multi_def.pas"),
+      vec![Some(Redefined); 4]
+    );
+    assert_eq!(
+      codes_of("/examples/semantic/misuse_func.pas"),
+      vec![
+        Some(NotAFunction),
+        Some(NotAnRValue),
+        Some(NotAnRValue),
+        Some(ArgsCountMismatch),
+        Some(Undefined),
+        Some(NotAVariable),
+      ]
+    );
+    assert_eq!(
+      codes_of("/examples/semantic/not_a_reference.pas"),
+      [vec![Some(NotAReference); 4], vec![Some(Undefined)]].concat()
+    );
+    assert_eq!(
+      codes_of("/examples/semantic/misuse_for.pas"),
+      [
+        vec![Some(NotAVariable)],
+        vec![Some(AssignToLoopVar); 4],
+        vec![Some(NotAVariable); 2],
+        vec![Some(Undefined)],
+      ]
+      .concat()
+    );
+    assert_eq!(
+      codes_of("/examples/semantic/misuse_array.pas"),
+      vec![
+        Some(InvalidBounds),
+        Some(InvalidBounds),
+        Some(NotAnArray),
+        Some(NotAVariable),
+        Some(NotAVariable),
+        Some(NotAnArray),
+        Some(Undefined),
+        Some(NotAnRValue),
+        Some(NotAnArray),
+        Some(Undefined),
+        Some(NotAReference),
+      ]
+    );
+    assert_eq!(
+      codes_of("/examples/semantic/assign_to_const_proc.pas"),
+      vec![Some(NotAVariable); 2]
+    );
+    assert_eq!(
+      codes_of("/examples/semantic/wrong_args_num.pas"),
+      vec![Some(ArgsCountMismatch)]
+    );
+    assert_eq!(
+      codes_of("/examples/lexer/single_colon.pas")[0],
+      Some(UndefinedSign)
+    );
+    assert_eq!(
+      codes_of("/examples/parser/losing_eqsign.pas"),
+      vec![Some(ExpectedToken)]
+    );
+    // every syntax error found by the parser has a code
+    assert!(codes_of("/examples/parser/multi_err.pas")
+      .iter()
+      .all(Option::is_some));
+
+    assert_eq!(
+      runtime_error_of("/examples/runtime/div_by_zero.pas").code(),
+      Some(DivisionByZero)
+    );
+    assert_eq!(
+      runtime_error_of("/examples/runtime/index_out_of_bounds.pas").code(),
+      Some(IndexOutOfBounds)
+    );
+  }
+
+  #[test]
+  fn render_demo() {
+    use pl_0::error::render::{ColorChoice, Renderer};
+
+    let content = file_to_string(PROJECT_ROOT.to_string() + "/examples/semantic/multi_def.pas");
+    let ast_entry = Parser::new(&content).parse().unwrap();
+    let errors = Translator::default().translate(&ast_entry).unwrap_err();
+    let renderer = Renderer::new(&content, "multi_def.pas").with_color(ColorChoice::Never);
+    assert_eq!(
+      renderer.render(&errors[0]),
+      "\
+SemanticError[E0202]: `a` is defined before
+ --> multi_def.pas:3:8
+  |
+3 | var a, a, a, a;
+  |     - first defined here
+  |        ^
+"
+    );
+    assert_eq!(
+      renderer.render(&errors[3]),
+      "\
+SemanticError[E0202]: `proc` is defined before
+  --> multi_def.pas:10:11
+   |
+ 5 | procedure proc();
+   |           - first defined here
+...
+10 | procedure proc();
+   |           ^^^^
+"
+    );
+
+    let content = file_to_string(PROJECT_ROOT.to_string() + "/examples/lexer/single_colon.pas");
+    let errors = Parser::new(&content).parse().unwrap_err();
+    let rendered = Renderer::new(&content, "single_colon.pas")
+      .with_color(ColorChoice::Never)
+      .render(&errors[0]);
+    assert!(rendered.ends_with("  |     ^\n  = help: did you mean ':='?\n"));
+    assert!(!rendered.contains('\x1b'));
+    assert!(rendered.starts_with("LexicalError[E0003]: "));
+
+    let rendered = Renderer::new(&content, "single_colon.pas")
+      .with_color(ColorChoice::Always)
+      .render(&errors[0]);
+    assert!(rendered.starts_with("\x1b[1;31mLexicalError[E0003]\x1b[0m"));
+  }
+
+  #[test]
+  fn wrong_args_list_len_demo() {
+    assert_eq!(
+      semantic_errors_of("/examples/semantic/wrong_args_list_len.pas"),
+      vec![
+        (16, 8, "`proc` expects 0 args, but received 3".to_string()),
+        (17, 8, "`procc` expects 3 args, but received 1".to_string()),
+      ]
+    );
+  }
+
+  #[test]
+  fn assign_to_const_proc_demo() {
+    assert_eq!(
+      semantic_errors_of("/examples/semantic/assign_to_const_proc.pas"),
+      vec![
+        (10, 3, "`i` is not a variable".to_string()),
+        (11, 3, "`proc` is not a variable".to_string()),
+      ]
+    );
+  }
+
+  #[test]
+  fn misuse_func_demo() {
+    assert_eq!(
+      semantic_errors_of("/examples/semantic/misuse_func.pas"),
+      vec![
+        (12, 8, "`a` is not a function".to_string()),
+        (
+          12,
+          14,
+          "`p` is a procedure without a return value".to_string()
+        ),
+        (12, 20, "`f` is a function, but it's not called".to_string()),
+        (12, 24, "`f` expects 1 args, but received 2".to_string()),
+        (12, 34, "`g` is undefined".to_string()),
+        (13, 3, "`f` is not a variable".to_string()),
+      ]
+    );
+  }
+
+  #[test]
+  fn function_demo() {
+    let output = run_with_input("/examples/correct/fib_func.pas", "").unwrap();
+    let lines = output.lines().collect::<Vec<_>>();
+    assert_eq!(lines.len(), 20);
+    assert_eq!(lines[0], "1  10  ");
+    assert_eq!(lines[11], "144  12  ");
+    assert_eq!(lines[19], "6765  20  ");
+
+    // nested routines calling the enclosing one
+    assert_eq!(
+      run_with_input("/examples/correct/nested_recursion.pas", "5\n"),
+      Ok("120  \n5  \n4  \n3  \n2  \n1  \n".to_string())
+    );
+
+    // the result is `0` if it's never assigned, and `call` discards it
+    let content = "
+      program p;
+      var a;
+      function f();
+      begin
+        a := a + 1
+      end
+      begin
+        a := 41;
+        call f();
+        write(f() + a)
+      end
+    ";
+    let io = BufIo::new("".as_bytes(), vec![]);
+    let mut vm = VM::try_with_io(pl_0::compile(content).unwrap(), io).unwrap();
+    vm.interpret().unwrap();
+    assert_eq!(
+      String::from_utf8(vm.into_io().into_inner().1).unwrap(),
+      "43  \n"
+    );
+  }
+
+  #[test]
+  fn not_a_reference_demo() {
+    assert_eq!(
+      semantic_errors_of("/examples/semantic/not_a_reference.pas"),
+      vec![
+        (
+          11,
+          12,
+          "`one` is not a variable, but it's passed by reference".to_string()
+        ),
+        (
+          12,
+          12,
+          "only a variable could be passed by reference".to_string()
+        ),
+        (
+          13,
+          12,
+          "only a variable could be passed by reference".to_string()
+        ),
+        (
+          14,
+          12,
+          "`inc` is not a variable, but it's passed by reference".to_string()
+        ),
+        (15, 12, "`b` is undefined".to_string()),
+      ]
+    );
+
+    // not hidden by constant folding (`a * 1` -> `a`)
+    let content =
+      file_to_string(PROJECT_ROOT.to_string() + "/examples/semantic/not_a_reference.pas");
+    assert_eq!(pl_0::compile(&content).unwrap_err().len(), 5);
+  }
+
+  #[test]
+  fn var_param_demo() {
+    assert_eq!(
+      run_with_input("/examples/correct/var_param.pas", "20\n"),
+      Ok("2  1  \n5  5  \n5  40  3  \n".to_string())
+    );
+  }
+
+  #[test]
+  fn misuse_array_demo() {
+    assert_eq!(
+      semantic_errors_of("/examples/semantic/misuse_array.pas"),
+      vec![
+        (
+          2,
+          13,
+          "`b` has no elements, its upper bound is less than its lower bound".to_string()
+        ),
+        (
+          2,
+          24,
+          "`c` has no elements, its upper bound is less than its lower bound".to_string()
+        ),
+        (10, 3, "`x` is not an array, but it's indexed".to_string()),
+        (11, 3, "`a` is not a variable".to_string()),
+        (12, 8, "`a` is not a variable".to_string()),
+        (12, 11, "`x` is not an array, but it's indexed".to_string()),
+        (12, 17, "`d` is undefined".to_string()),
+        (13, 9, "`a` is an array, but it's not indexed".to_string()),
+        (13, 16, "`x` is not an array, but it's indexed".to_string()),
+        (13, 22, "`d` is undefined".to_string()),
+        (
+          14,
+          12,
+          "`a` is not a variable, but it's passed by reference".to_string()
+        ),
+      ]
+    );
+  }
+
+  #[test]
+  fn misuse_for_demo() {
+    let read_only = |line, col| {
+      (
+        line,
+        col,
+        "`i` is the control variable of a `for` loop, it's read-only inside the loop".to_string(),
+      )
+    };
+    let not_a_var = |line, col, name| {
+      (
+        line,
+        col,
+        format!(
+          "`{}` is not a variable, it couldn't be the control variable of a `for` loop",
+          name
+        ),
+      )
+    };
+    assert_eq!(
+      semantic_errors_of("/examples/semantic/misuse_for.pas"),
+      vec![
+        not_a_var(8, 7, "x"),
+        read_only(14, 5),
+        read_only(15, 10),
+        read_only(16, 14),
+        read_only(18, 11),
+        not_a_var(22, 7, "c"),
+        not_a_var(23, 7, "a"),
+        (24, 7, "`k` is undefined".to_string()),
+      ]
+    );
+  }
+
+  #[test]
+  fn array_demo() {
+    assert_eq!(
+      run_with_input("/examples/correct/array.pas", "3\n1\n4\n1\n5\n"),
+      Ok("1  1  3  4  5  \n9  25  \n14  \n".to_string())
+    );
+
+    // elements of arrays in different frames, passed by reference
+    let content = "
+      program p;
+      var a[2 .. 4];
+      procedure q(var x);
+      var b[3];
+      begin
+        b[2] := x;
+        x := b[2] * 10;
+        a[4] := a[2] + a[3]
+      end
+      begin
+        a[2] := 1;
+        a[3] := 2;
+        call q(a[3]);
+        write(a[2], a[3], a[4])
+      end
+    ";
+    let io = BufIo::new("".as_bytes(), vec![]);
+    let mut vm = VM::try_with_io(pl_0::compile(content).unwrap(), io).unwrap();
+    vm.interpret().unwrap();
+    assert_eq!(
+      String::from_utf8(vm.into_io().into_inner().1).unwrap(),
+      "1  20  21  \n"
+    );
+  }
+
+  #[test]
+  fn bool_ops_demo() {
+    use pl_0::pcode::PcodeType;
+    for (input, output) in [
+      ("3\n", "1  \n1  \n0  \n0  \n0  \n0  \n1  \n2  \n2  \n"),
+      // no division by zero, `touch` is called 3 times
+      ("0\n", "0  \n1  \n1  \n1  \n0  \n0  \n3  \n1  \n"),
+      ("8\n", "0  \n1  \n0  \n1  \n1  \n1  \n1  \n2  \n3  \n"),
+    ] {
+      assert_eq!(
+        run_with_input("/examples/correct/bool_ops.pas", input),
+        Ok(output.to_string())
+      );
+    }
+
+    // constant comparisons are folded
+    let content = "
+      program p;
+      const on := 1;
+      var x;
+      begin
+        read(x);
+        if (on = 1) or (x > 0) then write(1);
+        if not (on = 0) and (x > 0) then write(2);
+        if (on = 0) and (x > 0) then write(3)
+      end
+    ";
+    let code = pl_0::compile(content).unwrap();
+    let cmp_ops = code
+      .pcode_list
+      .iter()
+      .filter(|pcode| matches!(pcode.f, PcodeType::OPR) && (8..=13).contains(&pcode.a))
+      .count();
+    // only `x > 0` is left in each `if`, it's kept in `true or x` and
+    // `false and x` (which are short-circuited), `x` may hide a semantic error
+    assert_eq!(cmp_ops, 3);
+    let mut vm = VM::with_io(code, BufIo::new("5\n".as_bytes(), vec![]));
+    vm.interpret().unwrap();
+    assert_eq!(
+      String::from_utf8(vm.into_io().into_inner().1).unwrap(),
+      "1  \n2  \n"
+    );
+  }
+
+  #[test]
+  fn loops_demo() {
+    assert_eq!(
+      run_with_input("/examples/correct/loops.pas", "4\n"),
+      Ok("10  0  \n10  \n5  120  \n1  \n0  \n2  \n1  \n0  \n3  \n2  \n1  \n0  \n".to_string())
+    );
+
+    // `i` stops at the bound, without overflow
+    let content = "
+      program p;
+      const max := 9223372036854775807;
+      var i, n;
+      begin
+        n := 0;
+        for i := max - 2 to max do n := n + 1;
+        write(i, n);
+        for i := -max + 1 downto -max - 1 do n := n + 1;
+        write(i, n)
+      end
+    ";
+    let io = BufIo::new("".as_bytes(), vec![]);
+    let mut vm = VM::try_with_io(pl_0::compile(content).unwrap(), io).unwrap();
+    vm.interpret().unwrap();
+    assert_eq!(
+      String::from_utf8(vm.into_io().into_inner().1).unwrap(),
+      "9223372036854775807  3  \n-9223372036854775808  6  \n"
+    );
+
+    // both bounds read `i` before it's assigned
+    let content = "
+      program p;
+      var i, n;
+      begin
+        i := 10;
+        n := 0;
+        for i := 1 to i + 5 do n := n + 1;
+        write(i, n);
+        i := 3;
+        n := 0;
+        for i := i * 2 downto i do n := n + 1;
+        write(i, n)
+      end
+    ";
+    let io = BufIo::new("".as_bytes(), vec![]);
+    let mut vm = VM::try_with_io(pl_0::compile(content).unwrap(), io).unwrap();
+    vm.interpret().unwrap();
+    assert_eq!(
+      String::from_utf8(vm.into_io().into_inner().1).unwrap(),
+      "15  15  \n3  4  \n"
+    );
+
+    // `until` a false condition never ends
+    let content = "
+      program p;
+      var x;
+      begin
+        repeat x := x + 1 until 1 = 0;
+        write(x)
+      end
+    ";
+    let mut optimizer = AstOptimizer::new(Parser::new(content).parse().unwrap());
+    optimizer.optimize().unwrap();
+    let warnings = optimizer
+      .warnings
+      .iter()
+      .map(|warning| (warning.line, warning.col, warning.info.to_owned()))
+      .collect::<Vec<_>>();
+    assert_eq!(warnings, vec![(6, 9, "unreachable statement".to_string())]);
+  }
+
+  fn runtime_error_of(filename: &str) -> pl_0::error::runtime_error::RuntimeError {
+    let content = file_to_string(PROJECT_ROOT.to_string() + filename);
+    VM::new(pl_0::compile(&content).unwrap())
+      .interpret()
+      .unwrap_err()
+  }
+
+  #[test]
+  fn div_by_zero_demo() {
+    let err = runtime_error_of("/examples/runtime/div_by_zero.pas");
+    assert_eq!(err.error_type, RuntimeErrorType::DivisionByZero);
+    assert_eq!(err.line, 6);
+    assert_eq!(err.proc_name, "DivByZero");
+  }
+
+  #[test]
+  fn index_out_of_bounds_demo() {
+    let err = runtime_error_of("/examples/runtime/index_out_of_bounds.pas");
+    assert_eq!(err.error_type, RuntimeErrorType::IndexOutOfBounds);
+    assert_eq!(err.line, 7);
+    assert!(err
+      .info
+      .starts_with("index `11` is out of the bounds `1 .. 10`"));
+  }
+
+  #[test]
+  fn infinite_recursion_demo() {
+    let err = runtime_error_of("/examples/runtime/infinite_recursion.pas");
+    assert_eq!(err.error_type, RuntimeErrorType::StackOverflow);
+    assert_eq!(err.line, 4);
+    assert_eq!(err.proc_name, "forever");
+  }
+
+  #[test]
+  fn invalid_instruction_demo() {
+    let mut code = pl_0::pcode::PCodeManager::default();
+    code.gen(pl_0::pcode::PcodeType::NIL, 0, 0);
+    let err = VM::new(code).interpret().unwrap_err();
+    assert_eq!(err.error_type, RuntimeErrorType::InvalidInstruction);
+    assert_eq!(err.pc, 0);
+  }
+
+  fn run_with_input(filename: &str, input: &str) -> Result<String, RuntimeErrorType> {
+    let content = file_to_string(PROJECT_ROOT.to_string() + filename);
+    let io = BufIo::new(input.as_bytes(), vec![]);
+    let mut vm = VM::with_io(pl_0::compile(&content).unwrap(), io);
+    vm.interpret().map_err(|err| err.error_type)?;
+    Ok(String::from_utf8(vm.into_io().into_inner().1).unwrap())
+  }
+
+  #[test]
+  fn scripted_io_demo() {
+    assert_eq!(
+      run_with_input("/examples/correct/sum_a_to_b.pas", "1\n10\n"),
+      Ok("55  \n".to_string())
+    );
+  }
+
+  #[test]
+  fn int_semantics_demo() {
+    use pl_0::{
+      error::code::ErrorCode,
+      vm::arith::{IntSemantics, IntWidth, Overflow},
+    };
+
+    let run = |source: &str, int: IntSemantics, input: &str| {
+      let ast_entry = Parser::new(source)
+        .with_int_width(int.width)
+        .parse()
+        .unwrap();
+      let ast_entry = AstOptimizer::new(ast_entry)
+        .with_int_semantics(int)
+        .optimize()
+        .unwrap();
+      let code = Translator::default().translate(&ast_entry).unwrap();
+      let io = BufIo::new(input.as_bytes(), vec![]);
+      let mut vm = VM::with_io(code, io).with_int_semantics(int);
+      vm.interpret().map_err(|err| err.error_type)?;
+      Ok(String::from_utf8(vm.into_io().into_inner().1).unwrap())
+    };
+    let i16_with = |overflow| IntSemantics::new(IntWidth::I16, overflow);
+
+    // computed by the VM
+    let source = file_to_string(PROJECT_ROOT.to_string() + "/examples/runtime/overflow.pas");
+    assert_eq!(
+      run(&source, i16_with(Overflow::Checked), "20000\n"),
+      Err(RuntimeErrorType::ArithmeticOverflow)
+    );
+    assert_eq!(
+      run(&source, i16_with(Overflow::Wrapping), "20000\n"),
+      Ok("-25536  \n".to_string())
+    );
+    assert_eq!(
+      run(&source, i16_with(Overflow::Saturating), "-20000\n"),
+      Ok("-32768  \n".to_string())
+    );
+    assert_eq!(
+      run(&source, i16_with(Overflow::Wrapping), "40000\n"),
+      Err(RuntimeErrorType::InvalidInput)
+    );
+    assert_eq!(
+      run(&source, IntSemantics::default(), "9223372036854775807\n"),
+      Err(RuntimeErrorType::ArithmeticOverflow)
+    );
+
+    // folded constants are the same as what the VM computes
+    let source = "program p; begin write(32767 + 1, -32767 - 2, 200 * 200) end";
+    assert_eq!(
+      run(source, i16_with(Overflow::Wrapping), ""),
+      Ok("-32768  32767  -25536  \n".to_string())
+    );
+    assert_eq!(
+      run(source, i16_with(Overflow::Saturating), ""),
+      Ok("32767  -32768  32767  \n".to_string())
+    );
+    assert_eq!(
+      run(source, i16_with(Overflow::Checked), ""),
+      Err(RuntimeErrorType::ArithmeticOverflow)
+    );
+
+    // literals out of range
+    let source = "program p; const a := 40000; begin write(a) end";
+    assert!(run(source, IntSemantics::default(), "").is_ok());
+    let errors = Parser::new(source)
+      .with_int_width(IntWidth::I16)
+      .parse()
+      .unwrap_err();
+    assert_eq!(
+      (
+        errors[0].code,
+        errors[0].span.location.1,
+        errors[0].span.end_location.1
+      ),
+      (Some(ErrorCode::IntegerOverflow), 23, 27)
+    );
+    // the range is checked before the negation, `-32768` can't be written
+    let errors = Parser::new("program p; begin write(-32768) end")
+      .with_int_width(IntWidth::I16)
+      .parse()
+      .unwrap_err();
+    assert_eq!(errors[0].code, Some(ErrorCode::IntegerOverflow));
+    assert_eq!(
+      run(
+        "program p; begin write(-32767 - 1) end",
+        i16_with(Overflow::Checked),
+        ""
+      ),
+      Ok("-32768  \n".to_string())
+    );
+    let errors = Parser::new("program p; begin write(99999999999999999999) end")
+      .parse()
+      .unwrap_err();
+    assert_eq!(errors[0].code, Some(ErrorCode::IntegerOverflow));
+
+    // one diagnostic for each literal, from both front-ends
+    let source = "
+      program p;
+      const c := 99999999999999999999;
+      var x, a[1 .. 99999999999999999999];
+      begin
+        x := 99999999999999999999 + x
+      end
+    ";
+    let codes_of = |errors: Vec<pl_0::error::compile_error::CompileError>| {
+      errors
+        .into_iter()
+        .map(|err| (err.line, err.code))
+        .collect::<Vec<_>>()
+    };
+    let expected = [3, 4, 6].map(|line| (line, Some(ErrorCode::IntegerOverflow)));
+    assert_eq!(codes_of(Parser::new(source).parse().unwrap_err()), expected);
+    assert_eq!(
+      codes_of(
+        pl_0::pest_parser::PestParser::new(source)
+          .parse()
+          .unwrap_err()
+      ),
+      expected
+    );
+  }
+
+  #[test]
+  fn invalid_input_demo() {
+    assert_eq!(
+      run_with_input("/examples/correct/sum_a_to_b.pas", "1\nten\n"),
+      Err(RuntimeErrorType::InvalidInput)
+    );
+    assert_eq!(
+      run_with_input("/examples/correct/sum_a_to_b.pas", "1\n"),
+      Err(RuntimeErrorType::InvalidInput)
+    );
+  }
+
+  #[test]
+  fn const_fold_demo() {
+    use pl_0::pcode::PcodeType;
+    let content = file_to_string(PROJECT_ROOT.to_string() + "/examples/optimizer/const_fold.pas");
+    let code = pl_0::compile(&content).unwrap();
+    let lits = code
+      .pcode_list
+      .iter()
+      .filter(|pcode| matches!(pcode.f, PcodeType::LIT))
+      .map(|pcode| pcode.a)
+      .collect::<Vec<_>>();
+    // `1 + k * 2` -> 7, `-(k - 4) * (7 / 2)` -> 3, `k * 2` is not folded
+    // inside `shadow` (`k` is an arg there)
+    assert_eq!(lits, vec![2, 7, 3, 5]);
+    let arith_ops = code
+      .pcode_list
+      .iter()
+      .filter(|pcode| matches!(pcode.f, PcodeType::OPR) && (1..=5).contains(&pcode.a))
+      .count();
+    assert_eq!(arith_ops, 1);
+    assert_eq!(
+      run_with_input("/examples/optimizer/const_fold.pas", ""),
+      Ok("7  \n3  \n10  \n".to_string())
+    );
+  }
+
+  #[test]
+  fn div_by_const_zero_demo() {
+    let content =
+      file_to_string(PROJECT_ROOT.to_string() + "/examples/optimizer/div_by_const_zero.pas");
+    let errors = pl_0::compile(&content)
+      .unwrap_err()
+      .into_iter()
+      .map(|err| match err {
+        pl_0::error::PL0Error::CompileError(err) => (err.line, err.col, err.info),
+        err => panic!("unexpected error: {}", err),
+      })
+      .collect::<Vec<_>>();
+    assert_eq!(
+      errors,
+      vec![
+        (6, 11, "attempt to divide by zero".to_string()),
+        (7, 11, "attempt to divide by zero".to_string()),
+      ]
+    );
+  }
+
+  #[test]
+  fn dead_code_demo() {
+    use pl_0::pcode::PcodeType;
+    let content = file_to_string(PROJECT_ROOT.to_string() + "/examples/optimizer/dead_code.pas");
+    let mut optimizer = AstOptimizer::new(Parser::new(&content).parse().unwrap());
+    let ast_entry = optimizer.optimize().unwrap();
+    let warnings = optimizer
+      .warnings
+      .iter()
+      .map(|warning| (warning.line, warning.col, warning.info.to_owned()))
+      .collect::<Vec<_>>();
+    assert_eq!(warnings, vec![(15, 3, "unreachable statement".to_string())]);
+
+    let code = Translator::default().translate(&ast_entry).unwrap();
+    assert!(!code
+      .pcode_list
+      .iter()
+      .any(|pcode| matches!(pcode.f, PcodeType::JPC)));
+    let written = code
+      .pcode_list
+      .iter()
+      .filter(|pcode| matches!(pcode.f, PcodeType::LIT))
+      .map(|pcode| pcode.a)
+      .collect::<Vec<_>>();
+    // `write(100)`, `write(200)` and `write(300)` are all gone
+    assert_eq!(written, vec![1, 2, 1]);
+
+    // warnings are kept by `compile_object`
+    let warnings = pl_0::compile_object(&content).unwrap().warnings;
+    assert_eq!(warnings.len(), 1);
+    assert_eq!(
+      warnings[0].to_diagnostic("dead_code.pas").code.as_deref(),
+      Some("W0001")
+    );
+  }
+
+  #[test]
+  fn dead_code_is_checked_demo() {
+    use pl_0::error::PL0Error;
+    let source = "
+program P;
+var x;
+begin
+  x := 1;
+  if 0 = 1 then y := 2;
+  while 1 = 1 do x := x + 1;
+  call nope()
+end";
+    let diagnostics_of = |opt_level: &str| {
+      let args = ["emit", opt_level, "--error-format", "sarif", "f.pas"].map(String::from);
+      let cli = Cli::parse(&args).unwrap();
+      assert_eq!(cli.compile(source).unwrap_err(), cli::EXIT_COMPILE_ERROR);
+      cli
+        .diagnostics()
+        .into_iter()
+        .map(|diagnostic| (diagnostic.line, diagnostic.column, diagnostic.code))
+        .collect::<Vec<_>>()
+    };
+    let expected = vec![
+      (6, 17, Some("E0201".to_string())),
+      (8, 8, Some("E0201".to_string())),
+    ];
+    assert_eq!(diagnostics_of("-O0"), expected);
+    assert_eq!(diagnostics_of("-O1"), expected);
+    assert_eq!(diagnostics_of("-O2"), expected);
+
+    let errors = pl_0::compile(source).unwrap_err();
+    assert!(matches!(
+      errors.as_slice(),
+      [PL0Error::CompileError(_), PL0Error::CompileError(_)]
+    ));
+  }
+
+  fn run_code(code: pl_0::pcode::PCodeManager, input: &str) -> String {
+    let mut vm = VM::with_io(code, BufIo::new(input.as_bytes(), vec![]));
+    vm.interpret().unwrap();
+    String::from_utf8(vm.into_io().into_inner().1).unwrap()
+  }
+
+  #[test]
+  fn peephole_demo() {
+    use pl_0::{optimizer::peephole::PeepholeOptimizer, pcode::PcodeType::*};
+    let mut code = pl_0::pcode::PCodeManager::default();
+    for (f, l, a) in [
+      (JMP, 0, 1),
+      (INT, 0, 4),
+      (INT, 0, 0),
+      (LIT, 0, 7),
+      (OPR, 0, 1),
+      (JPC, 0, 7),
+      (OPR, 0, 14),
+      (JMP, 0, 9),
+      (OPR, 0, 15),
+      (OPR, 0, 0),
+    ] {
+      code.gen(f, l, a);
+    }
+    let mut peephole = PeepholeOptimizer::new(code.clone());
+    let optimized = peephole.optimize();
+    let pcode_list = optimized
+      .pcode_list
+      .iter()
+      .map(|pcode| (pcode.f.to_string(), pcode.l, pcode.a))
+      .collect::<Vec<_>>();
+    let expected = [
+      (INT, 0, 4),
+      (LIT, 0, -7),
+      (JPC, 0, 6),
+      (OPR, 0, 14),
+      (JMP, 0, 6),
+      (OPR, 0, 15),
+      (OPR, 0, 0),
+    ]
+    .map(|(f, l, a)| (f.to_string(), l, a));
+    assert_eq!(pcode_list, expected);
+    assert_eq!(peephole.n_removed, 3);
+    assert_eq!(peephole.relocate(9), 6);
+    assert_eq!(
+      optimized.line_table.entry_list.len(),
+      optimized.pcode_list.len()
+    );
+    assert_eq!(run_code(optimized, ""), run_code(code, ""));
+
+    // `x := 7; write(x)`, `x` isn't read back after storing
+    let mut code = pl_0::pcode::PCodeManager::default();
+    for (f, l, a) in [
+      (INT, 0, 4),
+      (LIT, 0, 7),
+      (STO, 0, 3),
+      (LOD, 0, 3),
+      (OPR, 0, 14),
+      (OPR, 0, 0),
+    ] {
+      code.gen(f, l, a);
+    }
+    let optimized = PeepholeOptimizer::new(code.clone()).optimize();
+    let pcode_list = optimized
+      .pcode_list
+      .iter()
+      .map(|pcode| (pcode.f.to_string(), pcode.l, pcode.a))
+      .collect::<Vec<_>>();
+    let expected = [
+      (INT, 0, 4),
+      (LIT, 0, 7),
+      (OPR, 0, 18),
+      (STO, 0, 3),
+      (OPR, 0, 14),
+      (OPR, 0, 0),
+    ]
+    .map(|(f, l, a)| (f.to_string(), l, a));
+    assert_eq!(pcode_list, expected);
+    assert_eq!(run_code(optimized, ""), run_code(code, ""));
+  }
+
+  #[test]
+  fn peephole_keeps_behavior_demo() {
+    use pl_0::optimizer::peephole::PeepholeOptimizer;
+    for name in [
+      "arith_test",
+      "array",
+      "bool_ops",
+      "cond_test",
+      "fib_func",
+      "loops",
+      "nested_proc",
+      "nested_recursion",
+      "no_arg_proc",
+      "simple_proc",
+      "sum_a_to_b",
+      "var_param",
+    ] {
+      let content = file_to_string(PROJECT_ROOT.to_string() + "/examples/correct/" + name + ".pas");
+      let ast_entry = AstOptimizer::new(Parser::new(&content).parse().unwrap())
+        .optimize()
+        .unwrap();
+      let code = Translator::default().translate(&ast_entry).unwrap();
+      let optimized = PeepholeOptimizer::new(code.clone()).optimize();
+      assert_eq!(
+        run_code(optimized, "1\n10\n3\n1\n4\n"),
+        run_code(code, "1\n10\n3\n1\n4\n"),
+        "`{}` behaves differently after peephole optimization",
+        name
+      );
+    }
+  }
+
+  #[test]
+  fn golden_pcode_demo() {
+    for name in [
+      "arith_test",
+      "array",
+      "bool_ops",
+      "cond_test",
+      "fib",
+      "fib_func",
+      "loops",
+      "multi_arg",
+      "nested_proc",
+      "nested_recursion",
+      "no_arg_proc",
+      "simple_proc",
+      "sum_a_to_b",
+      "test",
+      "var_param",
+    ] {
+      let content = file_to_string(PROJECT_ROOT.to_string() + "/examples/correct/" + name + ".pas");
+      let golden = file_to_string(PROJECT_ROOT.to_string() + "/examples/pcode/" + name + ".pcode");
+      let code = pl_0::compile(&content).unwrap();
+      assert_eq!(
+        code.to_asm(),
+        golden,
+        "`{}` differs from its golden pcode",
+        name
+      );
+      let reassembled = pl_0::pcode::PCodeManager::from_asm(&golden).unwrap();
+      assert_eq!(reassembled.to_asm(), golden);
+    }
+  }
+
+  #[test]
+  fn hand_written_pcode_demo() {
+    // listing of the classic Wirth implementation (leading pc, lowercase)
+    let asm = "
+      0 jmp 0 1
+      1 int 0 4
+      2 lit 0 3
+      3 sto 0 3
+      4 lod 0 3
+      5 opr 0 14
+      6 opr 0 15
+      7 opr 0 0
+    ";
+    let code = pl_0::pcode::PCodeManager::from_asm(asm).unwrap();
+    assert_eq!(run_code(code, ""), "3  \n");
+
+    // count down with labels
+    let asm = "
+        JMP 0 main
+      main: INT 0 4
+        LIT 0 3
+        STO 0 3       ; line 3, col 5, in CountDown
+      loop:
+        LOD 0 3
+        LIT 0 0
+        OPR 0 12      ; >
+        JPC 0 done
+        LOD 0 3
+        OPR 0 14
+        LOD 0 3
+        LIT 0 1
+        OPR 0 3
+        STO 0 3
+        JMP 0 loop
+      done:
+        OPR 0 0
+    ";
+    let code = pl_0::pcode::PCodeManager::from_asm(asm).unwrap();
+    assert_eq!(
+      code.line_table.lookup(3).unwrap().to_string(),
+      "line 3, in CountDown"
+    );
+    assert_eq!(run_code(code, ""), "3  2  1  ");
+
+    let errors = pl_0::pcode::PCodeManager::from_asm("  FOO 0 1\n  JMP 0 nowhere\n  LIT x 1\n")
+      .unwrap_err()
+      .into_iter()
+      .map(|err| (err.line, err.col, err.info))
+      .collect::<Vec<_>>();
+    assert_eq!(
+      errors,
+      vec![
+        (1, 3, "`FOO` is not a valid mnemonic".to_string()),
+        (3, 7, "`x` is not a valid level".to_string()),
+        (2, 9, "label `nowhere` is undefined".to_string()),
+      ]
+    );
+  }
+
+  #[test]
+  fn object_file_demo() {
+    use pl_0::pcode::object::ObjectFile;
+    let names = |object: &ObjectFile| {
+      let sym_table = object.sym_table.as_ref().unwrap();
+      sym_table
+        .table
+        .iter()
+        .map(|row| {
+          (
+            row.name.to_owned(),
+            row.ty,
+            row.val,
+            row.scope_list.to_owned(),
+          )
+        })
+        .collect::<Vec<_>>()
+    };
+    for (name, input, output) in [
+      ("sum_a_to_b", "1\n10\n", "55  \n"),
+      ("var_param", "20\n", "2  1  \n5  5  \n5  40  3  \n"),
+      (
+        "array",
+        "3\n1\n4\n1\n5\n",
+        "1  1  3  4  5  \n9  25  \n14  \n",
+      ),
+    ] {
+      let content = file_to_string(PROJECT_ROOT.to_string() + "/examples/correct/" + name + ".pas");
+      let object = pl_0::compile_object(&content).unwrap().object;
+      let loaded = ObjectFile::from_bytes(&object.to_bytes()).unwrap();
+      assert_eq!(loaded.code.to_asm(), object.code.to_asm());
+      assert_eq!(names(&loaded), names(&object));
+      assert_eq!(loaded.int_semantics, Some(Default::default()));
+      assert_eq!(run_code(loaded.code, input), output);
+    }
+
+    // the lower bound of `CHK` doesn't fit in 32 bits
+    let source = "
+      program p;
+      var b[5000000000 .. 5000000002];
+      begin
+        b[5000000001] := 8;
+        write(b[5000000001])
+      end
+    ";
+    let object = pl_0::compile_object(source).unwrap().object;
+    let loaded = ObjectFile::from_bytes(&object.to_bytes()).unwrap();
+    assert_eq!(loaded.code.to_asm(), object.code.to_asm());
+    assert_eq!(run_code(loaded.code, ""), "8  \n");
+
+    // without optional sections
+    let bare = ObjectFile::new(
+      pl_0::pcode::PCodeManager::from_asm("  INT 0 3\n  OPR 0 0\n").unwrap(),
+      None,
+    );
+    let mut bare = ObjectFile::from_bytes(&bare.to_bytes()).unwrap();
+    assert!(bare.sym_table.is_none());
+    assert!(bare.int_semantics.is_none());
+    bare.code.line_table = Default::default();
+    assert!(ObjectFile::from_bytes(&bare.to_bytes()).is_ok());
+  }
+
+  #[test]
+  fn malformed_object_file_demo() {
+    use pl_0::{error::object_error::ObjectErrorType, pcode::object::ObjectFile};
+    let content = file_to_string(PROJECT_ROOT.to_string() + "/examples/correct/nested_proc.pas");
+    let bytes = pl_0::compile_object(&content).unwrap().object.to_bytes();
+
+    for len in 0..bytes.len() {
+      let err = ObjectFile::from_bytes(&bytes[..len]).unwrap_err();
+      assert_eq!(
+        err.error_type,
+        ObjectErrorType::Truncated,
+        "truncated at {}",
+        len
+      );
+    }
+
+    let mut wrong_version = bytes.clone();
+    wrong_version[4] = 4;
+    let err = ObjectFile::from_bytes(&wrong_version).unwrap_err();
+    assert_eq!(err.error_type, ObjectErrorType::UnsupportedVersion);
+    assert_eq!(
+      err.info,
+      "unsupported object file version `4` (expected `3`)"
+    );
+
+    // written before functions / `var` args / arrays were encoded
+    let mut old_version = bytes.clone();
+    old_version[4..6].copy_from_slice(&1u16.to_le_bytes());
+    let err = ObjectFile::from_bytes(&old_version).unwrap_err();
+    assert_eq!(err.error_type, ObjectErrorType::UnsupportedVersion);
+    assert_eq!(
+      err.info,
+      "unsupported object file version `1` (expected `3`)"
+    );
+
+    // written before `l` was widened to 64 bits
+    let mut old_version = bytes.clone();
+    old_version[4..6].copy_from_slice(&2u16.to_le_bytes());
+    let err = ObjectFile::from_bytes(&old_version).unwrap_err();
+    assert_eq!(err.error_type, ObjectErrorType::UnsupportedVersion);
+
+    let mut bad_magic = bytes.clone();
+    bad_magic[0] = b'X';
+    let err = ObjectFile::from_bytes(&bad_magic).unwrap_err();
+    assert_eq!(err.error_type, ObjectErrorType::BadMagic);
+
+    // `INT` is the last section
+    let mut bad_width = bytes.clone();
+    bad_width[bytes.len() - 2] = 8;
+    let err = ObjectFile::from_bytes(&bad_width).unwrap_err();
+    assert_eq!(err.error_type, ObjectErrorType::Malformed);
+    assert_eq!(err.info, "`8` is not a valid integer width");
+
+    let mut trailing = bytes.clone();
+    trailing.push(0);
+    let err = ObjectFile::from_bytes(&trailing).unwrap_err();
+    assert_eq!(err.error_type, ObjectErrorType::Malformed);
+  }
+
+  #[test]
+  fn verifier_accepts_compiled_demo() {
+    use pl_0::vm::verifier::Verifier;
+    for name in [
+      "arith_test",
+      "array",
+      "bool_ops",
+      "cond_test",
+      "fib",
+      "fib_func",
+      "loops",
+      "multi_arg",
+      "nested_proc",
+      "nested_recursion",
+      "no_arg_proc",
+      "simple_proc",
+      "sum_a_to_b",
+      "test",
+      "var_param",
+    ] {
+      let content = file_to_string(PROJECT_ROOT.to_string() + "/examples/correct/" + name + ".pas");
+      let code = pl_0::compile(&content).unwrap();
+      assert!(
+        Verifier::new(&code).verify().is_ok(),
+        "`{}` failed to verify",
+        name
+      );
+    }
+  }
+
+  #[test]
+  fn verifier_rejects_demo() {
+    use RuntimeErrorType::*;
+    let errors_of = |asm: &str| {
+      let code = pl_0::pcode::PCodeManager::from_asm(asm).unwrap();
+      match VM::try_new(code) {
+        Ok(_) => vec![],
+        Err(errors) => errors
+          .into_iter()
+          .map(|err| (err.pc, err.error_type))
+          .collect::<Vec<_>>(),
+      }
+    };
+
+    assert_eq!(
+      errors_of("  INT 0 3\n  JMP 0 7\n  NIL 0 0\n  OPR 0 19\n  OPR 0 0\n"),
+      vec![
+        (1, InvalidJumpTarget),
+        (2, InvalidInstruction),
+        (3, InvalidInstruction)
+      ]
+    );
+    // `LOD 1 3` at static level 0
+    assert_eq!(
+      errors_of("  INT 0 4\n  LOD 1 3\n  OPR 0 14\n  OPR 0 0\n"),
+      vec![(1, InvalidLevel)]
+    );
+    // one more value is left on the data stack for each round
+    assert_eq!(
+      errors_of(
+        "
+          INT 0 4
+        loop:
+          LIT 0 1
+          LIT 0 1
+          JPC 0 loop
+          OPR 0 0
+        "
+      ),
+      vec![(1, StackImbalance)]
+    );
+    // pop from an empty data slice
+    assert_eq!(
+      errors_of("  OPR 0 2\n  OPR 0 0\n"),
+      vec![(0, StackImbalance)]
+    );
+    // falls off the end
+    assert_eq!(errors_of("  INT 0 3\n"), vec![(0, InvalidJumpTarget)]);
+    // no index is in `5 ..= 1`, and `LDX` needs an offset
+    assert_eq!(
+      errors_of("  INT 0 4\n  CHK 5 1\n  OPR 0 0\n"),
+      vec![(1, InvalidInstruction)]
+    );
+    assert_eq!(
+      errors_of("  LDX 0 3\n  OPR 0 0\n"),
+      vec![(0, StackImbalance)]
+    );
+    // `STI` needs both an address and a value
+    assert_eq!(
+      errors_of("  LDA 0 3\n  STI 0 0\n  OPR 0 0\n"),
+      vec![(1, StackImbalance)]
+    );
+    // the callee returns with and without a result
+    assert_eq!(
+      errors_of(
+        "
+          INT 0 3
+          CAL 0 f
+          OPR 0 0
+        f:
+          INT 0 3
+          LIT 0 1
+          JPC 0 ret
+          OPR 0 0
+        ret:
+          LIT 0 1
+          OPR 0 17
+        "
+      ),
+      vec![(1, StackImbalance)]
+    );
+    // `OPR 5 17` drops 5 values, but the data slice of the caller only has 4
+    assert_eq!(
+      errors_of(
+        "
+          INT 0 3
+          LIT 0 1
+          CAL 0 f
+          OPR 0 14
+          OPR 0 0
+        f:
+          INT 0 5
+          LIT 0 1
+          OPR 5 17
+        "
+      ),
+      vec![(2, StackImbalance)]
+    );
+  }
+
+  #[test]
+  fn diagnostic_demo() {
+    use pl_0::error::{
+      diagnostic::{to_sarif, Diagnostic},
+      object_error::{ObjectError, ObjectErrorType},
+      PL0Error,
+    };
+    use serde_json::{json, Value};
+
+    let content = file_to_string(PROJECT_ROOT.to_string() + "/examples/semantic/multi_def.pas");
+    let ast_entry = Parser::new(&content).parse().unwrap();
+    let errors = Translator::default().translate(&ast_entry).unwrap_err();
+    let json = Diagnostic::new(&errors[3], "multi_def.pas").to_json();
+    assert_eq!(
+      serde_json::from_str::<Value>(&json).unwrap(),
+      json!({
+        "file": "multi_def.pas",
+        "line": 10,
+        "column": 11,
+        "end_line": 10,
+        "end_column": 14,
+        "severity": "error",
+        "code": "E0202",
+        "error_type": "SemanticError",
+        "message": "`proc` is defined before",
+        "labels": [{ "line": 5, "col": 11, "text": "first defined here" }],
+        "notes": [],
+        "help": null,
+      })
+    );
+
+    // at the end of the file, right after a line break
+    let errors = Parser::new("program p;\nbegin\n  write(1)\n")
+      .parse()
+      .unwrap_err();
+    let json = Diagnostic::new(&errors[0], "eof.pas").to_json();
+    let json = serde_json::from_str::<Value>(&json).unwrap();
+    assert_eq!(json["message"], "Expected `End`, but got `None`");
+    assert_eq!(
+      (&json["line"], &json["column"], &json["end_column"]),
+      (&json!(4), &json!(1), &json!(1))
+    );
+
+    let content = file_to_string(PROJECT_ROOT.to_string() + "/examples/optimizer/dead_code.pas");
+    let mut optimizer = AstOptimizer::new(Parser::new(&content).parse().unwrap());
+    optimizer.optimize().unwrap();
+    let mut diagnostics = optimizer
+      .warnings
+      .iter()
+      .map(|warning| Diagnostic::new(warning, "dead_code.pas"))
+      .collect::<Vec<_>>();
+    assert!(!diagnostics.is_empty());
+    let object_error = ObjectError::new(ObjectErrorType::BadMagic, 0, "bad magic".into());
+    diagnostics.push(PL0Error::from(object_error).to_diagnostic("a.out"));
+
+    let sarif = serde_json::from_str::<Value>(&to_sarif(&diagnostics)).unwrap();
+    assert_eq!(sarif["version"], "2.1.0");
+    let results = sarif["runs"][0]["results"].as_array().unwrap();
+    assert_eq!(results.len(), diagnostics.len());
+    assert_eq!(results[0]["level"], "warning");
+    assert_eq!(
+      results[0]["locations"][0]["physicalLocation"]["region"]["startLine"],
+      diagnostics[0].line
+    );
+    // unknown location, no region
+    let last = results.last().unwrap();
+    assert_eq!(last["level"], "error");
+    assert_eq!(last["ruleId"], "BadMagic");
+    assert!(last["locations"][0]["physicalLocation"]
+      .get("region")
+      .is_none());
+  }
+
+  #[test]
+  fn cli_args_demo() {
+    use cli::{Command, EmitFormat, ErrorFormat, FrontEnd, OptLevel};
+    use pl_0::{
+      error::render::ColorChoice,
+      vm::arith::{IntSemantics, IntWidth, Overflow},
+    };
+    let parse = |args: &str| {
+      Cli::parse(
+        &args
+          .split_whitespace()
+          .map(String::from)
+          .collect::<Vec<_>>(),
+      )
+    };
+
+    let cli = parse("emit -O1 --format binary -o a.out /abs/path/a.pas").unwrap();
+    assert_eq!(cli.command, Command::Emit);
+    assert_eq!(cli.opt_level, OptLevel::O1);
+    assert_eq!(cli.format, EmitFormat::Binary);
+    assert_eq!(cli.output.as_deref(), Some("a.out"));
+    // absolute paths are kept as is
+    assert_eq!(cli.file, "/abs/path/a.pas");
+
+    let cli = parse("a.pas -i in.txt --no-prompt").unwrap();
+    assert_eq!(cli.command, Command::Run);
+    assert_eq!(cli.input.as_deref(), Some("in.txt"));
+    assert!(!cli.prompt);
+
+    assert_eq!(parse("").unwrap().command, Command::Help);
+    assert!(parse("run").is_err());
+    assert!(parse("run -O3 a.pas").is_err());
+    assert!(parse("run --foo a.pas").is_err());
+    assert!(parse("run a.pas b.pas").is_err());
+
+    assert_eq!(parse("check a.pas").unwrap().color, ColorChoice::Auto);
+    assert_eq!(
+      parse("check --color never a.pas").unwrap().color,
+      ColorChoice::Never
+    );
+    assert!(parse("check --color red a.pas").is_err());
+
+    assert_eq!(
+      parse("check --error-format sarif a.pas")
+        .unwrap()
+        .error_format,
+      ErrorFormat::Sarif
+    );
+    assert!(parse("check --error-format xml a.pas").is_err());
+
+    let cli = parse("run --int-width i16 --overflow saturating a.pas").unwrap();
+    assert_eq!(
+      cli.int_semantics(),
+      IntSemantics::new(IntWidth::I16, Overflow::Saturating)
+    );
+    let cli = parse("run a.pas").unwrap();
+    assert_eq!((cli.int_width, cli.overflow), (None, None));
+    assert_eq!(cli.int_semantics().width, IntWidth::I64);
+    assert!(parse("run --int-width i8 a.pas").is_err());
+    assert!(parse("run --overflow panic a.pas").is_err());
+
+    assert_eq!(
+      parse("parse --front-end pest a.pas").unwrap().front_end,
+      FrontEnd::Pest
+    );
+    assert_eq!(parse("run a.pas").unwrap().front_end, FrontEnd::Handwritten);
+    assert!(parse("run --front-end yacc a.pas").is_err());
+  }
+
+  #[test]
+  fn cli_exit_code_demo() {
+    let execute = |args: &[&str]| {
+      let args = args.iter().map(|arg| arg.to_string()).collect::<Vec<_>>();
+      Cli::parse(&args).unwrap().execute()
+    };
+    let path_of = |name: &str| PROJECT_ROOT.to_string() + "/examples/" + name;
+    let tmp_of = |name: &str| {
+      let path = std::env::temp_dir().join(format!("pl_0_cli_{}_{}", std::process::id(), name));
+      path.to_str().unwrap().to_string()
+    };
+
+    assert_eq!(
+      execute(&["check", &path_of("correct/nested_proc.pas")]),
+      cli::EXIT_OK
+    );
+    assert_eq!(
+      execute(&["check", &path_of("semantic/undef.pas")]),
+      cli::EXIT_COMPILE_ERROR
+    );
+    assert_eq!(
+      execute(&["check", &path_of("parser/multi_err.pas")]),
+      cli::EXIT_COMPILE_ERROR
+    );
+    assert_eq!(
+      execute(&["run", &path_of("runtime/div_by_zero.pas")]),
+      cli::EXIT_RUNTIME_ERROR
+    );
+    assert_eq!(
+      execute(&["run", &path_of("no_such_file.pas")]),
+      cli::EXIT_IO_ERROR
+    );
+    assert_eq!(
+      execute(&[
+        "check",
+        "--front-end",
+        "pest",
+        &path_of("correct/nested_proc.pas")
+      ]),
+      cli::EXIT_OK
+    );
+    assert_eq!(
+      execute(&[
+        "check",
+        "--front-end",
+        "pest",
+        &path_of("parser/multi_err.pas")
+      ]),
+      cli::EXIT_COMPILE_ERROR
+    );
+    // `check` never optimizes, division by constant zero is found by folding
+    let div_by_const_zero = path_of("optimizer/div_by_const_zero.pas");
+    assert_eq!(execute(&["check", "-O2", &div_by_const_zero]), cli::EXIT_OK);
+    assert_eq!(
+      execute(&["emit", "-O2", &div_by_const_zero]),
+      cli::EXIT_COMPILE_ERROR
+    );
+    assert_eq!(execute(&["explain", "E0202"]), cli::EXIT_OK);
+    assert_eq!(execute(&["explain", "E9999"]), EXIT_USAGE);
+
+    // source -> binary object -> run, with redirected stdin / stdout
+    let (object, input, output) = (tmp_of("sum.pl0"), tmp_of("in.txt"), tmp_of("out.txt"));
+    std::fs::write(&input, "1\n10\n").unwrap();
+    let source = path_of("correct/sum_a_to_b.pas");
+    assert_eq!(
+      execute(&["emit", "-f", "binary", "-o", &object, &source]),
+      cli::EXIT_OK
+    );
+    assert_eq!(
+      execute(&["run", "-i", &input, "-o", &output, &object]),
+      cli::EXIT_OK
+    );
+    assert_eq!(std::fs::read_to_string(&output).unwrap(), "55  \n");
+
+    // the integer semantics are kept in the object
+    let source = path_of("correct/sum_a_to_b.pas");
+    assert_eq!(
+      execute(&[
+        "emit",
+        "--int-width",
+        "i16",
+        "-f",
+        "binary",
+        "-o",
+        &object,
+        &source
+      ]),
+      cli::EXIT_OK
+    );
+    std::fs::write(&input, "1\n300\n").unwrap();
+    assert_eq!(
+      execute(&["run", "-i", &input, "-o", &output, &object]),
+      cli::EXIT_RUNTIME_ERROR
+    );
+    assert_eq!(
+      execute(&[
+        "run",
+        "--int-width",
+        "i16",
+        "-i",
+        &input,
+        "-o",
+        &output,
+        &object
+      ]),
+      cli::EXIT_RUNTIME_ERROR
+    );
+    assert_eq!(
+      execute(&[
+        "run",
+        "--int-width",
+        "i32",
+        "-i",
+        &input,
+        "-o",
+        &output,
+        &object
+      ]),
+      EXIT_USAGE
+    );
+    assert_eq!(
+      execute(&[
+        "run",
+        "--overflow",
+        "wrapping",
+        "-i",
+        &input,
+        "-o",
+        &output,
+        &object
+      ]),
+      EXIT_USAGE
+    );
+
+    // truncated object
+    let bytes = std::fs::read(&object).unwrap();
+    std::fs::write(&object, &bytes[..bytes.len() / 2]).unwrap();
+    assert_eq!(
+      execute(&["run", "-i", &input, "-o", &output, &object]),
+      cli::EXIT_IO_ERROR
+    );
+
+    for path in [object, input, output] {
+      std::fs::remove_file(path).unwrap();
+    }
+  }
+
+  #[test]
+  fn cli_peephole_count_demo() {
+    let source = file_to_string(PROJECT_ROOT.to_string() + "/examples/correct/nested_proc.pas");
+    let compile = |opt_level: &str| {
+      let args = ["emit", opt_level, "a.pas"].map(String::from);
+      Cli::parse(&args).unwrap().compile(&source).unwrap()
+    };
+    let (o1, o2) = (compile("-O1"), compile("-O2"));
+    assert_eq!(o1.n_removed, 0);
+    assert!(o2.n_removed > 0);
+    assert_eq!(
+      o1.object.code.pcode_list.len() - o2.object.code.pcode_list.len(),
+      o2.n_removed
+    );
+  }
+
+  #[test]
+  fn line_table_demo() {
+    use pl_0::ast::Location;
+
+    let content = file_to_string(PROJECT_ROOT.to_string() + "/examples/correct/nested_proc.pas");
+    let code = pl_0::compile(&content).unwrap();
+    assert_eq!(code.line_table.entry_list.len(), code.pcode_list.len());
+    // `cnt := cnt + 1` in `aa` (nested in `a`)
+    let pc = code
+      .line_table
+      .entry_list
+      .iter()
+      .position(|(location, _)| location.0 == 9)
+      .unwrap();
+    let line_info = code.line_table.lookup(pc).unwrap();
+    assert_eq!(line_info.proc_name, "a.aa");
+    assert_eq!(line_info.to_string(), "line 9, in a.aa");
+
+    // the prologue of a procedure is mapped to its identifier, not to the
+    // last statement translated before it
+    let int_infos = code
+      .pcode_list
+      .iter()
+      .enumerate()
+      .filter(|(_, pcode)| matches!(pcode.f, pl_0::pcode::PcodeType::INT) && pcode.a > 0)
+      .map(|(pc, _)| {
+        let line_info = code.line_table.lookup(pc).unwrap();
+        (line_info.location, line_info.proc_name.to_string())
+      })
+      .collect::<Vec<_>>();
+    assert_eq!(
+      int_infos,
+      vec![
+        (Location(7, 15), "a.aa".to_string()),
+        (Location(4, 13), "a".to_string()),
+        (Location(19, 13), "b".to_string()),
+        (Location(1, 1), "NestedProc".to_string()),
+      ]
+    );
   }
 }
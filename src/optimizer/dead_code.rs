@@ -0,0 +1,144 @@
+use crate::{
+  ast::*,
+  error::{code::ErrorCode, compile_error::CompileError, error_builder::CompileErrorBuilder},
+};
+
+/// Removes branches / loops which can never run (conditions should have
+/// been folded by `ConstFolder` first), and warns about statements which
+/// can never be reached (e.g. after `while 1 = 1 do ...`).
+#[derive(Debug, Default)]
+pub(super) struct DeadCodeEliminator {
+  pub(super) warnings: Vec<CompileError>,
+}
+
+impl DeadCodeEliminator {
+  pub(super) fn program(&mut self, expr: &mut ProgramExpr) {
+    self.block(&mut expr.block);
+  }
+
+  fn block(&mut self, expr: &mut BlockExpr) {
+    if let Some(proc) = &mut expr.proc {
+      self.procedure(proc);
+    }
+    self.body(&mut expr.body);
+  }
+
+  fn procedure(&mut self, expr: &mut ProcExpr) {
+    self.block(&mut expr.block);
+    for proc in &mut expr.procs {
+      self.procedure(proc);
+    }
+  }
+
+  /// Returns `true` if control never leaves `expr`
+  fn body(&mut self, expr: &mut BodyExpr) -> bool {
+    let mut diverge_pos = None;
+    for (pos, statement) in expr.statements.iter_mut().enumerate() {
+      if self.statement(statement) {
+        diverge_pos = Some(pos);
+        break;
+      }
+    }
+
+    if let Some(pos) = diverge_pos {
+      if let Some(statement) = expr.statements.get(pos + 1) {
+        let warning = CompileErrorBuilder::warning_template()
+          .with_span(statement.span())
+          .with_code(ErrorCode::UnreachableStatement)
+          .with_info("unreachable statement".to_string())
+          .build();
+        self.warnings.push(warning);
+        expr.statements.truncate(pos + 1);
+      }
+    }
+    expr.statements.retain(|statement| !is_nop(statement));
+
+    diverge_pos.is_some()
+  }
+
+  /// Returns `true` if control never leaves `expr`
+  fn statement(&mut self, expr: &mut StatementExpr) -> bool {
+    match expr {
+      StatementExpr::If {
+        l_exp,
+        then_statement,
+        else_statement,
+        ..
+      } => match l_exp.as_ref() {
+        LExpExpr::Const(true, _) => {
+          *expr = *std::mem::replace(then_statement, Box::new(nop()));
+          self.statement(expr)
+        }
+        LExpExpr::Const(false, _) => {
+          *expr = else_statement
+            .take()
+            .map_or_else(nop, |statement| *statement);
+          self.statement(expr)
+        }
+        _ => {
+          let then_diverges = self.statement(then_statement);
+          let else_diverges = match else_statement {
+            Some(statement) => self.statement(statement),
+            None => false,
+          };
+          if else_statement.as_deref().is_some_and(is_nop) {
+            *else_statement = None;
+          }
+          then_diverges && else_diverges
+        }
+      },
+      StatementExpr::While {
+        l_exp, statement, ..
+      } => match l_exp.as_ref() {
+        LExpExpr::Const(false, _) => {
+          *expr = nop();
+          false
+        }
+        LExpExpr::Const(true, _) => {
+          self.statement(statement);
+          true
+        }
+        _ => {
+          self.statement(statement);
+          false
+        }
+      },
+      // `statements` always run once, like a `<body>`
+      StatementExpr::Repeat {
+        statements,
+        l_exp,
+        span,
+      } => {
+        let mut body = BodyExpr {
+          statements: std::mem::take(statements),
+          span: *span,
+        };
+        let diverges = self.body(&mut body);
+        *statements = body.statements;
+        diverges || matches!(l_exp.as_ref(), LExpExpr::Const(false, _))
+      }
+      // `statement` may never run
+      StatementExpr::For { statement, .. } => {
+        self.statement(statement);
+        false
+      }
+      StatementExpr::Body { body, .. } => self.body(body),
+      _ => false,
+    }
+  }
+}
+
+/// An empty `<body>`, which generates nothing
+fn nop() -> StatementExpr {
+  StatementExpr::Body {
+    body: Box::new(BodyExpr {
+      statements: vec![],
+      span: Span::default(),
+    }),
+    span: Span::default(),
+  }
+}
+
+fn is_nop(expr: &StatementExpr) -> bool {
+  matches!(expr, StatementExpr::Body { body, .. } if body.statements.is_empty())
+}
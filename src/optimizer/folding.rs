@@ -0,0 +1,447 @@
+use std::collections::HashMap;
+
+use crate::{
+  ast::*,
+  error::{code::ErrorCode, compile_error::CompileError, error_builder::CompileErrorBuilder},
+  vm::arith::IntSemantics,
+};
+
+/// What a name refers to, in the innermost scope it's visible
+#[derive(Debug, Clone)]
+enum Binding {
+  Const(i64),
+  Var,
+  Array,
+  /// whether each arg is passed by reference
+  Proc(Vec<bool>),
+}
+
+/// Folds constant `<exp>` / `<l-exp>` and simplifies algebraic identities.
+///
+/// Nothing which may fail at runtime (overflow, division by a variable, ...)
+/// or hide a semantic error (undefined / non-r-value id) will be removed.
+/// Constants are computed with the same `IntSemantics` as the VM, so an
+/// overflow is only kept for runtime if it's `Overflow::Checked`.
+#[derive(Debug, Default)]
+pub(super) struct ConstFolder {
+  scope_list: Vec<HashMap<String, Binding>>,
+  pub(super) errors: Vec<CompileError>,
+  int: IntSemantics,
+}
+
+impl ConstFolder {
+  pub(super) fn new(int: IntSemantics) -> Self {
+    Self {
+      int,
+      ..Default::default()
+    }
+  }
+
+  fn bind(&mut self, name: &str, binding: Binding) {
+    if let Some(scope) = self.scope_list.last_mut() {
+      scope.insert(name.to_owned(), binding);
+    }
+  }
+
+  fn lookup(&self, name: &str) -> Option<Binding> {
+    self
+      .scope_list
+      .iter()
+      .rev()
+      .find_map(|scope| scope.get(name))
+      .cloned()
+  }
+
+  /// `<integer>` or a defined `var` / `const`, which is always safe to evaluate
+  ///
+  /// A function call isn't, it may have side effects (e.g. `write`)
+  fn is_atomic(&self, expr: &FactorExpr) -> bool {
+    match expr {
+      FactorExpr::Integer(_) => true,
+      FactorExpr::Id(id) => matches!(self.lookup(&id.0), Some(Binding::Var | Binding::Const(_))),
+      FactorExpr::Exp(..) | FactorExpr::Call { .. } | FactorExpr::Index { .. } => false,
+    }
+  }
+}
+
+impl ConstFolder {
+  pub(super) fn program(&mut self, expr: &mut ProgramExpr) {
+    self.scope_list.push(HashMap::new());
+    self.block(&mut expr.block);
+    self.scope_list.pop();
+  }
+
+  fn block(&mut self, expr: &mut BlockExpr) {
+    if let Some(const_decl) = &expr.const_decl {
+      for constant in &const_decl.constants {
+        self.bind(&constant.id.0, Binding::Const(constant.integer.0));
+      }
+    }
+    if let Some(var_decl) = &expr.var_decl {
+      for (id, bound) in var_decl.id_list.iter().zip(&var_decl.bounds) {
+        let binding = match bound {
+          Some(_) => Binding::Array,
+          None => Binding::Var,
+        };
+        self.bind(&id.0, binding);
+      }
+    }
+    if let Some(proc) = &mut expr.proc {
+      self.procedure(proc);
+    }
+    self.body(&mut expr.body);
+  }
+
+  fn procedure(&mut self, expr: &mut ProcExpr) {
+    self.bind(&expr.id.0, Binding::Proc(expr.by_ref.clone()));
+    self.scope_list.push(HashMap::new());
+    for arg in &expr.args {
+      self.bind(&arg.0, Binding::Var);
+    }
+    self.block(&mut expr.block);
+    self.scope_list.pop();
+    for proc in &mut expr.procs {
+      self.procedure(proc);
+    }
+  }
+
+  fn body(&mut self, expr: &mut BodyExpr) {
+    for statement in &mut expr.statements {
+      self.statement(statement);
+    }
+  }
+
+  fn statement(&mut self, expr: &mut StatementExpr) {
+    match expr {
+      StatementExpr::Id { index, exp, .. } => {
+        if let Some(index) = index {
+          self.exp(index);
+        }
+        self.exp(exp);
+      }
+      StatementExpr::If {
+        l_exp,
+        then_statement,
+        else_statement,
+        ..
+      } => {
+        self.l_exp(l_exp);
+        self.statement(then_statement);
+        if let Some(else_statement) = else_statement {
+          self.statement(else_statement);
+        }
+      }
+      StatementExpr::While {
+        l_exp, statement, ..
+      } => {
+        self.l_exp(l_exp);
+        self.statement(statement);
+      }
+      StatementExpr::Repeat {
+        statements, l_exp, ..
+      } => {
+        for statement in statements {
+          self.statement(statement);
+        }
+        self.l_exp(l_exp);
+      }
+      StatementExpr::For {
+        from,
+        to,
+        statement,
+        ..
+      } => {
+        self.exp(from);
+        self.exp(to);
+        self.statement(statement);
+      }
+      StatementExpr::Call { id, args, .. } => self.args(id, args),
+      StatementExpr::Body { body, .. } => self.body(body),
+      StatementExpr::Read { index_list, .. } => {
+        for index in index_list.iter_mut().flatten() {
+          self.exp(index);
+        }
+      }
+      StatementExpr::Write { exps, .. } => {
+        for exp in exps {
+          self.exp(exp);
+        }
+      }
+    }
+  }
+}
+
+impl ConstFolder {
+  fn l_exp(&mut self, expr: &mut LExpExpr) {
+    let span = expr.span();
+    let val = match expr {
+      LExpExpr::Exp {
+        l_exp, lop, r_exp, ..
+      } => {
+        self.exp(l_exp);
+        self.exp(r_exp);
+        match (const_of_exp(l_exp), const_of_exp(r_exp)) {
+          (Some(l), Some(r)) => Some(match lop.as_ref() {
+            LopExpr::Eq(_) => l == r,
+            LopExpr::Ne(_) => l != r,
+            LopExpr::Lt(_) => l < r,
+            LopExpr::Le(_) => l <= r,
+            LopExpr::Gt(_) => l > r,
+            LopExpr::Ge(_) => l >= r,
+          }),
+          _ => None,
+        }
+      }
+      LExpExpr::Odd { exp, .. } => {
+        self.exp(exp);
+        const_of_exp(exp).map(|val| val % 2 != 0)
+      }
+      LExpExpr::Not { l_exp, .. } => {
+        self.l_exp(l_exp);
+        const_of_l_exp(l_exp).map(|val| !val)
+      }
+      LExpExpr::And { l_exp, r_exp, .. } | LExpExpr::Or { l_exp, r_exp, .. } => {
+        self.l_exp(l_exp);
+        self.l_exp(r_exp);
+        match (const_of_l_exp(l_exp), const_of_l_exp(r_exp)) {
+          (Some(l), Some(r)) if matches!(expr, LExpExpr::And { .. }) => Some(l && r),
+          (Some(l), Some(r)) => Some(l || r),
+          _ => None,
+        }
+      }
+      LExpExpr::Const(..) => None,
+    };
+    if let Some(val) = val {
+      *expr = LExpExpr::Const(val, span);
+      return;
+    }
+
+    // `true and x` / `x and true` -> `x`, `false or x` / `x or false` -> `x`
+    //
+    // `false and x` / `true or x` are kept, `x` may hide a semantic error
+    let kept = match expr {
+      LExpExpr::And { l_exp, r_exp, .. } => identity_of(l_exp, r_exp, true),
+      LExpExpr::Or { l_exp, r_exp, .. } => identity_of(l_exp, r_exp, false),
+      _ => None,
+    };
+    if let Some(kept) = kept {
+      *expr = kept;
+    }
+  }
+
+  fn exp(&mut self, expr: &mut ExpExpr) {
+    self.term(&mut expr.term);
+    for (_, term) in &mut expr.aop_terms {
+      self.term(term);
+    }
+
+    // constant prefix, e.g. `-1 + 2 + x` -> `1 + x`
+    if let Some((first, mut span)) = const_of_term(&expr.term) {
+      let first = if expr.is_negative {
+        self.int.neg(first)
+      } else {
+        Some(first)
+      };
+      if let Some(mut val) = first {
+        let mut n_folded = 0;
+        for (aop, term) in &expr.aop_terms {
+          let Some((next, next_span)) = const_of_term(term) else {
+            break;
+          };
+          let folded = match aop.as_ref() {
+            AopExpr::Add(_) => self.int.add(val, next),
+            AopExpr::Sub(_) => self.int.sub(val, next),
+          };
+          match folded {
+            Some(folded) => val = folded,
+            None => break,
+          }
+          span = span.to(next_span);
+          n_folded += 1;
+        }
+        expr.is_negative = false;
+        expr.term = integer_term(val, span);
+        expr.aop_terms.drain(..n_folded);
+      }
+    }
+
+    // `x + 0` / `x - 0` -> `x`
+    expr
+      .aop_terms
+      .retain(|(_, term)| const_of_term(term).map(|(val, _)| val) != Some(0));
+
+    // `0 + x` -> `x`, `0 - x` -> `-x`
+    let is_zero = const_of_term(&expr.term).map(|(val, _)| val) == Some(0);
+    if is_zero && !expr.aop_terms.is_empty() {
+      let (aop, term) = expr.aop_terms.remove(0);
+      expr.is_negative = matches!(aop.as_ref(), AopExpr::Sub(_));
+      expr.term = term;
+    }
+  }
+
+  fn term(&mut self, expr: &mut TermExpr) {
+    self.factor(&mut expr.factor);
+    for (_, factor) in &mut expr.mop_factors {
+      self.factor(factor);
+    }
+
+    // `x / 0`
+    for (mop, factor) in &expr.mop_factors {
+      if matches!(mop.as_ref(), MopExpr::Div(_))
+        && const_of_factor(factor).is_some_and(|(val, _)| val == 0)
+      {
+        let err = CompileErrorBuilder::from(mop.location())
+          .with_code(ErrorCode::DivisionByConstZero)
+          .with_info("attempt to divide by zero".to_string())
+          .build();
+        self.errors.push(err);
+      }
+    }
+
+    // constant prefix, e.g. `2 * 3 * x` -> `6 * x`
+    if let Some((mut val, mut span)) = const_of_factor(&expr.factor) {
+      let mut n_folded = 0;
+      for (mop, factor) in &expr.mop_factors {
+        let Some((next, next_span)) = const_of_factor(factor) else {
+          break;
+        };
+        let folded = match mop.as_ref() {
+          MopExpr::Mul(_) => self.int.mul(val, next),
+          MopExpr::Div(_) => self.int.div(val, next),
+        };
+        match folded {
+          Some(folded) => val = folded,
+          None => break,
+        }
+        span = span.to(next_span);
+        n_folded += 1;
+      }
+      if n_folded > 0 {
+        *expr.factor = FactorExpr::Integer(Box::new(IntegerExpr(val, span)));
+        expr.mop_factors.drain(..n_folded);
+      }
+    }
+
+    // `x * 1` / `x / 1` -> `x`
+    expr
+      .mop_factors
+      .retain(|(_, factor)| const_of_factor(factor).map(|(val, _)| val) != Some(1));
+
+    // `1 * x` -> `x`
+    let is_one = const_of_factor(&expr.factor).map(|(val, _)| val) == Some(1);
+    if is_one
+      && matches!(expr.mop_factors.first(), Some((mop, _)) if matches!(mop.as_ref(), MopExpr::Mul(_)))
+    {
+      let (_, factor) = expr.mop_factors.remove(0);
+      expr.factor = factor;
+    }
+
+    // `0 * x` / `x * 0 * y` -> `0`
+    //
+    // only if nothing before `0` could overflow, and every factor is atomic
+    let factors = std::iter::once(&expr.factor)
+      .chain(expr.mop_factors.iter().map(|(_, factor)| factor))
+      .collect::<Vec<_>>();
+    let zero = factors
+      .iter()
+      .enumerate()
+      .find_map(|(i, factor)| match const_of_factor(factor) {
+        Some((0, _)) => Some(i),
+        _ => None,
+      });
+    if let Some(pos) = zero {
+      let all_mul = expr
+        .mop_factors
+        .iter()
+        .all(|(mop, _)| matches!(mop.as_ref(), MopExpr::Mul(_)));
+      let all_atomic = factors.iter().all(|factor| self.is_atomic(factor));
+      if factors.len() > 1 && pos <= 1 && all_mul && all_atomic {
+        *expr.factor = FactorExpr::Integer(Box::new(IntegerExpr(0, expr.span)));
+        expr.mop_factors.clear();
+      }
+    }
+  }
+
+  fn factor(&mut self, expr: &mut FactorExpr) {
+    match expr {
+      FactorExpr::Id(id) => {
+        if let Some(Binding::Const(val)) = self.lookup(&id.0) {
+          *expr = FactorExpr::Integer(Box::new(IntegerExpr(val, id.1)));
+        }
+      }
+      FactorExpr::Integer(_) => (),
+      FactorExpr::Exp(exp, _) => {
+        self.exp(exp);
+        // `(x)` -> `x`
+        if !exp.is_negative && exp.aop_terms.is_empty() && exp.term.mop_factors.is_empty() {
+          *expr = exp.term.factor.as_ref().to_owned();
+        }
+      }
+      FactorExpr::Call { id, args, .. } => self.args(id, args),
+      FactorExpr::Index { index, .. } => self.exp(index),
+    }
+  }
+
+  /// An arg passed by reference is left as is, `a * 1` must not become a
+  /// variable `a`
+  fn args(&mut self, id: &IdExpr, args: &mut [Box<ExpExpr>]) {
+    let by_ref = match self.lookup(&id.0) {
+      Some(Binding::Proc(by_ref)) => by_ref,
+      _ => vec![],
+    };
+    for (i, arg) in args.iter_mut().enumerate() {
+      if !by_ref.get(i).copied().unwrap_or_default() {
+        self.exp(arg);
+      }
+    }
+  }
+}
+
+fn integer_term(val: i64, span: Span) -> Box<TermExpr> {
+  Box::new(TermExpr {
+    factor: Box::new(FactorExpr::Integer(Box::new(IntegerExpr(val, span)))),
+    mop_factors: vec![],
+    span,
+  })
+}
+
+fn const_of_factor(expr: &FactorExpr) -> Option<(i64, Span)> {
+  match expr {
+    FactorExpr::Integer(integer) => Some((integer.0, integer.1)),
+    _ => None,
+  }
+}
+
+fn const_of_term(expr: &TermExpr) -> Option<(i64, Span)> {
+  if expr.mop_factors.is_empty() {
+    const_of_factor(&expr.factor)
+  } else {
+    None
+  }
+}
+
+fn const_of_l_exp(expr: &LExpExpr) -> Option<bool> {
+  match expr {
+    LExpExpr::Const(val, _) => Some(*val),
+    _ => None,
+  }
+}
+
+/// The other side of `l op r`, if one side is the identity `unit` of `op`
+fn identity_of(l: &LExpExpr, r: &LExpExpr, unit: bool) -> Option<LExpExpr> {
+  if const_of_l_exp(l) == Some(unit) {
+    Some(r.to_owned())
+  } else if const_of_l_exp(r) == Some(unit) {
+    Some(l.to_owned())
+  } else {
+    None
+  }
+}
+
+fn const_of_exp(expr: &ExpExpr) -> Option<i64> {
+  if !expr.is_negative && expr.aop_terms.is_empty() {
+    const_of_term(&expr.term).map(|(val, _)| val)
+  } else {
+    None
+  }
+}
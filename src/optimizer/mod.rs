@@ -1,22 +1,54 @@
-use crate::ast::ProgramExpr;
+use self::{dead_code::DeadCodeEliminator, folding::ConstFolder};
+use crate::{ast::ProgramExpr, error::compile_error::CompileError, vm::arith::IntSemantics};
+
+mod dead_code;
+mod folding;
+pub mod peephole;
 
 #[derive(Debug, Clone)]
 pub struct AstOptimizer {
   ast_entry: Box<ProgramExpr>,
+  pub warnings: Vec<CompileError>,
+  /// constants are folded as the VM computes them
+  int: IntSemantics,
 }
 
 impl AstOptimizer {
-  pub fn optimize(self) -> Box<ProgramExpr> {
-    println!();
-    println!("Unimplemented AstOptimizer, use the raw AST instead");
-    println!("Successfully optimized the AST");
-    println!();
-    self.ast_entry
+  /// Optimize the AST:
+  ///
+  /// - fold constant `<exp>` / `<l-exp>` (`const` symbols included)
+  /// - simplify algebraic identities (`x * 1`, `x + 0`, `x * 0`, ...)
+  /// - remove `if` / `while` branches which can never run
+  ///
+  /// Diagnostics found on the way (e.g. division by constant zero) are
+  /// returned as `Err`, warnings (e.g. unreachable statements) are kept in
+  /// `warnings`, nothing will be printed.
+  pub fn optimize(&mut self) -> Result<Box<ProgramExpr>, Vec<CompileError>> {
+    let mut folder = ConstFolder::new(self.int);
+    folder.program(&mut self.ast_entry);
+    if !folder.errors.is_empty() {
+      return Err(folder.errors);
+    }
+
+    let mut eliminator = DeadCodeEliminator::default();
+    eliminator.program(&mut self.ast_entry);
+    self.warnings = eliminator.warnings;
+
+    Ok(self.ast_entry.clone())
   }
 }
 
 impl AstOptimizer {
   pub fn new(ast_entry: Box<ProgramExpr>) -> Self {
-    Self { ast_entry }
+    Self {
+      ast_entry,
+      warnings: vec![],
+      int: IntSemantics::default(),
+    }
+  }
+
+  pub fn with_int_semantics(mut self, int: IntSemantics) -> Self {
+    self.int = int;
+    self
   }
 }
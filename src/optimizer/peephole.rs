@@ -0,0 +1,175 @@
+use std::collections::HashSet;
+
+use crate::pcode::{PCodeManager, Pcode, PcodeType};
+
+/// Peephole optimizer over `PCodeManager`:
+///
+/// - `JMP` / `JPC` / `CAL` to a `JMP` -> jump to the final target directly
+/// - `JMP` to the next pcode -> removed
+/// - `LIT a` + `OPR 1` -> `LIT -a`
+/// - `INT 0 0` -> removed
+/// - `STO x` + `LOD x` -> `OPR 0 18` (duplicate the top) + `STO x`, which
+///   doesn't read `x` back
+///
+/// Every `JMP` / `JPC` / `CAL` target is relocated afterwards, and
+/// `line_table` is kept in sync.
+///
+/// Addresses kept elsewhere (e.g. procedures in `SymTable`) could be moved by
+/// `relocate`.
+#[derive(Debug, Clone)]
+pub struct PeepholeOptimizer {
+  code: PCodeManager,
+  /// how many pcode have been removed
+  pub n_removed: usize,
+  /// original pc -> current pc
+  relocation: Vec<usize>,
+}
+
+impl PeepholeOptimizer {
+  pub fn new(code: PCodeManager) -> Self {
+    let relocation = (0..=code.pcode_list.len()).collect();
+    Self {
+      code,
+      n_removed: 0,
+      relocation,
+    }
+  }
+
+  /// New position of the pcode at `pc` before optimization (or the first
+  /// pcode after it, if it has been removed)
+  pub fn relocate(&self, pc: usize) -> usize {
+    self.relocation.get(pc).copied().unwrap_or(pc)
+  }
+
+  /// Optimize until nothing changes, `n_removed` will be updated
+  pub fn optimize(&mut self) -> PCodeManager {
+    loop {
+      self.thread_jumps();
+      let keep_list = self.rewrite();
+      if keep_list.iter().all(|&keep| keep) {
+        break;
+      }
+      self.compact(&keep_list);
+    }
+    self.code.clone()
+  }
+}
+
+impl PeepholeOptimizer {
+  fn is_jump(f: PcodeType) -> bool {
+    matches!(f, PcodeType::JMP | PcodeType::JPC | PcodeType::CAL)
+  }
+
+  fn jump_targets(&self) -> HashSet<i64> {
+    self
+      .code
+      .pcode_list
+      .iter()
+      .filter(|pcode| Self::is_jump(pcode.f))
+      .map(|pcode| pcode.a)
+      .collect()
+  }
+
+  /// `JMP` / `JPC` / `CAL` to a `JMP` -> jump to the final target directly
+  fn thread_jumps(&mut self) {
+    let pcode_list = &mut self.code.pcode_list;
+    for pc in 0..pcode_list.len() {
+      if !Self::is_jump(pcode_list[pc].f) {
+        continue;
+      }
+      let mut target = pcode_list[pc].a;
+      // bounded, in case of `JMP` cycles
+      for _ in 0..pcode_list.len() {
+        match usize::try_from(target).ok().and_then(|t| pcode_list.get(t)) {
+          Some(next) if matches!(next.f, PcodeType::JMP) && next.a != target => target = next.a,
+          _ => break,
+        }
+      }
+      pcode_list[pc].set_a(target);
+    }
+  }
+
+  /// Rewrite in place, returns whether each pcode should be kept
+  fn rewrite(&mut self) -> Vec<bool> {
+    let targets = self.jump_targets();
+    let pcode_list = &mut self.code.pcode_list;
+    let mut keep_list = vec![true; pcode_list.len()];
+
+    let mut pc = 0;
+    while pc < pcode_list.len() {
+      let pcode = pcode_list[pc];
+      match pcode.f {
+        PcodeType::JMP if pcode.a == pc as i64 + 1 => keep_list[pc] = false,
+        PcodeType::INT if pcode.a == 0 => keep_list[pc] = false,
+        PcodeType::STO => {
+          let next = pcode_list.get(pc + 1);
+          let is_reload = next.is_some_and(|next| {
+            matches!(next.f, PcodeType::LOD) && next.l == pcode.l && next.a == pcode.a
+          });
+          if is_reload && !targets.contains(&(pc as i64 + 1)) {
+            pcode_list[pc] = Pcode {
+              f: PcodeType::OPR,
+              l: 0,
+              a: 18,
+            };
+            pcode_list[pc + 1] = pcode;
+            // `OPR 0 18` takes the place of the read
+            self.code.line_table.entry_list.swap(pc, pc + 1);
+            pc += 1;
+          }
+        }
+        PcodeType::LIT => {
+          let next = pcode_list.get(pc + 1);
+          let is_neg = next.is_some_and(|next| matches!(next.f, PcodeType::OPR) && next.a == 1);
+          if is_neg && !targets.contains(&(pc as i64 + 1)) {
+            if let Some(val) = pcode.a.checked_neg() {
+              pcode_list[pc].set_a(val);
+              keep_list[pc + 1] = false;
+              pc += 1;
+            }
+          }
+        }
+        _ => (),
+      }
+      pc += 1;
+    }
+
+    keep_list
+  }
+
+  /// Drop pcode which is not kept, then relocate every jump target
+  fn compact(&mut self, keep_list: &[bool]) {
+    // new_pos[pc] = how many pcode are kept before `pc`, which is also the
+    // new position of the first kept pcode at / after `pc`
+    let mut new_pos = Vec::with_capacity(keep_list.len() + 1);
+    let mut n_kept = 0;
+    for &keep in keep_list {
+      new_pos.push(n_kept);
+      n_kept += keep as usize;
+    }
+    new_pos.push(n_kept);
+
+    let mut keep_iter = keep_list.iter();
+    self.code.pcode_list.retain(|_| *keep_iter.next().unwrap());
+    let mut keep_iter = keep_list.iter();
+    self
+      .code
+      .line_table
+      .entry_list
+      .retain(|_| *keep_iter.next().unwrap());
+
+    for pcode in &mut self.code.pcode_list {
+      if !Self::is_jump(pcode.f) {
+        continue;
+      }
+      if let Some(&pos) = usize::try_from(pcode.a).ok().and_then(|a| new_pos.get(a)) {
+        pcode.set_a(pos as i64);
+      }
+    }
+
+    for pos in &mut self.relocation {
+      *pos = new_pos[*pos];
+    }
+    self.n_removed += keep_list.len() - n_kept;
+  }
+}
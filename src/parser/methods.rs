@@ -8,15 +8,18 @@ impl<'a> Parser<'a> {
   /// ```bnf
   /// <prog> -> program <id> ; <block>
   pub(super) fn parse_program(&mut self) -> Option<Box<ProgramExpr>> {
+    let start = self.start_span();
     self.consume_next(Token::Program);
     let id = self.parse_id();
     self.consume_next(Token::Semicolon);
     let block = self.parse_block();
-    if id.is_some() && block.is_some() {
-      let (id, block) = (id.unwrap(), block.unwrap());
-      Some(Box::new(ProgramExpr { id, block }))
-    } else {
-      None
+    match (id, block) {
+      (Some(id), Some(block)) => Some(Box::new(ProgramExpr {
+        id,
+        block,
+        span: self.span_from(start),
+      })),
+      _ => None,
     }
   }
 
@@ -33,9 +36,10 @@ impl<'a> Parser<'a> {
         }
         let err = CompileErrorBuilder::syntax_error_template()
           .with_lexer_ref(&self.lexer)
+          .with_code(ErrorCode::ExpectedIdentifier)
           .with_info("Expected <id> field, but not found!".to_string())
           .build();
-        eprintln!("{}", err);
+        self.errors.push(err);
         None
       }
     }
@@ -43,7 +47,14 @@ impl<'a> Parser<'a> {
 
   /// ```bnf
   /// <integer> -> @digit { @digit }
+  ///
+  /// An integer literal out of range is a lexical error already, it's parsed
+  /// as a poisoned `0` so that it's reported only once
   fn parse_integer(&mut self) -> Option<Box<IntegerExpr>> {
+    if self.peek_integer_overflow() {
+      self.consume_next_integer().unwrap_err();
+      return Some(Box::new(IntegerExpr(0, self.lexer.as_ref().into())));
+    }
     match self.consume_next_integer() {
       Ok(integer) => Some(Box::new(IntegerExpr(integer, self.lexer.as_ref().into()))),
       Err(is_lexical_error) => {
@@ -54,9 +65,10 @@ impl<'a> Parser<'a> {
         }
         let err = CompileErrorBuilder::syntax_error_template()
           .with_lexer_ref(&self.lexer)
+          .with_code(ErrorCode::ExpectedInteger)
           .with_info("Expected <integer> field, but not found!".to_string())
           .build();
-        eprintln!("{}", err);
+        self.errors.push(err);
         None
       }
     }
@@ -65,6 +77,7 @@ impl<'a> Parser<'a> {
   /// ```bnf
   /// <block> -> [<const-decl>][<var-decl>][<proc>]<body>
   fn parse_block(&mut self) -> Option<Box<BlockExpr>> {
+    let start = self.start_span();
     // [<const-decl>]
     let const_decl = if self.match_next(Token::Const) {
       self.parse_const_decl()
@@ -78,7 +91,7 @@ impl<'a> Parser<'a> {
       None
     };
     // [<proc>]
-    let proc = if self.match_next(Token::Procedure) {
+    let proc = if self.match_next(Token::Procedure) || self.match_next(Token::Function) {
       self.parse_proc()
     } else {
       None
@@ -90,6 +103,7 @@ impl<'a> Parser<'a> {
         var_decl,
         proc,
         body,
+        span: self.span_from(start),
       })
     })
   }
@@ -99,6 +113,7 @@ impl<'a> Parser<'a> {
   fn parse_const_decl(&mut self) -> Option<Box<ConstDeclExpr>> {
     let mut errored = false;
     let mut constants = vec![];
+    let start = self.start_span();
     self.consume_next(Token::Const);
     match self.parse_const() {
       Some(c) => constants.push(c),
@@ -116,38 +131,52 @@ impl<'a> Parser<'a> {
       return None;
     }
     self.consume_next(Token::Semicolon);
-    Some(Box::new(ConstDeclExpr { constants }))
+    Some(Box::new(ConstDeclExpr {
+      constants,
+      span: self.span_from(start),
+    }))
   }
 
   /// ```bnf
   /// <const> -> <id> := <integer>
   fn parse_const(&mut self) -> Option<Box<ConstExpr>> {
+    let start = self.start_span();
     let id_expr = self.parse_id();
     self.consume_next(Token::EqSign);
     let integer_expr = self.parse_integer();
-    if id_expr.is_some() && integer_expr.is_some() {
-      let (id, integer) = (id_expr.unwrap(), integer_expr.unwrap());
-      Some(Box::new(ConstExpr { id, integer }))
-    } else {
-      None
+    match (id_expr, integer_expr) {
+      (Some(id), Some(integer)) => Some(Box::new(ConstExpr {
+        id,
+        integer,
+        span: self.span_from(start),
+      })),
+      _ => None,
     }
   }
 
   /// ```bnf
-  /// <var-decl> -> var <id> {, <id>} ;
+  /// <var-decl> -> var <var-def> {, <var-def>} ;
   fn parse_var_decl(&mut self) -> Option<Box<VarDeclExpr>> {
     let mut errored = false;
     let mut id_list = vec![];
+    let mut bounds = vec![];
+    let start = self.start_span();
     self.consume_next(Token::Var);
-    match self.parse_id() {
-      Some(id) => id_list.push(id),
+    match self.parse_var_def() {
+      Some((id, bound)) => {
+        id_list.push(id);
+        bounds.push(bound);
+      }
       None => errored = true,
     }
-    // {, <id>}
+    // {, <var-def>}
     while self.match_next(Token::Comma) {
       self.consume_next(Token::Comma);
-      match self.parse_id() {
-        Some(id) => id_list.push(id),
+      match self.parse_var_def() {
+        Some((id, bound)) => {
+          id_list.push(id);
+          bounds.push(bound);
+        }
         None => errored = true,
       }
     }
@@ -155,28 +184,78 @@ impl<'a> Parser<'a> {
     if errored {
       return None;
     }
-    Some(Box::new(VarDeclExpr { id_list }))
+    Some(Box::new(VarDeclExpr {
+      id_list,
+      bounds,
+      span: self.span_from(start),
+    }))
+  }
+
+  /// ```bnf
+  /// <var-def> -> <id> [<bound>]
+  fn parse_var_def(&mut self) -> Option<(Box<IdExpr>, Option<Box<BoundExpr>>)> {
+    let id = self.parse_id();
+    // [<bound>]
+    if !self.match_next(Token::BracketL) {
+      return id.map(|id| (id, None));
+    }
+    let bound = self.parse_bound()?;
+    id.map(|id| (id, Some(bound)))
   }
 
   /// ```bnf
-  /// <proc> -> procedure <id> ([<id> {, <id>}]) ; <block> {; <proc>}
+  /// <bound> -> "[" <integer> [.. <integer>] "]"
+  fn parse_bound(&mut self) -> Option<Box<BoundExpr>> {
+    let start = self.start_span();
+    self.consume_next(Token::BracketL);
+    let lo = self.parse_integer();
+    // [.. <integer>]
+    let hi = if self.match_next(Token::Range) {
+      self.consume_next(Token::Range);
+      Some(self.parse_integer())
+    } else {
+      None
+    };
+    self.consume_next(Token::BracketR);
+    let span = self.span_from(start);
+    match (lo?, hi) {
+      (lo, None) => Some(Box::new(BoundExpr::Len(lo, span))),
+      (lo, Some(hi)) => Some(Box::new(BoundExpr::Range(lo, hi?, span))),
+    }
+  }
+
+  /// ```bnf
+  /// <proc> -> (procedure | function) <id> ([<param> {, <param>}]) ; <block> {; <proc>}
   fn parse_proc(&mut self) -> Option<Box<ProcExpr>> {
-    self.consume_next(Token::Procedure);
+    let start = self.start_span();
+    let is_function = self.match_next(Token::Function);
+    if is_function {
+      self.consume_next(Token::Function);
+    } else {
+      self.consume_next(Token::Procedure);
+    }
     let id = self.parse_id();
     self.consume_next(Token::ParL);
     let mut args: Vec<Box<IdExpr>> = vec![];
+    let mut by_ref = vec![];
     let mut errored = false;
-    // [<id>]
+    // [<param>]
     if !self.match_next(Token::ParR) {
-      match self.parse_id() {
-        Some(id) => args.push(id),
+      match self.parse_param() {
+        Some((id, is_ref)) => {
+          args.push(id);
+          by_ref.push(is_ref);
+        }
         None => errored = true,
       }
-      // [<id> {, <id>}]
+      // [<param> {, <param>}]
       while self.match_next(Token::Comma) {
         self.consume_next(Token::Comma);
-        match self.parse_id() {
-          Some(id) => args.push(id),
+        match self.parse_param() {
+          Some((id, is_ref)) => {
+            args.push(id);
+            by_ref.push(is_ref);
+          }
           None => errored = true,
         }
       }
@@ -187,6 +266,7 @@ impl<'a> Parser<'a> {
     self.consume_next(Token::ParR);
     self.consume_next(Token::Semicolon);
     let block = self.parse_block();
+    let span = self.span_from(start);
     let mut procs = vec![];
     // {; <proc>}
     while self.match_next(Token::Semicolon) {
@@ -199,17 +279,30 @@ impl<'a> Parser<'a> {
     if errored {
       return None;
     }
-    if id.is_some() && block.is_some() {
-      let (id, block) = (id.unwrap(), block.unwrap());
-      Some(Box::new(ProcExpr {
+    match (id, block) {
+      (Some(id), Some(block)) => Some(Box::new(ProcExpr {
         id,
+        is_function,
         args,
+        by_ref,
         block,
         procs,
-      }))
-    } else {
-      None
+        span,
+      })),
+      _ => None,
+    }
+  }
+
+  /// ```bnf
+  /// <param> -> [var] <id>
+  ///
+  /// `true` if it's passed by reference
+  fn parse_param(&mut self) -> Option<(Box<IdExpr>, bool)> {
+    let is_ref = self.match_next(Token::Var);
+    if is_ref {
+      self.consume_next(Token::Var);
     }
+    self.parse_id().map(|id| (id, is_ref))
   }
 
   /// ```bnf
@@ -217,6 +310,7 @@ impl<'a> Parser<'a> {
   fn parse_body(&mut self) -> Option<Box<BodyExpr>> {
     let mut statements = vec![];
     let mut errored = false;
+    let start = self.start_span();
     self.consume_next(Token::Begin);
     match self.parse_statement() {
       Some(stmt) => statements.push(stmt),
@@ -234,20 +328,26 @@ impl<'a> Parser<'a> {
       return None;
     }
     self.consume_next(Token::End);
-    Some(Box::new(BodyExpr { statements }))
+    Some(Box::new(BodyExpr {
+      statements,
+      span: self.span_from(start),
+    }))
   }
 
   /// ```bnf
-  /// <statement> -> <id> := <exp>
+  /// <statement> -> <var> := <exp>
   ///               | if <l-exp> then <statement> [else <statement>]
   ///               | while <l-exp> do <statement>
-  ///               | call <id> ([<exp> {, <exp>}])
-  ///               | read (<id> {, <id>})
+  ///               | repeat <statement> {; <statement>} until <l-exp>
+  ///               | for <id> := <exp> <dir> <exp> do <statement>
+  ///               | call <id> <args>
+  ///               | read (<var> {, <var>})
   ///               | write (<exp> {, <exp>})
   ///               | <body>
-  ///               | read (<id> {, <id>})
+  ///               | read (<var> {, <var>})
   ///               | write (<exp> {, <exp>})
   fn parse_statement(&mut self) -> Option<Box<StatementExpr>> {
+    let start = self.start_span();
     match self.lexer.peek() {
       Some(token) => match token {
         Token::If => {
@@ -261,15 +361,14 @@ impl<'a> Parser<'a> {
           } else {
             None
           };
-          if l_exp.is_some() && then_statement.is_some() {
-            let (l_exp, then_statement) = (l_exp.unwrap(), then_statement.unwrap());
-            Some(Box::new(StatementExpr::If {
+          match (l_exp, then_statement) {
+            (Some(l_exp), Some(then_statement)) => Some(Box::new(StatementExpr::If {
               l_exp,
               then_statement,
               else_statement,
-            }))
-          } else {
-            None
+              span: self.span_from(start),
+            })),
+            _ => None,
           }
         }
         Token::While => {
@@ -277,59 +376,106 @@ impl<'a> Parser<'a> {
           let l_exp = self.parse_l_exp();
           self.consume_next(Token::Do);
           let statement = self.parse_statement();
-          if l_exp.is_some() && statement.is_some() {
-            let (l_exp, statement) = (l_exp.unwrap(), statement.unwrap());
-            Some(Box::new(StatementExpr::While { l_exp, statement }))
-          } else {
-            None
+          match (l_exp, statement) {
+            (Some(l_exp), Some(statement)) => Some(Box::new(StatementExpr::While {
+              l_exp,
+              statement,
+              span: self.span_from(start),
+            })),
+            _ => None,
           }
         }
-        Token::Call => {
+        Token::Repeat => {
           let mut errored = false;
-          self.consume_next(Token::Call);
-          let id = self.parse_id();
-          self.consume_next(Token::ParL);
-          let mut args = vec![];
-          // ([<exp>])
-          if !self.match_next(Token::ParR) {
-            match self.parse_exp() {
-              Some(exp) => args.push(exp),
+          self.consume_next(Token::Repeat);
+          let mut statements = vec![];
+          match self.parse_statement() {
+            Some(stmt) => statements.push(stmt),
+            None => errored = true,
+          }
+          // {; <statement>}
+          while self.match_next(Token::Semicolon) {
+            self.consume_next(Token::Semicolon);
+            match self.parse_statement() {
+              Some(stmt) => statements.push(stmt),
               None => errored = true,
             }
-            // ([<exp> {, <exp>}])
-            while self.match_next(Token::Comma) {
-              self.consume_next(Token::Comma);
-              match self.parse_exp() {
-                Some(exp) => args.push(exp),
-                None => errored = true,
-              }
-            }
           }
-          self.consume_next(Token::ParR);
+          self.consume_next(Token::Until);
+          let l_exp = self.parse_l_exp();
           if errored {
             return None;
           }
-          if id.is_some() {
-            let id = id.unwrap();
-            Some(Box::new(StatementExpr::Call { id, args }))
+          l_exp.map(|l_exp| {
+            Box::new(StatementExpr::Repeat {
+              statements,
+              l_exp,
+              span: self.span_from(start),
+            })
+          })
+        }
+        Token::For => {
+          self.consume_next(Token::For);
+          let id = self.parse_id();
+          self.consume_next(Token::EqSign);
+          let from = self.parse_exp();
+          // (to | downto)
+          let is_downto = self.match_next(Token::Downto);
+          if is_downto {
+            self.consume_next(Token::Downto);
           } else {
-            None
+            self.consume_next(Token::To);
+          }
+          let to = self.parse_exp();
+          self.consume_next(Token::Do);
+          let statement = self.parse_statement();
+          match (id, from, to, statement) {
+            (Some(id), Some(from), Some(to), Some(statement)) => {
+              Some(Box::new(StatementExpr::For {
+                id,
+                from,
+                is_downto,
+                to,
+                statement,
+                span: self.span_from(start),
+              }))
+            }
+            _ => None,
           }
         }
+        Token::Call => {
+          self.consume_next(Token::Call);
+          let id = self.parse_id();
+          let args = self.parse_args()?;
+          id.map(|id| {
+            Box::new(StatementExpr::Call {
+              id,
+              args,
+              span: self.span_from(start),
+            })
+          })
+        }
         Token::Read => {
           let mut errored = false;
           self.consume_next(Token::Read);
           self.consume_next(Token::ParL);
           let mut id_list = vec![];
-          match self.parse_id() {
-            Some(id) => id_list.push(id),
+          let mut index_list = vec![];
+          match self.parse_var() {
+            Some((id, index)) => {
+              id_list.push(id);
+              index_list.push(index);
+            }
             None => errored = true,
           }
-          // (<id> {, <id>})
+          // (<var> {, <var>})
           while self.match_next(Token::Comma) {
             self.consume_next(Token::Comma);
-            match self.parse_id() {
-              Some(id) => id_list.push(id),
+            match self.parse_var() {
+              Some((id, index)) => {
+                id_list.push(id);
+                index_list.push(index);
+              }
               None => errored = true,
             }
           }
@@ -337,7 +483,11 @@ impl<'a> Parser<'a> {
           if errored {
             return None;
           }
-          Some(Box::new(StatementExpr::Read { id_list }))
+          Some(Box::new(StatementExpr::Read {
+            id_list,
+            index_list,
+            span: self.span_from(start),
+          }))
         }
         Token::Write => {
           let mut errored = false;
@@ -360,21 +510,30 @@ impl<'a> Parser<'a> {
           if errored {
             return None;
           }
-          Some(Box::new(StatementExpr::Write { exps }))
+          Some(Box::new(StatementExpr::Write {
+            exps,
+            span: self.span_from(start),
+          }))
         }
         Token::Begin => {
           let body = self.parse_body();
-          body.map(|body| Box::new(StatementExpr::Body { body }))
+          body.map(|body| {
+            let span = body.span;
+            Box::new(StatementExpr::Body { body, span })
+          })
         }
         Token::Identifier(_) => {
-          let id = self.parse_id();
+          let var = self.parse_var();
           self.consume_next(Token::EqSign);
           let exp = self.parse_exp();
-          if id.is_some() && exp.is_some() {
-            let (id, exp) = (id.unwrap(), exp.unwrap());
-            Some(Box::new(StatementExpr::Id { id, exp }))
-          } else {
-            None
+          match (var, exp) {
+            (Some((id, index)), Some(exp)) => Some(Box::new(StatementExpr::Id {
+              id,
+              index,
+              exp,
+              span: self.span_from(start),
+            })),
+            _ => None,
           }
         }
         Token::LexicalError(_) => {
@@ -384,60 +543,170 @@ impl<'a> Parser<'a> {
           }
           let err = CompileErrorBuilder::syntax_error_template()
             .with_lexer_ref(&self.lexer)
+            .with_code(ErrorCode::ExpectedStatement)
             .with_info("Expected <statement> field, but not found!".to_string())
             .build();
           self.consume_next(Token::EqSign);
           let _exp = self.parse_exp();
-          eprintln!("{}", err);
+          self.errors.push(err);
           None
         }
         _ => {
           let unexpected_token = token.to_owned();
           let err = CompileErrorBuilder::syntax_error_template()
             .with_lexer_ref(&self.lexer)
+            .with_code(ErrorCode::ExpectedStatement)
             .with_info(format!(
               "Expected <statement> field, but got an unmatchable token `{}`",
               unexpected_token
             ))
             .build();
-          eprintln!("{}", err);
+          self.errors.push(err);
           None
         }
       },
       None => {
         let err = CompileErrorBuilder::syntax_error_template()
           .with_lexer_ref(&self.lexer)
+          .with_code(ErrorCode::ExpectedStatement)
           .with_info("Expected <statement> field, but got `None`".to_string())
           .build();
-        eprintln!("{}", err);
+        self.errors.push(err);
         None
       }
     }
   }
 
   /// ```bnf
-  /// <l-exp> -> <exp> <lop> <exp> | odd <exp>
+  /// <l-exp> -> <l-term> {or <l-term>}
   fn parse_l_exp(&mut self) -> Option<Box<LExpExpr>> {
-    if self.match_next(Token::Odd) {
+    let start = self.start_span();
+    let mut l_exp = self.parse_l_term();
+    // {or <l-term>}
+    while self.match_next(Token::Or) {
+      self.consume_next(Token::Or);
+      let r_exp = self.parse_l_term();
+      l_exp = match (l_exp, r_exp) {
+        (Some(l_exp), Some(r_exp)) => Some(Box::new(LExpExpr::Or {
+          l_exp,
+          r_exp,
+          span: self.span_from(start),
+        })),
+        _ => None,
+      };
+    }
+    l_exp
+  }
+
+  /// ```bnf
+  /// <l-term> -> <l-factor> {and <l-factor>}
+  fn parse_l_term(&mut self) -> Option<Box<LExpExpr>> {
+    let start = self.start_span();
+    let mut l_exp = self.parse_l_factor();
+    // {and <l-factor>}
+    while self.match_next(Token::And) {
+      self.consume_next(Token::And);
+      let r_exp = self.parse_l_factor();
+      l_exp = match (l_exp, r_exp) {
+        (Some(l_exp), Some(r_exp)) => Some(Box::new(LExpExpr::And {
+          l_exp,
+          r_exp,
+          span: self.span_from(start),
+        })),
+        _ => None,
+      };
+    }
+    l_exp
+  }
+
+  /// ```bnf
+  /// <l-factor> -> not <l-factor> | "(" <l-exp> ")" | <exp> <lop> <exp> | odd <exp>
+  ///
+  /// `(` could start both `"(" <l-exp> ")"` and `<exp>`, see `is_paren_l_exp`
+  fn parse_l_factor(&mut self) -> Option<Box<LExpExpr>> {
+    let start = self.start_span();
+    if self.match_next(Token::Not) {
+      self.consume_next(Token::Not);
+      let l_exp = self.parse_l_factor();
+      l_exp.map(|l_exp| {
+        Box::new(LExpExpr::Not {
+          l_exp,
+          span: self.span_from(start),
+        })
+      })
+    } else if self.match_next(Token::ParL) && self.is_paren_l_exp() {
+      self.consume_next(Token::ParL);
+      let l_exp = self.parse_l_exp();
+      self.consume_next(Token::ParR);
+      l_exp
+    } else if self.match_next(Token::Odd) {
       self.consume_next(Token::Odd);
       let exp = self.parse_exp();
-      exp.map(|exp| Box::new(LExpExpr::Odd { exp }))
+      exp.map(|exp| {
+        Box::new(LExpExpr::Odd {
+          exp,
+          span: self.span_from(start),
+        })
+      })
     } else {
       let l_exp = self.parse_exp();
+      // the whole `<l-factor>` is missing (e.g. `if x > 0 and then`), don't
+      // go on expecting a `<lop>` and skip the rest of the condition
+      if l_exp.is_none()
+        && self
+          .lexer
+          .peek()
+          .is_some_and(|t| FIELD_FOLLOW_TABLE.get(&Field::LFactor).unwrap().contains(t))
+      {
+        return None;
+      }
       let lop = self.parse_lop();
       let r_exp = self.parse_exp();
       match (l_exp, lop, r_exp) {
-        (Some(l_exp), Some(lop), Some(r_exp)) => {
-          Some(Box::new(LExpExpr::Exp { l_exp, lop, r_exp }))
-        }
+        (Some(l_exp), Some(lop), Some(r_exp)) => Some(Box::new(LExpExpr::Exp {
+          l_exp,
+          lop,
+          r_exp,
+          span: self.span_from(start),
+        })),
         _ => None,
       }
     }
   }
 
+  /// Whether the `(` next starts a parenthesized `<l-exp>` instead of an
+  /// `<exp>`, i.e. a `<lop>`, `and`, `or`, `not` or `odd` appears before the
+  /// matching `)`. It's the only place the parser looks further than the next
+  /// token.
+  fn is_paren_l_exp(&self) -> bool {
+    let mut depth = 0;
+    for token in self.lexer.clone() {
+      match token {
+        Token::ParL => depth += 1,
+        Token::ParR if depth <= 1 => return false,
+        Token::ParR => depth -= 1,
+        Token::Eq
+        | Token::Ne
+        | Token::Lt
+        | Token::Le
+        | Token::Gt
+        | Token::Ge
+        | Token::And
+        | Token::Or
+        | Token::Not
+        | Token::Odd => return true,
+        // end of the condition, `)` is missing
+        Token::Then | Token::Do | Token::Semicolon | Token::End => return false,
+        _ => (),
+      }
+    }
+    false
+  }
+
   /// ```bnf
   /// <exp> -> [+|-] <term> {<aop> <term>}
   fn parse_exp(&mut self) -> Option<Box<ExpExpr>> {
+    let start = self.start_span();
     let is_next_add = self.match_next(Token::Add);
     let is_next_sub = self.match_next(Token::Sub);
     if is_next_add || is_next_sub {
@@ -454,70 +723,91 @@ impl<'a> Parser<'a> {
     while self.match_next(Token::Add) || self.match_next(Token::Sub) {
       let aop = self.parse_aop();
       let term = self.parse_term();
-      if aop.is_some() && term.is_some() {
-        let (aop, term) = (aop.unwrap(), term.unwrap());
-        aop_terms.push((aop, term));
-      } else {
-        errored = true;
+      match (aop, term) {
+        (Some(aop), Some(term)) => {
+          aop_terms.push((aop, term));
+        }
+        _ => errored = true,
       }
     }
     if errored {
       return None;
     }
-    if term.is_some() {
-      let term = term.unwrap();
-      Some(Box::new(ExpExpr {
+    term.map(|term| {
+      Box::new(ExpExpr {
         is_negative: is_next_sub,
         term,
         aop_terms,
-      }))
-    } else {
-      None
-    }
+        span: self.span_from(start),
+      })
+    })
   }
 
   /// ```bnf
   /// <term> -> <factor> {<mop> <factor>}
   fn parse_term(&mut self) -> Option<Box<TermExpr>> {
+    let start = self.start_span();
     let factor = self.parse_factor();
     let mut mop_factors = vec![];
     let mut errored = false;
     while self.match_next(Token::Mul) || self.match_next(Token::Div) {
       let mop = self.parse_mop();
       let factor = self.parse_factor();
-      if mop.is_some() && factor.is_some() {
-        let (mop, factor) = (mop.unwrap(), factor.unwrap());
-        mop_factors.push((mop, factor));
-      } else {
-        errored = true;
+      match (mop, factor) {
+        (Some(mop), Some(factor)) => {
+          mop_factors.push((mop, factor));
+        }
+        _ => errored = true,
       }
     }
     if errored {
       return None;
     }
-    if factor.is_some() {
-      let factor = factor.unwrap();
-      Some(Box::new(TermExpr {
+    factor.map(|factor| {
+      Box::new(TermExpr {
         factor,
         mop_factors,
-      }))
-    } else {
-      None
-    }
+        span: self.span_from(start),
+      })
+    })
   }
 
   /// ```bnf
-  /// <factor> -> <id> | <integer> | (<exp>)
+  /// <factor> -> <id> [<args> | <index>] | <integer> | (<exp>)
   fn parse_factor(&mut self) -> Option<Box<FactorExpr>> {
     if self.match_next(Token::ParL) {
+      let start = self.start_span();
       self.consume_next(Token::ParL);
       let exp = self.parse_exp();
       self.consume_next(Token::ParR);
-      exp.map(|exp| Box::new(FactorExpr::Exp(exp)))
+      exp.map(|exp| Box::new(FactorExpr::Exp(exp, self.span_from(start))))
     } else if matches!(self.lexer.peek(), Some(Token::Identifier(_))) {
+      let start = self.start_span();
       let id = self.parse_id();
-      id.map(|id| Box::new(FactorExpr::Id(id)))
-    } else if matches!(self.lexer.peek(), Some(Token::Integer(_))) {
+      // [<index>]
+      if self.match_next(Token::BracketL) {
+        let index = self.parse_index()?;
+        return id.map(|id| {
+          Box::new(FactorExpr::Index {
+            id,
+            index,
+            span: self.span_from(start),
+          })
+        });
+      }
+      // [<args>]
+      if !self.match_next(Token::ParL) {
+        return id.map(|id| Box::new(FactorExpr::Id(id)));
+      }
+      let args = self.parse_args()?;
+      id.map(|id| {
+        Box::new(FactorExpr::Call {
+          id,
+          args,
+          span: self.span_from(start),
+        })
+      })
+    } else if matches!(self.lexer.peek(), Some(Token::Integer(_))) || self.peek_integer_overflow() {
       let integer = self.parse_integer();
       integer.map(|integer| Box::new(FactorExpr::Integer(integer)))
     } else if matches!(self.lexer.peek(), Some(Token::LexicalError(_))) {
@@ -527,15 +817,17 @@ impl<'a> Parser<'a> {
       }
       let err = CompileErrorBuilder::syntax_error_template()
         .with_lexer_ref(&self.lexer)
+        .with_code(ErrorCode::ExpectedFactor)
         .with_info("Expected `<id>` / `<integer>` / `(<exp>)` field, but not found!".to_string())
         .build();
-      eprintln!("{}", err);
+      self.errors.push(err);
       None
     } else {
       self.has_error = true;
       let unexpected_t = self.lexer.peek().cloned();
       let err = CompileErrorBuilder::syntax_error_template()
         .with_lexer_ref(&self.lexer)
+        .with_code(ErrorCode::ExpectedFactor)
         .with_info(format!(
           "Expected `<id>` / `<integer>` / `(<exp>)` field, but got an unmatchable token `{}`",
           match &unexpected_t {
@@ -544,7 +836,7 @@ impl<'a> Parser<'a> {
           }
         ))
         .build();
-      eprintln!("{}", err);
+      self.errors.push(err);
       if let Some(t) = unexpected_t {
         if !FIELD_FOLLOW_TABLE.get(&Field::Factor).unwrap().contains(&t) {
           self.lexer.next();
@@ -556,6 +848,57 @@ impl<'a> Parser<'a> {
     }
   }
 
+  /// ```bnf
+  /// <var> -> <id> [<index>]
+  fn parse_var(&mut self) -> Option<(Box<IdExpr>, Option<Box<ExpExpr>>)> {
+    let id = self.parse_id();
+    // [<index>]
+    if !self.match_next(Token::BracketL) {
+      return id.map(|id| (id, None));
+    }
+    let index = self.parse_index()?;
+    id.map(|id| (id, Some(index)))
+  }
+
+  /// ```bnf
+  /// <index> -> "[" <exp> "]"
+  fn parse_index(&mut self) -> Option<Box<ExpExpr>> {
+    self.consume_next(Token::BracketL);
+    let exp = self.parse_exp();
+    self.consume_next(Token::BracketR);
+    exp
+  }
+
+  /// ```bnf
+  /// <args> -> ([<exp> {, <exp>}])
+  // same as `args` of the AST
+  #[allow(clippy::vec_box)]
+  fn parse_args(&mut self) -> Option<Vec<Box<ExpExpr>>> {
+    let mut errored = false;
+    self.consume_next(Token::ParL);
+    let mut args = vec![];
+    // ([<exp>])
+    if !self.match_next(Token::ParR) {
+      match self.parse_exp() {
+        Some(exp) => args.push(exp),
+        None => errored = true,
+      }
+      // ([<exp> {, <exp>}])
+      while self.match_next(Token::Comma) {
+        self.consume_next(Token::Comma);
+        match self.parse_exp() {
+          Some(exp) => args.push(exp),
+          None => errored = true,
+        }
+      }
+    }
+    self.consume_next(Token::ParR);
+    if errored {
+      return None;
+    }
+    Some(args)
+  }
+
   /// ```bnf
   /// <lop> -> = | <> | < | <= | > | >=
   fn parse_lop(&mut self) -> Option<Box<LopExpr>> {
@@ -590,12 +933,13 @@ impl<'a> Parser<'a> {
           let unexpected_t = token.to_owned();
           let err = CompileErrorBuilder::syntax_error_template()
             .with_lexer_ref(&self.lexer)
+            .with_code(ErrorCode::ExpectedLop)
             .with_info(format!(
               "Expected <lop> field, but got an unmatchable token `{}`",
               &unexpected_t
             ))
             .build();
-          eprintln!("{}", err);
+          self.errors.push(err);
           if !FIELD_FOLLOW_TABLE
             .get(&Field::Lop)
             .unwrap()
@@ -610,9 +954,10 @@ impl<'a> Parser<'a> {
         self.has_error = true;
         let err = CompileErrorBuilder::syntax_error_template()
           .with_lexer_ref(&self.lexer)
+          .with_code(ErrorCode::ExpectedLop)
           .with_info("Expected <lop> field, but got `None`".to_string())
           .build();
-        eprintln!("{}", err);
+        self.errors.push(err);
         self.lexer.next();
         None
       }
@@ -633,6 +978,7 @@ impl<'a> Parser<'a> {
       let unexpected_t = self.lexer.peek().cloned();
       let err = CompileErrorBuilder::syntax_error_template()
         .with_lexer_ref(&self.lexer)
+        .with_code(ErrorCode::ExpectedAop)
         .with_info(format!(
           "Expected <aop> field, but got an unmatchable token `{}`",
           match &unexpected_t {
@@ -641,7 +987,7 @@ impl<'a> Parser<'a> {
           }
         ))
         .build();
-      eprintln!("{}", err);
+      self.errors.push(err);
       if let Some(t) = unexpected_t {
         if !FIELD_FOLLOW_TABLE.get(&Field::Aop).unwrap().contains(&t) {
           self.lexer.next();
@@ -667,6 +1013,7 @@ impl<'a> Parser<'a> {
       let unexpected_t = self.lexer.peek().cloned();
       let err = CompileErrorBuilder::syntax_error_template()
         .with_lexer_ref(&self.lexer)
+        .with_code(ErrorCode::ExpectedMop)
         .with_info(format!(
           "Expected <mop> field, but got an unmatchable token `{}`",
           match &unexpected_t {
@@ -675,7 +1022,7 @@ impl<'a> Parser<'a> {
           }
         ))
         .build();
-      eprintln!("{}", err);
+      self.errors.push(err);
       if let Some(t) = unexpected_t {
         if !FIELD_FOLLOW_TABLE.get(&Field::Mop).unwrap().contains(&t) {
           self.lexer.next();
@@ -2,11 +2,12 @@ pub mod methods;
 pub mod synchronizer;
 
 use crate::{
-  ast::ProgramExpr,
-  error::error_builder::CompileErrorBuilder,
+  ast::{ProgramExpr, Span},
+  error::{code::ErrorCode, compile_error::CompileError, error_builder::CompileErrorBuilder},
   lexer::{token_def::Token, Lexer, LexerIterator},
   optimizer::AstOptimizer,
   parser::synchronizer::tables::TOKEN_FOLLOW_TABLE,
+  vm::arith::IntWidth,
   SEP,
 };
 
@@ -15,6 +16,7 @@ pub struct Parser<'a> {
   lexer: Lexer<'a>,
   ast_entry: Option<Box<ProgramExpr>>,
   has_error: bool,
+  errors: Vec<CompileError>,
 }
 
 impl<'a> Parser<'a> {
@@ -30,25 +32,42 @@ impl<'a> From<Parser<'a>> for AstOptimizer {
 }
 
 impl<'a> Parser<'a> {
+  /// Span of the next token, where a node starts
+  fn start_span(&mut self) -> Span {
+    self.lexer.peek_span().unwrap_or_default()
+  }
+
+  /// From `start` to the end of the last consumed token
+  fn span_from(&self, start: Span) -> Span {
+    start.to(self.lexer.span())
+  }
+
   fn consume_next(&mut self, token: Token) {
     if self.lexer.peek().is_none() {
       self.has_error = true;
+      let err = CompileErrorBuilder::syntax_error_template()
+        .with_lexer_ref(&self.lexer)
+        .with_code(ErrorCode::ExpectedToken)
+        .with_info(format!("Expected `{}`, but got `None`", token))
+        .build();
+      self.errors.push(err);
       return;
     }
 
     let t = self.lexer.peek().cloned().unwrap();
 
     if let Token::LexicalError(err) = t {
-      eprintln!("{}", err);
+      self.errors.push(err);
       self.lexer.next();
       self.has_error = true;
     } else if t != token {
       let unexpected_t = t.to_owned();
       let err = CompileErrorBuilder::syntax_error_template()
         .with_lexer_ref(&self.lexer)
+        .with_code(ErrorCode::ExpectedToken)
         .with_info(format!("Expected `{}`, but got `{}`", token, unexpected_t))
         .build();
-      eprintln!("{}", err);
+      self.errors.push(err);
       if !TOKEN_FOLLOW_TABLE.get(&token).unwrap().contains(&t) {
         self.lexer.next();
       }
@@ -84,7 +103,7 @@ impl<'a> Parser<'a> {
     let t = self.lexer.peek().cloned().unwrap();
 
     if let Token::LexicalError(err) = t {
-      eprintln!("{}", err);
+      self.errors.push(err);
       self.lexer.next();
       self.has_error = true;
       Err(true)
@@ -104,6 +123,14 @@ impl<'a> Parser<'a> {
     }
   }
 
+  /// Whether the next token is an integer literal out of range
+  fn peek_integer_overflow(&mut self) -> bool {
+    matches!(
+      self.lexer.peek(),
+      Some(Token::LexicalError(err)) if err.code == Some(ErrorCode::IntegerOverflow)
+    )
+  }
+
   fn consume_next_integer(&mut self) -> Result<i64, bool> {
     if self.lexer.peek().is_none() {
       self.has_error = true;
@@ -113,7 +140,7 @@ impl<'a> Parser<'a> {
     let t = self.lexer.peek().cloned().unwrap();
 
     if let Token::LexicalError(err) = t {
-      eprintln!("{}", err);
+      self.errors.push(err);
       self.lexer.next();
       self.has_error = true;
       Err(true)
@@ -140,16 +167,47 @@ impl<'a> Parser<'a> {
       lexer: Lexer::new(ctx),
       ast_entry: None,
       has_error: false,
+      errors: vec![],
     }
   }
 
-  pub fn parse(&mut self) -> &mut Self {
+  /// Integer literals out of the range of `int_width` are lexical errors
+  pub fn with_int_width(mut self, int_width: IntWidth) -> Self {
+    self.lexer = self.lexer.with_int_width(int_width);
+    self
+  }
+
+  /// Parse the whole program.
+  ///
+  /// Every lexical / syntax error found during error-sync is collected (in
+  /// the order of detection) and returned as `Err`, nothing will be printed.
+  pub fn parse(&mut self) -> Result<Box<ProgramExpr>, Vec<CompileError>> {
     let program_expr = self.parse_program();
-    if self.has_error {
-      panic!("|> Errors above occurred (during `parsing`), compiling stopped ... <|\n");
+    // tokens after the program are ignored, but not lexical errors (e.g. an
+    // unterminated comment)
+    for token in self.lexer.by_ref() {
+      if let Token::LexicalError(err) = token {
+        self.errors.push(err);
+        self.has_error = true;
+      }
+    }
+    match program_expr {
+      Some(program_expr) if !self.has_error => {
+        self.ast_entry = Some(program_expr.to_owned());
+        Ok(program_expr)
+      }
+      _ => {
+        if self.errors.is_empty() {
+          let err = CompileErrorBuilder::syntax_error_template()
+            .with_lexer_ref(&self.lexer)
+            .with_code(ErrorCode::InvalidProgram)
+            .with_info("Failed to parse the program".to_string())
+            .build();
+          self.errors.push(err);
+        }
+        Err(std::mem::take(&mut self.errors))
+      }
     }
-    self.ast_entry = program_expr;
-    self
   }
 
   pub fn show_ast(&mut self) -> &mut Self {
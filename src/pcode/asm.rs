@@ -0,0 +1,262 @@
+use std::{collections::HashMap, str::FromStr};
+
+use super::{PCodeManager, PcodeType};
+use crate::{
+  ast::Location,
+  error::{code::ErrorCode, compile_error::CompileError, error_builder::CompileErrorBuilder},
+};
+
+/// Textual format of pcode (one instruction per line):
+///
+/// ```text
+/// ; comment
+/// L2:
+///   LOD 1 3    ; line 7, col 5, in a.aa
+///   JPC 0 L2
+/// ```
+///
+/// - mnemonics are case-insensitive, a leading pc (e.g. `2 lod 1 3`, as in
+///   the listing of the classic Wirth implementation) is allowed and ignored
+/// - `a` could be an integer or a label (`<ident>:`, on its own line or
+///   before an instruction)
+/// - `; line <line>, col <col>, in <proc>` fills the line table, other
+///   comments are ignored
+impl PCodeManager {
+  /// Disassemble into the textual format, jump targets are labeled as `L<pc>`
+  pub fn to_asm(&self) -> String {
+    let mut targets = self
+      .pcode_list
+      .iter()
+      .filter(|pcode| is_jump(pcode.f))
+      .map(|pcode| pcode.a)
+      .collect::<Vec<_>>();
+    targets.sort_unstable();
+    targets.dedup();
+
+    let mut asm = String::new();
+    for (pc, pcode) in self.pcode_list.iter().enumerate() {
+      if targets.binary_search(&(pc as i64)).is_ok() {
+        asm += &format!("L{}:\n", pc);
+      }
+      let a = if is_jump(pcode.f) && pcode.a >= 0 {
+        format!("L{}", pcode.a)
+      } else {
+        pcode.a.to_string()
+      };
+      let inst = format!("  {} {} {}", pcode.f, pcode.l, a);
+      match self.line_table.lookup(pc) {
+        Some(info) if info.location != Location::default() || !info.proc_name.is_empty() => {
+          asm += &format!(
+            "{:<16}; line {}, col {}, in {}\n",
+            inst, info.location.0, info.location.1, info.proc_name
+          )
+        }
+        _ => asm += &format!("{}\n", inst),
+      }
+    }
+    // jump to the end of the pcode list
+    if targets
+      .binary_search(&(self.pcode_list.len() as i64))
+      .is_ok()
+    {
+      asm += &format!("L{}:\n", self.pcode_list.len());
+    }
+    asm
+  }
+
+  /// Assemble from the textual format, all errors will be returned
+  pub fn from_asm(asm: &str) -> Result<Self, Vec<CompileError>> {
+    let mut errors = vec![];
+    let mut labels = HashMap::new();
+    let mut insts = vec![];
+
+    for (line_num, line) in asm.lines().enumerate() {
+      let line_num = line_num + 1;
+      let (code, comment) = line.split_once(';').unwrap_or((line, ""));
+      let mut words = Words::new(code);
+
+      if let Some((label, col)) = words.label() {
+        if labels.insert(label.to_string(), insts.len()).is_some() {
+          errors.push(asm_error(
+            line_num,
+            col,
+            format!("label `{}` is defined before", label),
+          ));
+        }
+      }
+
+      let Some((first, first_col)) = words.next() else {
+        continue;
+      };
+      // leading pc
+      let (mnemonic, col) = if first.parse::<usize>().is_ok() {
+        match words.next() {
+          Some(word) => word,
+          None => {
+            errors.push(asm_error(line_num, first_col, "Expected <mnemonic>".into()));
+            continue;
+          }
+        }
+      } else {
+        (first, first_col)
+      };
+
+      let f = match PcodeType::from_str(mnemonic) {
+        Ok(f) => f,
+        Err(err) => {
+          errors.push(asm_error(line_num, col, err));
+          continue;
+        }
+      };
+      let l = match words.next() {
+        Some((l, col)) => match l.parse::<usize>() {
+          Ok(l) => l,
+          Err(_) => {
+            errors.push(asm_error(
+              line_num,
+              col,
+              format!("`{}` is not a valid level", l),
+            ));
+            continue;
+          }
+        },
+        None => {
+          errors.push(asm_error(line_num, col, "Expected <level>".into()));
+          continue;
+        }
+      };
+      let (a, a_col) = match words.next() {
+        Some(word) => word,
+        None => {
+          errors.push(asm_error(line_num, col, "Expected <address/value>".into()));
+          continue;
+        }
+      };
+      if let Some((word, col)) = words.next() {
+        errors.push(asm_error(line_num, col, format!("Unexpected `{}`", word)));
+        continue;
+      }
+
+      let (location, proc_name) = parse_line_info(comment).unwrap_or_default();
+      insts.push((line_num, f, l, (a, a_col), location, proc_name));
+    }
+
+    let mut code = Self::default();
+    for (line_num, f, l, (a, col), location, proc_name) in insts {
+      let a = match a.parse::<i64>() {
+        Ok(a) => a,
+        Err(_) => match labels.get(a) {
+          Some(&pc) => pc as i64,
+          None => {
+            errors.push(asm_error(
+              line_num,
+              col,
+              format!("label `{}` is undefined", a),
+            ));
+            continue;
+          }
+        },
+      };
+      code.set_location(location);
+      code.set_proc(proc_name);
+      code.gen(f, l, a);
+    }
+
+    if errors.is_empty() {
+      Ok(code)
+    } else {
+      Err(errors)
+    }
+  }
+}
+
+impl FromStr for PcodeType {
+  type Err = String;
+
+  fn from_str(s: &str) -> Result<Self, Self::Err> {
+    let f = match s.to_ascii_uppercase().as_str() {
+      "NIL" => Self::NIL,
+      "LIT" => Self::LIT,
+      "OPR" => Self::OPR,
+      "LOD" => Self::LOD,
+      "STO" => Self::STO,
+      "STA" => Self::STA,
+      "CAL" => Self::CAL,
+      "INT" => Self::INT,
+      "JMP" => Self::JMP,
+      "JPC" => Self::JPC,
+      "RED" => Self::RED,
+      "WRT" => Self::WRT,
+      "LDA" => Self::LDA,
+      "LDI" => Self::LDI,
+      "STI" => Self::STI,
+      "CHK" => Self::CHK,
+      "LDX" => Self::LDX,
+      "STX" => Self::STX,
+      _ => return Err(format!("`{}` is not a valid mnemonic", s)),
+    };
+    Ok(f)
+  }
+}
+
+fn is_jump(f: PcodeType) -> bool {
+  matches!(f, PcodeType::JMP | PcodeType::JPC | PcodeType::CAL)
+}
+
+fn asm_error(line: usize, col: usize, info: String) -> CompileError {
+  CompileErrorBuilder::syntax_error_template()
+    .with_line(line)
+    .with_col(col)
+    .with_code(ErrorCode::InvalidAssembly)
+    .with_info(info)
+    .build()
+}
+
+/// `line <line>, col <col>, in <proc>`
+fn parse_line_info(comment: &str) -> Option<(Location, &str)> {
+  let rest = comment.trim().strip_prefix("line ")?;
+  let (line, rest) = rest.split_once(", col ")?;
+  let (col, proc_name) = rest.split_once(", in ")?;
+  let location = Location(line.trim().parse().ok()?, col.trim().parse().ok()?);
+  Some((location, proc_name.trim()))
+}
+
+/// Whitespace separated words of a line, with their (1-based) columns
+struct Words<'a> {
+  line: &'a str,
+  pos: usize,
+}
+
+impl<'a> Words<'a> {
+  fn new(line: &'a str) -> Self {
+    Self { line, pos: 0 }
+  }
+
+  /// `<ident>:` at the beginning of the line
+  fn label(&mut self) -> Option<(&'a str, usize)> {
+    let rest = &self.line[self.pos..];
+    let start = rest.len() - rest.trim_start().len();
+    let (label, _) = rest[start..].split_once(':')?;
+    let is_ident = label.starts_with(|c: char| c.is_ascii_alphabetic() || c == '_')
+      && label.chars().all(|c| c.is_ascii_alphanumeric() || c == '_');
+    if !is_ident {
+      return None;
+    }
+    let col = self.pos + start + 1;
+    self.pos += start + label.len() + 1;
+    Some((label, col))
+  }
+}
+
+impl<'a> Iterator for Words<'a> {
+  type Item = (&'a str, usize);
+
+  fn next(&mut self) -> Option<Self::Item> {
+    let rest = &self.line[self.pos..];
+    let start = rest.len() - rest.trim_start().len();
+    let word = rest[start..].split_whitespace().next()?;
+    let col = self.pos + start + 1;
+    self.pos += start + word.len();
+    Some((word, col))
+  }
+}
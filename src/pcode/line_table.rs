@@ -0,0 +1,97 @@
+use std::fmt::Display;
+
+use crate::{ast::Location, SEP};
+
+/// Debug info of a single pcode
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct LineInfo<'a> {
+  pub location: Location,
+  pub proc_name: &'a str,
+}
+
+impl Display for LineInfo<'_> {
+  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    write!(f, "line {}, in {}", self.location.0, self.proc_name)
+  }
+}
+
+/// Maps each pcode (by index) back to the source.
+///
+/// - entry_list: (source location, index of `proc_list`) of each pcode,
+///   same index as `PCodeManager.pcode_list`
+/// - proc_list: name of each procedure, the program itself included
+#[derive(Debug, Clone, Default)]
+pub struct LineTable {
+  pub entry_list: Vec<(Location, usize)>,
+  pub proc_list: Vec<String>,
+  curr_location: Location,
+  curr_proc: usize,
+}
+
+impl LineTable {
+  /// Every pcode generated later will be mapped back to `location`
+  pub fn set_location(&mut self, location: Location) {
+    self.curr_location = location;
+  }
+
+  /// Every pcode generated later will be mapped into procedure `name`
+  pub fn set_proc(&mut self, name: &str) {
+    self.curr_proc = match self.proc_list.iter().position(|proc| proc == name) {
+      Some(pos) => pos,
+      None => {
+        self.proc_list.push(name.to_string());
+        self.proc_list.len() - 1
+      }
+    };
+  }
+
+  /// Record the debug info of a newly generated pcode
+  pub fn record(&mut self) {
+    self.entry_list.push((self.curr_location, self.curr_proc));
+  }
+
+  pub fn lookup(&self, pc: usize) -> Option<LineInfo<'_>> {
+    self
+      .entry_list
+      .get(pc)
+      .map(|&(location, proc_pos)| LineInfo {
+        location,
+        proc_name: self.proc_name(proc_pos),
+      })
+  }
+
+  fn proc_name(&self, proc_pos: usize) -> &str {
+    self.proc_list.get(proc_pos).map_or("", String::as_str)
+  }
+
+  /// Source location of the pcode at `pc` (`Location(0, 0)` if unknown)
+  pub fn location_of(&self, pc: usize) -> Location {
+    self
+      .lookup(pc)
+      .map(|info| info.location)
+      .unwrap_or_default()
+  }
+}
+
+impl LineTable {
+  pub fn show_line_table(&self) {
+    println!("Line Table:");
+    println!("{}", SEP.as_str());
+    println!(
+      "{:>4} | {:<6} | {:<6} | {:<16}",
+      "pc", "line", "col", "proc"
+    );
+    println!("{}", SEP.as_str());
+    for (pc, (location, proc_pos)) in self.entry_list.iter().enumerate() {
+      println!(
+        "{:>4} | {:<6} | {:<6} | {:<16}",
+        pc,
+        location.0,
+        location.1,
+        self.proc_name(*proc_pos)
+      );
+    }
+    println!("{}", SEP.as_str());
+    println!();
+  }
+}
@@ -1,6 +1,11 @@
 use std::fmt::Display;
 
-use crate::SEP;
+use self::line_table::LineTable;
+use crate::{ast::Location, SEP};
+
+pub mod asm;
+pub mod line_table;
+pub mod object;
 
 #[derive(Debug, Clone, Copy, Default)]
 pub enum PcodeType {
@@ -17,6 +22,20 @@ pub enum PcodeType {
   JPC,
   RED,
   WRT,
+  /// push the absolute address of `LOD l a` (a `var` passed by reference)
+  LDA,
+  /// pop an address, push the value at it
+  LDI,
+  /// pop a value, then an address, store the value at it
+  STI,
+  /// `CHK lo hi`: pop an index, push its offset from `lo` if it's in
+  /// `lo ..= hi`, runtime error otherwise
+  CHK,
+  /// `LDX l a`: pop an offset, push the value at `a + offset` (`LOD l a` of
+  /// an element)
+  LDX,
+  /// `STX l a`: pop a value, then an offset, store the value at `a + offset`
+  STX,
 }
 
 impl Display for PcodeType {
@@ -42,9 +61,11 @@ impl Pcode {
   }
 }
 
+/// - line_table: debug info, maps each pcode back to the source
 #[derive(Debug, Clone, Default)]
 pub struct PCodeManager {
   pub pcode_list: Vec<Pcode>,
+  pub line_table: LineTable,
 }
 
 impl PCodeManager {
@@ -65,7 +86,18 @@ impl PCodeManager {
 }
 
 impl PCodeManager {
+  /// Every pcode generated later will be mapped back to `location`
+  pub fn set_location(&mut self, location: Location) {
+    self.line_table.set_location(location);
+  }
+
+  /// Every pcode generated later will be mapped into procedure `name`
+  pub fn set_proc(&mut self, name: &str) {
+    self.line_table.set_proc(name);
+  }
+
   pub fn gen(&mut self, f: PcodeType, l: usize, a: i64) {
     self.pcode_list.push(Pcode { f, l, a });
+    self.line_table.record();
   }
 }
@@ -0,0 +1,451 @@
+use std::{fs, path::Path};
+
+use super::{line_table::LineTable, PCodeManager, Pcode, PcodeType};
+use crate::{
+  ast::Location,
+  error::object_error::{ObjectError, ObjectErrorType},
+  symbol_table::{sym_type::SymType, table_row::TableRow, SymTable},
+  vm::arith::{IntSemantics, IntWidth, Overflow},
+};
+
+pub const MAGIC: [u8; 4] = *b"PL0\0";
+/// Bumped whenever the encoding changes, an old VM rejects a new object
+/// file by the version instead of failing on an unknown opcode / tag.
+///
+/// - 1: the initial format
+/// - 2: `LDA` / `LDI` / `STI` / `CHK` / `LDX` / `STX`, `OPR n 17`,
+///   `OPR 0 18`, the `func` / `ref` / `array` symbol types, and the `INT`
+///   section
+/// - 3: `l` of each pcode is 64-bit, it's the lower bound of `CHK`
+pub const VERSION: u16 = 3;
+
+const SECTION_CODE: u8 = 1;
+const SECTION_SYMS: u8 = 2;
+const SECTION_LINE: u8 = 3;
+const SECTION_INT: u8 = 4;
+
+const PCODE_TYPES: [PcodeType; 18] = [
+  PcodeType::NIL,
+  PcodeType::LIT,
+  PcodeType::OPR,
+  PcodeType::LOD,
+  PcodeType::STO,
+  PcodeType::STA,
+  PcodeType::CAL,
+  PcodeType::INT,
+  PcodeType::JMP,
+  PcodeType::JPC,
+  PcodeType::RED,
+  PcodeType::WRT,
+  PcodeType::LDA,
+  PcodeType::LDI,
+  PcodeType::STI,
+  PcodeType::CHK,
+  PcodeType::LDX,
+  PcodeType::STX,
+];
+
+const INT_WIDTHS: [IntWidth; 3] = [IntWidth::I16, IntWidth::I32, IntWidth::I64];
+
+const OVERFLOWS: [Overflow; 3] = [Overflow::Checked, Overflow::Wrapping, Overflow::Saturating];
+
+const SYM_TYPES: [SymType; 7] = [
+  SymType::Nil,
+  SymType::Const,
+  SymType::Var,
+  SymType::Proc,
+  SymType::Func,
+  SymType::Ref,
+  SymType::Array,
+];
+
+/// Compiled program, ready to be saved / loaded / fed into `VM::new`.
+///
+/// ## Format (little-endian)
+///
+/// | magic `PL0\0` | version: u16 | n_sections: u16 | section ... |
+///
+/// each section: | kind: u8 | len: u32 | payload (`len` bytes) |
+///
+/// - `CODE` (1, required): n: u32, then n * | f: i8 | l: i64 | a: i64 |
+/// - `SYMS` (2, optional): rows of the symbol table
+/// - `LINE` (3, optional): procedure names, then (line, col, proc) of each
+///   pcode
+/// - `INT` (4, optional): | width (bits): u8 | overflow: u8 |, 0 ~ 2 for
+///   checked / wrapping / saturating
+///
+/// Sections of unknown kinds are skipped.
+#[derive(Debug, Clone, Default)]
+pub struct ObjectFile {
+  pub code: PCodeManager,
+  pub sym_table: Option<SymTable>,
+  /// what the code has been compiled for, `None` if not recorded
+  pub int_semantics: Option<IntSemantics>,
+}
+
+impl ObjectFile {
+  pub fn new(code: PCodeManager, sym_table: Option<SymTable>) -> Self {
+    Self {
+      code,
+      sym_table,
+      int_semantics: None,
+    }
+  }
+
+  pub fn with_int_semantics(mut self, int_semantics: IntSemantics) -> Self {
+    self.int_semantics = Some(int_semantics);
+    self
+  }
+
+  pub fn to_bytes(&self) -> Vec<u8> {
+    let mut sections = vec![(SECTION_CODE, self.code_section())];
+    if let Some(sym_table) = &self.sym_table {
+      sections.push((SECTION_SYMS, syms_section(sym_table)));
+    }
+    if !self.code.line_table.entry_list.is_empty() {
+      sections.push((SECTION_LINE, self.line_section()));
+    }
+    if let Some(int_semantics) = self.int_semantics {
+      sections.push((SECTION_INT, int_section(int_semantics)));
+    }
+
+    let mut bytes = MAGIC.to_vec();
+    bytes.extend(VERSION.to_le_bytes());
+    bytes.extend((sections.len() as u16).to_le_bytes());
+    for (kind, payload) in sections {
+      bytes.push(kind);
+      bytes.extend((payload.len() as u32).to_le_bytes());
+      bytes.extend(payload);
+    }
+    bytes
+  }
+
+  /// Load and validate, nothing will panic on malformed input
+  pub fn from_bytes(bytes: &[u8]) -> Result<Self, ObjectError> {
+    let mut reader = Reader::new(bytes, 0);
+
+    if reader.take(MAGIC.len())? != MAGIC {
+      return Err(ObjectError::new(
+        ObjectErrorType::BadMagic,
+        0,
+        "not a pl_0 object file (bad magic number)".to_string(),
+      ));
+    }
+    let version = reader.u16()?;
+    if version != VERSION {
+      return Err(ObjectError::new(
+        ObjectErrorType::UnsupportedVersion,
+        MAGIC.len(),
+        format!(
+          "unsupported object file version `{}` (expected `{}`)",
+          version, VERSION
+        ),
+      ));
+    }
+
+    let mut code = None;
+    let mut sym_table = None;
+    let mut line_section = None;
+    let mut int_semantics = None;
+    for _ in 0..reader.u16()? {
+      let offset = reader.pos;
+      let kind = reader.u8()?;
+      let len = reader.u32()? as usize;
+      let payload = Reader::new(reader.take(len)?, reader.pos - len);
+      let is_dup = match kind {
+        SECTION_CODE => code.replace(read_code(payload)?).is_some(),
+        SECTION_SYMS => sym_table.replace(read_syms(payload)?).is_some(),
+        SECTION_LINE => line_section.replace(payload).is_some(),
+        SECTION_INT => int_semantics.replace(read_int(payload)?).is_some(),
+        _ => false,
+      };
+      if is_dup {
+        return Err(malformed(offset, format!("duplicate section `{}`", kind)));
+      }
+    }
+    if !reader.is_empty() {
+      return Err(malformed(
+        reader.pos,
+        "trailing bytes after the last section".into(),
+      ));
+    }
+
+    let Some(pcode_list) = code else {
+      return Err(malformed(reader.pos, "missing `CODE` section".into()));
+    };
+    let line_table = match line_section {
+      Some(payload) => read_line(payload, pcode_list.len())?,
+      None => LineTable::default(),
+    };
+    Ok(Self {
+      code: PCodeManager {
+        pcode_list,
+        line_table,
+      },
+      sym_table,
+      int_semantics,
+    })
+  }
+
+  pub fn save(&self, path: impl AsRef<Path>) -> Result<(), ObjectError> {
+    fs::write(path, self.to_bytes()).map_err(io_error)
+  }
+
+  pub fn load(path: impl AsRef<Path>) -> Result<Self, ObjectError> {
+    Self::from_bytes(&fs::read(path).map_err(io_error)?)
+  }
+}
+
+impl ObjectFile {
+  fn code_section(&self) -> Vec<u8> {
+    let mut payload = (self.code.pcode_list.len() as u32).to_le_bytes().to_vec();
+    for pcode in &self.code.pcode_list {
+      payload.push(pcode.f as i8 as u8);
+      payload.extend((pcode.l as i64).to_le_bytes());
+      payload.extend(pcode.a.to_le_bytes());
+    }
+    payload
+  }
+
+  fn line_section(&self) -> Vec<u8> {
+    let line_table = &self.code.line_table;
+    let mut payload = (line_table.proc_list.len() as u32).to_le_bytes().to_vec();
+    for proc_name in &line_table.proc_list {
+      write_str(&mut payload, proc_name);
+    }
+    payload.extend((line_table.entry_list.len() as u32).to_le_bytes());
+    for (location, proc_pos) in &line_table.entry_list {
+      payload.extend((location.0 as u32).to_le_bytes());
+      payload.extend((location.1 as u32).to_le_bytes());
+      payload.extend((*proc_pos as u32).to_le_bytes());
+    }
+    payload
+  }
+}
+
+fn syms_section(sym_table: &SymTable) -> Vec<u8> {
+  let mut payload = (sym_table.table.len() as u32).to_le_bytes().to_vec();
+  for row in &sym_table.table {
+    payload.push(row.ty as u8);
+    payload.extend(row.val.to_le_bytes());
+    payload.extend((row.level as u32).to_le_bytes());
+    payload.extend((row.addr as u32).to_le_bytes());
+    payload.extend((row.size as u32).to_le_bytes());
+    write_str(&mut payload, &row.name);
+    payload.extend((row.scope_list.len() as u32).to_le_bytes());
+    for scope in &row.scope_list {
+      write_str(&mut payload, scope);
+    }
+  }
+  payload
+}
+
+fn int_section(int_semantics: IntSemantics) -> Vec<u8> {
+  vec![
+    int_semantics.width.bits() as u8,
+    int_semantics.overflow as u8,
+  ]
+}
+
+fn write_str(payload: &mut Vec<u8>, s: &str) {
+  payload.extend((s.len() as u32).to_le_bytes());
+  payload.extend(s.as_bytes());
+}
+
+fn read_code(mut reader: Reader) -> Result<Vec<Pcode>, ObjectError> {
+  let n = reader.u32()?;
+  let mut pcode_list = vec![];
+  for _ in 0..n {
+    let offset = reader.pos;
+    let f = reader.u8()? as i8;
+    let Some(&f) = PCODE_TYPES.iter().find(|&&ty| ty as i8 == f) else {
+      return Err(malformed(
+        offset,
+        format!("`{}` is not a valid pcode type", f),
+      ));
+    };
+    let l = reader.i64()? as usize;
+    let a = reader.i64()?;
+    pcode_list.push(Pcode { f, l, a });
+  }
+  reader.finish()?;
+  Ok(pcode_list)
+}
+
+fn read_syms(mut reader: Reader) -> Result<SymTable, ObjectError> {
+  let n = reader.u32()?;
+  let mut table = vec![];
+  for _ in 0..n {
+    let offset = reader.pos;
+    let ty = reader.u8()?;
+    let Some(&ty) = SYM_TYPES.iter().find(|&&sym_ty| sym_ty as u8 == ty) else {
+      return Err(malformed(
+        offset,
+        format!("`{}` is not a valid symbol type", ty),
+      ));
+    };
+    let val = reader.i64()?;
+    let level = reader.u32()? as usize;
+    let addr = reader.u32()? as usize;
+    let size = reader.u32()? as usize;
+    let name = reader.string()?;
+    let mut scope_list = vec![];
+    for _ in 0..reader.u32()? {
+      scope_list.push(reader.string()?);
+    }
+    table.push(TableRow {
+      ty,
+      val,
+      level,
+      addr,
+      size,
+      name,
+      scope_list,
+      location: Location::default(),
+    });
+  }
+  reader.finish()?;
+  Ok(SymTable {
+    table_ptr: table.len(),
+    table,
+  })
+}
+
+fn read_int(mut reader: Reader) -> Result<IntSemantics, ObjectError> {
+  let offset = reader.pos;
+  let bits = reader.u8()?;
+  let Some(&width) = INT_WIDTHS.iter().find(|width| width.bits() == bits as u32) else {
+    return Err(malformed(
+      offset,
+      format!("`{}` is not a valid integer width", bits),
+    ));
+  };
+  let offset = reader.pos;
+  let overflow = reader.u8()?;
+  let Some(&overflow) = OVERFLOWS.iter().find(|&&ty| ty as u8 == overflow) else {
+    return Err(malformed(
+      offset,
+      format!("`{}` is not a valid overflow behavior", overflow),
+    ));
+  };
+  reader.finish()?;
+  Ok(IntSemantics::new(width, overflow))
+}
+
+fn read_line(mut reader: Reader, n_pcode: usize) -> Result<LineTable, ObjectError> {
+  let mut line_table = LineTable::default();
+  for _ in 0..reader.u32()? {
+    let proc_name = reader.string()?;
+    line_table.proc_list.push(proc_name);
+  }
+  let offset = reader.pos;
+  let n = reader.u32()? as usize;
+  if n != n_pcode {
+    return Err(malformed(
+      offset,
+      format!(
+        "line table has {} entries, but there're {} pcode",
+        n, n_pcode
+      ),
+    ));
+  }
+  for _ in 0..n {
+    let offset = reader.pos;
+    let location = Location(reader.u32()? as usize, reader.u32()? as usize);
+    let proc_pos = reader.u32()? as usize;
+    if proc_pos >= line_table.proc_list.len() {
+      return Err(malformed(
+        offset,
+        format!("procedure `#{}` is undefined", proc_pos),
+      ));
+    }
+    line_table.entry_list.push((location, proc_pos));
+  }
+  reader.finish()?;
+  Ok(line_table)
+}
+
+fn malformed(offset: usize, info: String) -> ObjectError {
+  ObjectError::new(ObjectErrorType::Malformed, offset, info)
+}
+
+fn io_error(err: std::io::Error) -> ObjectError {
+  ObjectError::new(ObjectErrorType::IoError, 0, err.to_string())
+}
+
+/// Bounds-checked reader, `base` is the offset of `bytes` in the whole file
+struct Reader<'a> {
+  bytes: &'a [u8],
+  base: usize,
+  pos: usize,
+}
+
+impl<'a> Reader<'a> {
+  fn new(bytes: &'a [u8], base: usize) -> Self {
+    Self {
+      bytes,
+      base,
+      pos: base,
+    }
+  }
+
+  fn is_empty(&self) -> bool {
+    self.pos - self.base == self.bytes.len()
+  }
+
+  fn take(&mut self, len: usize) -> Result<&'a [u8], ObjectError> {
+    let start = self.pos - self.base;
+    match self.bytes.get(start..start.saturating_add(len)) {
+      Some(bytes) => {
+        self.pos += len;
+        Ok(bytes)
+      }
+      None => Err(ObjectError::new(
+        ObjectErrorType::Truncated,
+        self.pos,
+        format!(
+          "expected {} more bytes, but only {} left",
+          len,
+          self.bytes.len() - start
+        ),
+      )),
+    }
+  }
+
+  /// The whole section should have been read
+  fn finish(&self) -> Result<(), ObjectError> {
+    if self.is_empty() {
+      Ok(())
+    } else {
+      Err(malformed(self.pos, "trailing bytes in the section".into()))
+    }
+  }
+
+  fn u8(&mut self) -> Result<u8, ObjectError> {
+    Ok(self.take(1)?[0])
+  }
+
+  fn u16(&mut self) -> Result<u16, ObjectError> {
+    let bytes = self.take(2)?;
+    Ok(u16::from_le_bytes([bytes[0], bytes[1]]))
+  }
+
+  fn u32(&mut self) -> Result<u32, ObjectError> {
+    let mut buf = [0; 4];
+    buf.copy_from_slice(self.take(4)?);
+    Ok(u32::from_le_bytes(buf))
+  }
+
+  fn i64(&mut self) -> Result<i64, ObjectError> {
+    let mut buf = [0; 8];
+    buf.copy_from_slice(self.take(8)?);
+    Ok(i64::from_le_bytes(buf))
+  }
+
+  fn string(&mut self) -> Result<String, ObjectError> {
+    let len = self.u32()? as usize;
+    let offset = self.pos;
+    String::from_utf8(self.take(len)?.to_vec())
+      .map_err(|_| malformed(offset, "string is not valid UTF-8".into()))
+  }
+}
@@ -1,16 +1,635 @@
-use crate::ast::ProgramExpr;
-use pest::Parser;
+use crate::{
+  ast::*,
+  error::{code::ErrorCode, compile_error::CompileError, error_builder::CompileErrorBuilder},
+  vm::arith::IntWidth,
+};
+use pest::{
+  error::{Error, ErrorVariant, InputLocation},
+  iterators::Pair,
+  Parser,
+};
 use pest_derive::Parser;
 
 #[derive(Parser)]
 #[grammar = "pest_parser/grammar.pest"]
-pub struct PestParser {
-  pub ast_entry: Option<Box<ProgramExpr>>,
+struct Grammar;
+
+/// The second front-end, built on `grammar.pest`, which produces the same AST
+/// (spans included) as `parser::Parser`.
+///
+/// There's no error recovery: only the first syntax error is reported, and
+/// anything after the final `end` is an error (rather than being ignored).
+#[derive(Debug, Clone)]
+pub struct PestParser<'a> {
+  source: &'a str,
+  /// byte offset of the first char of each line
+  line_starts: Vec<usize>,
+  int_width: IntWidth,
+  errors: Vec<CompileError>,
+}
+
+impl<'a> PestParser<'a> {
+  pub fn new(source: &'a str) -> Self {
+    // same line breaks as `Lexer`: `\n`, `\r\n` or a lone `\r`
+    let mut line_starts = vec![0];
+    let mut chars = source.char_indices().peekable();
+    while let Some((offset, c)) = chars.next() {
+      let ends_line = c == '\n' || (c == '\r' && chars.peek().map(|&(_, c)| c) != Some('\n'));
+      if ends_line {
+        line_starts.push(offset + 1);
+      }
+    }
+    Self {
+      source,
+      line_starts,
+      int_width: IntWidth::default(),
+      errors: vec![],
+    }
+  }
+
+  /// Integer literals out of the range of `int_width` are lexical errors
+  pub fn with_int_width(mut self, int_width: IntWidth) -> Self {
+    self.int_width = int_width;
+    self
+  }
+
+  /// Same as `PestParser::new(source).parse()`
+  pub fn parse_content(source: &str) -> Result<Box<ProgramExpr>, Vec<CompileError>> {
+    PestParser::new(source).parse()
+  }
+
+  /// Parse the whole program, diagnostics are returned as `Err`
+  pub fn parse(&mut self) -> Result<Box<ProgramExpr>, Vec<CompileError>> {
+    let mut pairs = Grammar::parse(Rule::prog, self.source).map_err(|err| vec![self.error(err)])?;
+    let program = pairs.next().unwrap().into_inner().next().unwrap();
+    let program_expr = self.program(program);
+    if self.errors.is_empty() {
+      Ok(program_expr)
+    } else {
+      Err(std::mem::take(&mut self.errors))
+    }
+  }
+}
+
+impl PestParser<'_> {
+  fn location(&self, offset: usize) -> Location {
+    let line = self.line_starts.partition_point(|&start| start <= offset);
+    let line_start = self.line_starts[line - 1];
+    let col = self.source[line_start..offset].chars().count() + 1;
+    Location(line, col)
+  }
+
+  fn span(&self, start: usize, end: usize) -> Span {
+    // location of the last char
+    let last = self.source[start..end]
+      .chars()
+      .next_back()
+      .map_or(start, |c| end - c.len_utf8());
+    Span {
+      start,
+      end,
+      location: self.location(start),
+      end_location: self.location(last),
+    }
+  }
+
+  /// The span of `pair`, without the whitespace and comments pest skips
+  /// before a trailing `?` or `*` that matches nothing
+  fn span_of(&self, pair: &Pair<Rule>) -> Span {
+    self.span(pair.as_span().start(), self.end_of(pair))
+  }
+
+  fn end_of(&self, pair: &Pair<Rule>) -> usize {
+    let end = pair.as_span().end();
+    match pair.clone().into_inner().last() {
+      Some(last) => {
+        let last_end = self.end_of(&last);
+        if skip_trivia(&self.source[last_end..end]).is_empty() {
+          last_end
+        } else {
+          end
+        }
+      }
+      None => end,
+    }
+  }
+
+  fn error(&self, err: Error<Rule>) -> CompileError {
+    let (start, end) = match err.location {
+      InputLocation::Pos(pos) => (pos, pos),
+      InputLocation::Span(span) => span,
+    };
+    let found = match self.source[start..].chars().next() {
+      Some(c) => format!("`{}`", c),
+      None => "the end of the file".to_string(),
+    };
+    let info = match err.variant {
+      ErrorVariant::ParsingError { positives, .. } if !positives.is_empty() => {
+        let mut expected = vec![];
+        for name in positives.into_iter().map(rule_name) {
+          if !expected.contains(&name) {
+            expected.push(name);
+          }
+        }
+        let expected = match expected.split_last() {
+          Some((last, [])) => last.to_owned(),
+          Some((last, rest)) => format!("{} or {}", rest.join(", "), last),
+          None => unreachable!(),
+        };
+        format!("Expected {}, but got {}", expected, found)
+      }
+      ErrorVariant::ParsingError { .. } => format!("Unexpected {}", found),
+      ErrorVariant::CustomError { message } => message,
+    };
+    CompileErrorBuilder::syntax_error_template()
+      .with_span(self.span(start, end))
+      .with_code(ErrorCode::ExpectedToken)
+      .with_info(info)
+      .build()
+  }
+}
+
+impl PestParser<'_> {
+  /// `<prog> -> program <id> ; <block>`
+  fn program(&mut self, pair: Pair<Rule>) -> Box<ProgramExpr> {
+    let span = self.span_of(&pair);
+    let mut inner = children(pair);
+    let id = self.id(inner.next().unwrap());
+    let block = self.block(inner.next().unwrap());
+    Box::new(ProgramExpr { id, block, span })
+  }
+
+  /// `<block> -> [<const-decl>][<var-decl>][<proc>]<body>`
+  fn block(&mut self, pair: Pair<Rule>) -> Box<BlockExpr> {
+    let span = self.span_of(&pair);
+    let mut const_decl = None;
+    let mut var_decl = None;
+    let mut procs = vec![];
+    let mut body = None;
+    for pair in children(pair) {
+      match pair.as_rule() {
+        Rule::const_decl => const_decl = Some(self.const_decl(pair)),
+        Rule::var_decl => var_decl = Some(self.var_decl(pair)),
+        Rule::proc => procs.push(self.proc(pair)),
+        Rule::body => body = Some(self.body(pair)),
+        rule => unreachable!("{:?}", rule),
+      }
+    }
+    // `p1; p2; p3` is nested as `p1 { procs: [p2 { procs: [p3] }] }`, the
+    // same as `Parser`
+    let proc = procs.into_iter().rev().reduce(|next, mut proc| {
+      proc.procs.push(next);
+      proc
+    });
+    Box::new(BlockExpr {
+      const_decl,
+      var_decl,
+      proc,
+      body: body.unwrap(),
+      span,
+    })
+  }
+
+  /// `<const-decl> -> const <const> {, <const>} ;`
+  fn const_decl(&mut self, pair: Pair<Rule>) -> Box<ConstDeclExpr> {
+    let span = self.span_of(&pair);
+    let constants = children(pair).map(|pair| self.constant(pair)).collect();
+    Box::new(ConstDeclExpr { constants, span })
+  }
+
+  /// `<const> -> <id> := <integer>`
+  fn constant(&mut self, pair: Pair<Rule>) -> Box<ConstExpr> {
+    let span = self.span_of(&pair);
+    let mut inner = children(pair);
+    let id = self.id(inner.next().unwrap());
+    let integer = self.integer(inner.next().unwrap());
+    Box::new(ConstExpr { id, integer, span })
+  }
+
+  /// `<var-decl> -> var <var-def> {, <var-def>} ;`
+  fn var_decl(&mut self, pair: Pair<Rule>) -> Box<VarDeclExpr> {
+    let span = self.span_of(&pair);
+    let mut id_list = vec![];
+    let mut bounds = vec![];
+    for pair in children(pair) {
+      match pair.as_rule() {
+        Rule::id => {
+          id_list.push(self.id(pair));
+          bounds.push(None);
+        }
+        _ => *bounds.last_mut().unwrap() = Some(self.bound(pair)),
+      }
+    }
+    Box::new(VarDeclExpr {
+      id_list,
+      bounds,
+      span,
+    })
+  }
+
+  /// `<bound> -> "[" <integer> [.. <integer>] "]"`
+  fn bound(&mut self, pair: Pair<Rule>) -> Box<BoundExpr> {
+    let span = self.span_of(&pair);
+    let mut inner = children(pair);
+    let lo = self.integer(inner.next().unwrap());
+    match inner.next() {
+      Some(hi) => Box::new(BoundExpr::Range(lo, self.integer(hi), span)),
+      None => Box::new(BoundExpr::Len(lo, span)),
+    }
+  }
+
+  /// `<var> -> <id> [<index>]`, `(<id>, <index>)` of `pairs`, from where
+  /// `<var>` is silent
+  fn vars<'i>(
+    &mut self,
+    pairs: impl Iterator<Item = Pair<'i, Rule>>,
+  ) -> Vec<(Box<IdExpr>, Option<Box<ExpExpr>>)> {
+    let mut vars = vec![];
+    for pair in pairs {
+      match pair.as_rule() {
+        Rule::id => vars.push((self.id(pair), None)),
+        _ => vars.last_mut().unwrap().1 = Some(self.index(pair)),
+      }
+    }
+    vars
+  }
+
+  /// `<index> -> "[" <exp> "]"`
+  fn index(&mut self, pair: Pair<Rule>) -> Box<ExpExpr> {
+    self.exp(children(pair).next().unwrap())
+  }
+
+  /// `(procedure | function) <id> ([<id> {, <id>}]) ; <block>`, without
+  /// `{; <proc>}`
+  fn proc(&mut self, pair: Pair<Rule>) -> Box<ProcExpr> {
+    let span = self.span_of(&pair);
+    let is_function = pair.clone().into_inner().next().unwrap().as_rule() == Rule::FUNCTION;
+    let mut ids = vec![];
+    let mut by_ref = vec![];
+    let mut block = None;
+    for pair in children(pair) {
+      match pair.as_rule() {
+        Rule::id => {
+          ids.push(self.id(pair));
+          by_ref.push(false);
+        }
+        Rule::ref_param => {
+          ids.push(self.id(children(pair).next().unwrap()));
+          by_ref.push(true);
+        }
+        _ => block = Some(self.block(pair)),
+      }
+    }
+    let id = ids.remove(0);
+    by_ref.remove(0);
+    Box::new(ProcExpr {
+      id,
+      is_function,
+      args: ids,
+      by_ref,
+      block: block.unwrap(),
+      procs: vec![],
+      span,
+    })
+  }
+
+  /// `<body> -> begin <statement> {; <statement>} end`
+  fn body(&mut self, pair: Pair<Rule>) -> Box<BodyExpr> {
+    let span = self.span_of(&pair);
+    let statements = children(pair).map(|pair| self.statement(pair)).collect();
+    Box::new(BodyExpr { statements, span })
+  }
+
+  fn statement(&mut self, pair: Pair<Rule>) -> Box<StatementExpr> {
+    let span = self.span_of(&pair);
+    let rule = pair.as_rule();
+    if rule == Rule::body {
+      let body = self.body(pair);
+      return Box::new(StatementExpr::Body { body, span });
+    }
+    let mut inner = children(pair);
+    let statement = match rule {
+      Rule::assign => {
+        let mut pairs = inner.collect::<Vec<_>>();
+        let exp = self.exp(pairs.pop().unwrap());
+        let (id, index) = self.vars(pairs.into_iter()).pop().unwrap();
+        StatementExpr::Id {
+          id,
+          index,
+          exp,
+          span,
+        }
+      }
+      Rule::if_stmt => StatementExpr::If {
+        l_exp: self.l_exp(inner.next().unwrap()),
+        then_statement: self.statement(inner.next().unwrap()),
+        else_statement: inner.next().map(|pair| self.statement(pair)),
+        span,
+      },
+      Rule::while_stmt => StatementExpr::While {
+        l_exp: self.l_exp(inner.next().unwrap()),
+        statement: self.statement(inner.next().unwrap()),
+        span,
+      },
+      Rule::repeat_stmt => {
+        let mut pairs = inner.collect::<Vec<_>>();
+        let l_exp = self.l_exp(pairs.pop().unwrap());
+        StatementExpr::Repeat {
+          statements: pairs.into_iter().map(|pair| self.statement(pair)).collect(),
+          l_exp,
+          span,
+        }
+      }
+      Rule::for_stmt => StatementExpr::For {
+        id: self.id(inner.next().unwrap()),
+        from: self.exp(inner.next().unwrap()),
+        // `TO` / `DOWNTO` is kept by `children`
+        is_downto: inner.next().unwrap().as_rule() == Rule::DOWNTO,
+        to: self.exp(inner.next().unwrap()),
+        statement: self.statement(inner.next().unwrap()),
+        span,
+      },
+      Rule::call => StatementExpr::Call {
+        id: self.id(inner.next().unwrap()),
+        args: inner.map(|pair| self.exp(pair)).collect(),
+        span,
+      },
+      Rule::read => {
+        let (id_list, index_list) = self.vars(inner).into_iter().unzip();
+        StatementExpr::Read {
+          id_list,
+          index_list,
+          span,
+        }
+      }
+      Rule::write => StatementExpr::Write {
+        exps: inner.map(|pair| self.exp(pair)).collect(),
+        span,
+      },
+      rule => unreachable!("{:?}", rule),
+    };
+    Box::new(statement)
+  }
+
+  /// `<l-exp> -> <l-term> {or <l-term>}` and
+  /// `<l-term> -> <l-factor> {and <l-factor>}`, left associative (same as
+  /// `Parser`)
+  fn l_exp(&mut self, pair: Pair<Rule>) -> Box<LExpExpr> {
+    let is_or = pair.as_rule() == Rule::l_exp;
+    let start = self.span_of(&pair);
+    let mut inner = children(pair);
+    let mut l_exp = self.l_operand(inner.next().unwrap());
+    for pair in inner {
+      let span = start.to(self.span_of(&pair));
+      let r_exp = self.l_operand(pair);
+      l_exp = Box::new(match is_or {
+        true => LExpExpr::Or { l_exp, r_exp, span },
+        false => LExpExpr::And { l_exp, r_exp, span },
+      });
+    }
+    l_exp
+  }
+
+  /// `<l-term>` of `<l-exp>`, or `<l-factor>` of `<l-term>`
+  fn l_operand(&mut self, pair: Pair<Rule>) -> Box<LExpExpr> {
+    match pair.as_rule() {
+      Rule::l_term => self.l_exp(pair),
+      _ => self.l_factor(pair),
+    }
+  }
+
+  /// `<l-factor> -> not <l-factor> | "(" <l-exp> ")" | <exp> <lop> <exp> | odd <exp>`
+  fn l_factor(&mut self, pair: Pair<Rule>) -> Box<LExpExpr> {
+    let span = self.span_of(&pair);
+    let rule = pair.as_rule();
+    let mut inner = children(pair);
+    let l_exp = match rule {
+      Rule::paren_l_exp => return self.l_exp(inner.next().unwrap()),
+      Rule::not_exp => LExpExpr::Not {
+        l_exp: self.l_factor(inner.next().unwrap()),
+        span,
+      },
+      Rule::odd_exp => LExpExpr::Odd {
+        exp: self.exp(inner.next().unwrap()),
+        span,
+      },
+      Rule::cmp_exp => LExpExpr::Exp {
+        l_exp: self.exp(inner.next().unwrap()),
+        lop: self.lop(inner.next().unwrap()),
+        r_exp: self.exp(inner.next().unwrap()),
+        span,
+      },
+      rule => unreachable!("{:?}", rule),
+    };
+    Box::new(l_exp)
+  }
+
+  /// `<exp> -> [+|-] <term> {<aop> <term>}`
+  fn exp(&mut self, pair: Pair<Rule>) -> Box<ExpExpr> {
+    let span = self.span_of(&pair);
+    let mut inner = children(pair).peekable();
+    let is_negative = match inner.next_if(|pair| pair.as_rule() == Rule::sign) {
+      Some(sign) => sign.as_str() == "-",
+      None => false,
+    };
+    let term = self.term(inner.next().unwrap());
+    let mut aop_terms = vec![];
+    while let Some(aop) = inner.next() {
+      let aop = self.aop(aop);
+      let term = self.term(inner.next().unwrap());
+      aop_terms.push((aop, term));
+    }
+    Box::new(ExpExpr {
+      is_negative,
+      term,
+      aop_terms,
+      span,
+    })
+  }
+
+  /// `<term> -> <factor> {<mop> <factor>}`
+  fn term(&mut self, pair: Pair<Rule>) -> Box<TermExpr> {
+    let span = self.span_of(&pair);
+    let mut inner = children(pair);
+    let factor = self.factor(inner.next().unwrap());
+    let mut mop_factors = vec![];
+    while let Some(mop) = inner.next() {
+      let mop = self.mop(mop);
+      let factor = self.factor(inner.next().unwrap());
+      mop_factors.push((mop, factor));
+    }
+    Box::new(TermExpr {
+      factor,
+      mop_factors,
+      span,
+    })
+  }
+
+  /// `<factor> -> <id> [<args> | <index>] | <integer> | (<exp>)`
+  fn factor(&mut self, pair: Pair<Rule>) -> Box<FactorExpr> {
+    let factor = match pair.as_rule() {
+      Rule::id => FactorExpr::Id(self.id(pair)),
+      Rule::integer => FactorExpr::Integer(self.integer(pair)),
+      Rule::paren_exp => {
+        let span = self.span_of(&pair);
+        FactorExpr::Exp(self.exp(children(pair).next().unwrap()), span)
+      }
+      Rule::call_exp => {
+        let span = self.span_of(&pair);
+        let mut inner = children(pair);
+        FactorExpr::Call {
+          id: self.id(inner.next().unwrap()),
+          args: inner.map(|pair| self.exp(pair)).collect(),
+          span,
+        }
+      }
+      Rule::index_exp => {
+        let span = self.span_of(&pair);
+        let mut inner = children(pair);
+        FactorExpr::Index {
+          id: self.id(inner.next().unwrap()),
+          index: self.index(inner.next().unwrap()),
+          span,
+        }
+      }
+      rule => unreachable!("{:?}", rule),
+    };
+    Box::new(factor)
+  }
+
+  fn lop(&self, pair: Pair<Rule>) -> Box<LopExpr> {
+    let span = self.span_of(&pair);
+    let lop = match pair.as_str() {
+      "=" => LopExpr::Eq(span),
+      "<>" => LopExpr::Ne(span),
+      "<" => LopExpr::Lt(span),
+      "<=" => LopExpr::Le(span),
+      ">" => LopExpr::Gt(span),
+      ">=" => LopExpr::Ge(span),
+      lop => unreachable!("{}", lop),
+    };
+    Box::new(lop)
+  }
+
+  fn aop(&self, pair: Pair<Rule>) -> Box<AopExpr> {
+    let span = self.span_of(&pair);
+    match pair.as_str() {
+      "+" => Box::new(AopExpr::Add(span)),
+      _ => Box::new(AopExpr::Sub(span)),
+    }
+  }
+
+  fn mop(&self, pair: Pair<Rule>) -> Box<MopExpr> {
+    let span = self.span_of(&pair);
+    match pair.as_str() {
+      "*" => Box::new(MopExpr::Mul(span)),
+      _ => Box::new(MopExpr::Div(span)),
+    }
+  }
+
+  fn id(&self, pair: Pair<Rule>) -> Box<IdExpr> {
+    Box::new(IdExpr(pair.as_str().to_string(), self.span_of(&pair)))
+  }
+
+  /// Out of the range of `int_width`: a lexical error (same as `Lexer`)
+  fn integer(&mut self, pair: Pair<Rule>) -> Box<IntegerExpr> {
+    let span = self.span_of(&pair);
+    let integer = pair
+      .as_str()
+      .parse::<i64>()
+      .ok()
+      .filter(|&integer| self.int_width.contains(integer));
+    if integer.is_none() {
+      let err = CompileErrorBuilder::lexical_error_template()
+        .with_span(span)
+        .with_code(ErrorCode::IntegerOverflow)
+        .with_info(format!(
+          "integer literal is out of range for `{}`",
+          self.int_width
+        ))
+        .with_help(format!(
+          "the maximum value of `{}` is {}",
+          self.int_width,
+          self.int_width.max()
+        ))
+        .build();
+      self.errors.push(err);
+    }
+    Box::new(IntegerExpr(integer.unwrap_or_default(), span))
+  }
+}
+
+/// Inner pairs, without keywords (which are kept as pairs, only to be atomic)
+///
+/// `TO` / `DOWNTO` of `for` are kept, they tell the direction
+fn children(pair: Pair<Rule>) -> impl Iterator<Item = Pair<Rule>> {
+  pair.into_inner().filter(|pair| {
+    !matches!(
+      pair.as_rule(),
+      Rule::CONST
+        | Rule::PROGRAM
+        | Rule::VAR
+        | Rule::PROCEDURE
+        | Rule::FUNCTION
+        | Rule::BEGIN
+        | Rule::END
+        | Rule::IF
+        | Rule::THEN
+        | Rule::ELSE
+        | Rule::WHILE
+        | Rule::DO
+        | Rule::REPEAT
+        | Rule::UNTIL
+        | Rule::FOR
+        | Rule::CALL
+        | Rule::READ
+        | Rule::WRITE
+        | Rule::ODD
+        | Rule::AND
+        | Rule::OR
+        | Rule::NOT
+    )
+  })
+}
+
+/// e.g. `<exp>`, `` `end` ``
+fn rule_name(rule: Rule) -> String {
+  let name = format!("{:?}", rule);
+  match rule {
+    Rule::EOI => "the end of the file".to_string(),
+    Rule::if_stmt
+    | Rule::while_stmt
+    | Rule::repeat_stmt
+    | Rule::for_stmt
+    | Rule::assign
+    | Rule::call
+    | Rule::read
+    | Rule::write => "<statement>".to_string(),
+    Rule::odd_exp | Rule::not_exp | Rule::cmp_exp | Rule::paren_l_exp => "<l-factor>".to_string(),
+    Rule::paren_exp | Rule::call_exp | Rule::index_exp => "<factor>".to_string(),
+    Rule::sign => "<aop>".to_string(),
+    Rule::ref_param => "<param>".to_string(),
+    _ if name.chars().all(|c| c.is_ascii_uppercase()) => format!("`{}`", name.to_lowercase()),
+    _ => format!("<{}>", name.replace('_', "-")),
+  }
 }
 
-impl PestParser {
-  pub fn parse_content(str: &str) -> Option<Box<ProgramExpr>> {
-    let _ = PestParser::parse(Rule::prog, str);
-    None
+/// `s` without its leading whitespace and comments
+fn skip_trivia(mut s: &str) -> &str {
+  loop {
+    s = s.trim_start_matches([' ', '\t', '\r', '\n']);
+    let rest = if let Some(rest) = s.strip_prefix('{') {
+      rest.find('}').map(|i| &rest[i + 1..])
+    } else if let Some(rest) = s.strip_prefix("(*") {
+      rest.find("*)").map(|i| &rest[i + 2..])
+    } else {
+      let line_end = |c| c == '\n' || c == '\r';
+      s.strip_prefix("//")
+        .map(|rest| &rest[rest.find(line_end).unwrap_or(rest.len())..])
+    };
+    match rest {
+      Some(rest) => s = rest,
+      None => return s,
+    }
   }
 }
@@ -1,4 +1,5 @@
 use self::{sym_type::SymType, table_row::TableRow};
+use crate::ast::Location;
 
 pub mod sym_type;
 pub mod table_row;
@@ -11,6 +12,13 @@ pub struct SymTable {
 
 impl SymTable {
   pub fn try_find_closest_sym(&self, name: &str, curr_scope_list: &[String]) -> Option<&TableRow> {
+    self
+      .try_find_closest_pos(name, curr_scope_list)
+      .map(|pos| &self.table[pos])
+  }
+
+  /// Position of `try_find_closest_sym` in `table`
+  pub fn try_find_closest_pos(&self, name: &str, curr_scope_list: &[String]) -> Option<usize> {
     // must `rev()`
     //
     // you should find a symbol with as higher level as you can
@@ -18,7 +26,7 @@ impl SymTable {
     // higher level's symbol always appears later in the linear table
     //
     // another condition: sym.scope_list was totally contained by curr_scope_list
-    self.table.iter().rev().find(|&sym| {
+    self.table.iter().rposition(|sym| {
       sym.name == name
         && sym
           .scope_list
@@ -27,13 +35,20 @@ impl SymTable {
     })
   }
 
+  /// Args of the procedure / function at `pos`, which are loaded right after
+  /// it
+  pub fn args_of(&self, pos: usize) -> &[TableRow] {
+    &self.table[pos + 1..pos + 1 + self.table[pos].size]
+  }
+
   pub fn find_closest_sym(&self, name: &str, curr_scope_list: &[String]) -> &TableRow {
     self.try_find_closest_sym(name, curr_scope_list).unwrap()
   }
 
+  /// Procedure or function
   pub fn get_proc_in_curr_level(&self) -> Option<usize> {
     for i in (0..self.table.len()).rev() {
-      if let SymType::Proc = self.table[i].ty {
+      if let SymType::Proc | SymType::Func = self.table[i].ty {
         return Some(i);
       }
     }
@@ -54,13 +69,16 @@ impl SymTable {
     false
   }
 
+  /// Symbol defined in exactly `curr_scope_list`
+  pub fn try_find_now(&self, name: &str, curr_scope_list: &[String]) -> Option<&TableRow> {
+    self
+      .table
+      .iter()
+      .find(|&sym| sym.name == name && sym.scope_list == curr_scope_list)
+  }
+
   pub fn is_now_exists(&self, name: &str, curr_scope_list: &[String]) -> bool {
-    for sym in &self.table {
-      if sym.name == name && sym.scope_list == curr_scope_list {
-        return true;
-      }
-    }
-    false
+    self.try_find_now(name, curr_scope_list).is_some()
   }
 
   pub fn load_const(
@@ -70,6 +88,7 @@ impl SymTable {
     val: i64,
     addr: usize,
     scope_list: Vec<String>,
+    location: Location,
   ) {
     let value = TableRow {
       ty: SymType::Const,
@@ -79,12 +98,20 @@ impl SymTable {
       size: 0,
       name: name.to_string(),
       scope_list,
+      location,
     };
     self.table.push(value);
     self.table_ptr += 1;
   }
 
-  pub fn load_var(&mut self, name: &str, level: usize, addr: usize, scope_list: Vec<String>) {
+  pub fn load_var(
+    &mut self,
+    name: &str,
+    level: usize,
+    addr: usize,
+    scope_list: Vec<String>,
+    location: Location,
+  ) {
     let value = TableRow {
       ty: SymType::Var,
       val: 0,
@@ -93,12 +120,20 @@ impl SymTable {
       size: 0,
       name: name.to_string(),
       scope_list,
+      location,
     };
     self.table.push(value);
     self.table_ptr += 1;
   }
 
-  pub fn load_proc(&mut self, name: &str, level: usize, addr: usize, scope_list: Vec<String>) {
+  pub fn load_proc(
+    &mut self,
+    name: &str,
+    level: usize,
+    addr: usize,
+    scope_list: Vec<String>,
+    location: Location,
+  ) {
     let value = TableRow {
       ty: SymType::Proc,
       val: 0,
@@ -107,8 +142,59 @@ impl SymTable {
       size: 0,
       name: name.to_string(),
       scope_list,
+      location,
     };
     self.table.push(value);
     self.table_ptr += 1;
   }
+
+  /// Same as `load_proc`, `size` (number of args) is set later as well
+  pub fn load_func(
+    &mut self,
+    name: &str,
+    level: usize,
+    addr: usize,
+    scope_list: Vec<String>,
+    location: Location,
+  ) {
+    self.load_proc(name, level, addr, scope_list, location);
+    self.table[self.table_ptr - 1].ty = SymType::Func;
+  }
+
+  /// Same as `load_var`, but the arg is passed by reference
+  pub fn load_ref(
+    &mut self,
+    name: &str,
+    level: usize,
+    addr: usize,
+    scope_list: Vec<String>,
+    location: Location,
+  ) {
+    self.load_var(name, level, addr, scope_list, location);
+    self.table[self.table_ptr - 1].ty = SymType::Ref;
+  }
+
+  /// Same as `load_var`, `val` (the lower bound) and `size` (the length) are
+  /// set later
+  pub fn load_array(
+    &mut self,
+    name: &str,
+    level: usize,
+    addr: usize,
+    scope_list: Vec<String>,
+    location: Location,
+  ) {
+    self.load_var(name, level, addr, scope_list, location);
+    self.table[self.table_ptr - 1].ty = SymType::Array;
+  }
+
+  /// Move the entry (`val`) of each procedure to `relocate(val)`, e.g. after
+  /// pcode has been removed by `PeepholeOptimizer`
+  pub fn relocate_procs(&mut self, relocate: impl Fn(usize) -> usize) {
+    for row in &mut self.table {
+      if matches!(row.ty, SymType::Proc | SymType::Func) {
+        row.set_val(relocate(row.val as usize) as i64);
+      }
+    }
+  }
 }
@@ -7,6 +7,12 @@ pub enum SymType {
   Const = 1,
   Var = 2,
   Proc = 3,
+  Func = 4,
+  /// an arg passed by reference, holding the address of a variable
+  Ref = 5,
+  /// elements are stored from `addr`, `val` is the lower bound, `size` is the
+  /// length
+  Array = 6,
 }
 
 impl Display for SymType {
@@ -16,6 +22,9 @@ impl Display for SymType {
       SymType::Const => write!(f, "const"),
       SymType::Var => write!(f, "var"),
       SymType::Proc => write!(f, "proc"),
+      SymType::Func => write!(f, "func"),
+      SymType::Ref => write!(f, "ref"),
+      SymType::Array => write!(f, "array"),
     }
   }
 }
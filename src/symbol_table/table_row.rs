@@ -1,4 +1,5 @@
 use super::sym_type::SymType;
+use crate::ast::Location;
 
 #[derive(Debug, Default, Clone)]
 pub struct TableRow {
@@ -9,6 +10,8 @@ pub struct TableRow {
   pub size: usize,
   pub name: String,
   pub scope_list: Vec<String>,
+  /// where it's defined (default if loaded from an object file)
+  pub location: Location,
 }
 
 impl TableRow {
@@ -1,11 +1,12 @@
 use crate::{
   ast::{
-    AopExpr, BlockExpr, BodyExpr, ConstDeclExpr, ConstExpr, ExpExpr, FactorExpr, LExpExpr, LopExpr,
-    MopExpr, ProcExpr, ProgramExpr, StatementExpr, TermExpr, VarDeclExpr,
+    AopExpr, BlockExpr, BodyExpr, BoundExpr, ConstDeclExpr, ConstExpr, ExpExpr, FactorExpr, IdExpr,
+    LExpExpr, Location, LopExpr, MopExpr, ProcExpr, ProgramExpr, StatementExpr, TermExpr,
+    VarDeclExpr,
   },
-  error::error_builder::CompileErrorBuilder,
+  error::{code::ErrorCode, compile_error::CompileError, error_builder::CompileErrorBuilder},
   pcode::{PCodeManager, PcodeType},
-  symbol_table::{sym_type::SymType, SymTable},
+  symbol_table::{sym_type::SymType, table_row::TableRow, SymTable},
   SEP,
 };
 
@@ -18,10 +19,17 @@ pub struct Translator {
   pub pcode: PCodeManager,
   pub sym_table: SymTable,
   pub has_error: bool,
+  pub errors: Vec<CompileError>,
   pub level: usize,
   pub addr: usize,
   pub addr_increment: usize,
   pub scope_list: Vec<String>,
+  pub program_name: String,
+  /// slots of the current frame, the hidden bounds of `for` loops included
+  pub frame_size: usize,
+  /// control variables of the enclosing `for` loops (with the location of
+  /// each loop), which are read-only
+  pub loop_vars: Vec<(TableRow, Location)>,
 }
 
 impl Translator {
@@ -56,54 +64,90 @@ impl Default for Translator {
       pcode: Default::default(),
       sym_table: Default::default(),
       has_error: Default::default(),
+      errors: Default::default(),
       level: Default::default(),
       addr: Default::default(),
       addr_increment: 1,
       scope_list: Default::default(),
+      program_name: Default::default(),
+      frame_size: Default::default(),
+      loop_vars: Default::default(),
     }
   }
 }
 
 impl Translator {
-  pub fn translate(&mut self, entry: &ProgramExpr) -> PCodeManager {
+  /// Translate the AST into pcode.
+  ///
+  /// Every semantic error is collected (in the order of detection) and
+  /// returned as `Err`, nothing will be printed.
+  pub fn translate(&mut self, entry: &ProgramExpr) -> Result<PCodeManager, Vec<CompileError>> {
     self.program(entry);
     if !self.has_error {
-      self.pcode.to_owned()
+      Ok(self.pcode.to_owned())
     } else {
-      panic!("|> Errors above occurred (during `translation/codegen`), compiling stopped ... <|\n")
+      Err(std::mem::take(&mut self.errors))
     }
   }
 }
 
 impl Translator {
   fn program(&mut self, expr: &ProgramExpr) {
-    self.block(&expr.block);
+    self.program_name = expr.id.as_ref().0.to_owned();
+    self.block(&expr.block, expr.span.location);
   }
 
-  /// temporarily not support for non-zero-arg-proc
-  fn block(&mut self, expr: &BlockExpr) {
+  /// Map pcode generated later into the innermost procedure of `scope_list`
+  /// (e.g. `a.aa` for `aa` nested in `a`, or the program name for the entry)
+  fn sync_proc_name(&mut self) {
+    if self.scope_list.len() > 1 {
+      self.pcode.set_proc(&self.scope_list[1..].join("."));
+    } else {
+      self.pcode.set_proc(&self.program_name);
+    }
+  }
+
+  /// The prologue (`JMP`, `STA`s and `INT`) is mapped to `location`, the
+  /// identifier of the procedure (or the start of the program)
+  fn block(&mut self, expr: &BlockExpr, location: Location) {
     // tmp
     let old_addr = self.addr;
 
     // init curr level
     let start = self.sym_table.table_ptr;
     let mut pos = 0;
+    let mut is_func = false;
     self.addr = 3; // DL - SL - RA
 
     // if not main
     if start != 0 {
       pos = self.sym_table.get_proc_in_curr_level().unwrap();
+      is_func = matches!(self.sym_table.table[pos].ty, SymType::Func);
 
       // hold the args
       self.addr += self.sym_table.table[pos].size;
+      // and the result of a function (right after the args)
+      if is_func {
+        self.addr += 1;
+      }
     } else {
       self.scope_list.push(ENTRY_SCOPE_NAME.into());
+      self.sync_proc_name();
     }
 
     // (jmp, 0, 0)
+    self.pcode.set_location(location);
     let tmp_pcode_ptr = self.pcode.get_pcode_ptr();
     self.pcode.gen(PcodeType::JMP, 0, 0);
 
+    // if not main
+    //
+    // the `JMP` above is the entry of curr_proc, it's known before the nested
+    // procedures are translated, which may call curr_proc as well
+    if start != 0 {
+      self.sym_table.table[pos].set_val(tmp_pcode_ptr as i64);
+    }
+
     if let Some(expr) = &expr.const_decl {
       self.const_decl(expr);
     }
@@ -116,6 +160,14 @@ impl Translator {
       self.level -= 1;
     }
 
+    // nested procedures have moved the location on
+    self.sync_proc_name();
+    self.pcode.set_location(location);
+
+    // fix jmp
+    let fixed_a = self.pcode.get_pcode_ptr() as i64;
+    self.pcode.pcode_list[tmp_pcode_ptr].set_a(fixed_a);
+
     // if not main
     if start != 0 {
       // use STA to load params immediately (data stack, reversed order)
@@ -128,10 +180,6 @@ impl Translator {
       }
     }
 
-    // fix jmp
-    let fixed_a = self.pcode.get_pcode_ptr() as i64;
-    self.pcode.pcode_list[tmp_pcode_ptr].set_a(fixed_a);
-
     // allocate
     //
     // right now, after `load params`, self.addr (same as data_stack.top)
@@ -140,27 +188,35 @@ impl Translator {
     //
     // so, INT self.addr will move `data_stack.top` to new area of curr proc's
     // data slice
+    //
+    // `for` loops in the body may need more slots, it's fixed after the body
+    let int_pcode_ptr = self.pcode.get_pcode_ptr();
     self.pcode.gen(PcodeType::INT, 0, self.addr as i64);
+    self.frame_size = self.addr;
 
-    // if not main
-    if start != 0 {
-      // set curr_proc's sym_value into curr_proc's begin pos
-      //
-      // right now, after `load params` and `increase(allocate) space`, the slice of `pcode`
-      // for current procedure's head part must be:
-      //
-      // - STA <ptr-move-back-operand> <param-relative-address>
-      // - ... (STA repeat for `curr_proc.args.count` times)
-      // - INT 0 <>
-      let val = self.pcode.get_pcode_ptr() - 1 - self.sym_table.table[pos].size;
-      self.sym_table.table[pos].set_val(val as i64);
+    // the result is `0` if it's never assigned
+    if is_func {
+      self.pcode.gen(PcodeType::LIT, 0, 0);
+      self.pcode.gen(PcodeType::STO, 0, self.result_addr(pos));
     }
 
     // call body
     self.body(&expr.body);
+    let frame_size = self.frame_size as i64;
+    self.pcode.pcode_list[int_pcode_ptr].set_a(frame_size);
 
     // end of procedure
-    self.pcode.gen(PcodeType::OPR, 0, 0);
+    //
+    // a function drops its args and leaves the result on the caller's stack
+    // by `OPR <n_args> 17` instead
+    if is_func {
+      self.pcode.gen(PcodeType::LOD, 0, self.result_addr(pos));
+      self
+        .pcode
+        .gen(PcodeType::OPR, self.sym_table.table[pos].size, 17);
+    } else {
+      self.pcode.gen(PcodeType::OPR, 0, 0);
+    }
 
     // resume addr
     self.addr = old_addr;
@@ -175,40 +231,67 @@ impl Translator {
     let name = expr.id.as_ref().0.to_owned();
 
     // duplicate-definition
-    if self.sym_table.is_now_exists(&name, &self.scope_list) {
+    //
+    // keep translating, so that `level` stays balanced and errors
+    // inside the procedure (and its siblings) could still be found
+    if let Some(prev) = self.sym_table.try_find_now(&name, &self.scope_list) {
       self.has_error = true;
-      CompileErrorBuilder::from(expr.id.as_ref().1)
+      let err = CompileErrorBuilder::from(expr.id.as_ref().1)
+        .with_code(ErrorCode::Redefined)
         .with_info(format!("`{}` is defined before", name))
-        .build()
-        .show();
-      return;
+        .with_label(prev.location, "first defined here".to_string())
+        .build();
+      self.errors.push(err);
     }
 
     let proc_pos = self.sym_table.table_ptr;
-    self
-      .sym_table
-      .load_proc(&name, self.level, self.addr, self.scope_list.to_owned());
+    let load = if expr.is_function {
+      SymTable::load_func
+    } else {
+      SymTable::load_proc
+    };
+    load(
+      &mut self.sym_table,
+      &name,
+      self.level,
+      self.addr,
+      self.scope_list.to_owned(),
+      expr.id.as_ref().1.location,
+    );
     self.addr += self.addr_increment;
     self.level += 1; // update level
 
     self.scope_list.push(name.to_owned());
+    self.sync_proc_name();
+    self.pcode.set_location(expr.id.as_ref().1.location);
 
     // args
-    for arg in &expr.args {
+    for (arg, &is_ref) in expr.args.iter().zip(&expr.by_ref) {
       let id = arg.as_ref().0.to_owned();
+      let load = if is_ref {
+        SymTable::load_ref
+      } else {
+        SymTable::load_var
+      };
       // +3 :: DL - SL - RA
-      self
-        .sym_table
-        .load_var(&id, self.level, args_count + 3, self.scope_list.to_owned());
+      load(
+        &mut self.sym_table,
+        &id,
+        self.level,
+        args_count + 3,
+        self.scope_list.to_owned(),
+        arg.as_ref().1.location,
+      );
       args_count += 1;
       self.sym_table.table[proc_pos].set_size(args_count);
     }
 
     // block
-    self.block(&expr.block);
+    self.block(&expr.block, expr.id.as_ref().1.location);
 
     // resume scope
     self.scope_list.pop();
+    self.sync_proc_name();
 
     // procs
     for proc_expr in &expr.procs {
@@ -222,21 +305,220 @@ impl Translator {
       self.statement(expr);
     }
   }
+
+  /// Relative address of the result of the function at `pos`, right after
+  /// its args
+  fn result_addr(&self, pos: usize) -> i64 {
+    (3 + self.sym_table.table[pos].size) as i64
+  }
+
+  /// Whether the body of `sym` (a procedure or function) is being translated,
+  /// nested procedures inside it included
+  fn is_inside(&self, sym: &TableRow) -> bool {
+    self.scope_list.len() > sym.scope_list.len()
+      && self.scope_list.starts_with(&sym.scope_list)
+      && self.scope_list[sym.scope_list.len()] == sym.name
+  }
+
+  /// Check the number of args of a call to the procedure / function at `pos`,
+  /// then evaluate them (or take their addresses, if passed by reference)
+  fn args(&mut self, id: &IdExpr, pos: usize, args: &[Box<ExpExpr>]) -> bool {
+    let sym = &self.sym_table.table[pos];
+    if sym.size != args.len() {
+      self.has_error = true;
+      let err = CompileErrorBuilder::from(id.1)
+        .with_code(ErrorCode::ArgsCountMismatch)
+        .with_info(format!(
+          "`{}` expects {} args, but received {}",
+          id.0,
+          sym.size,
+          args.len()
+        ))
+        .build();
+      self.errors.push(err);
+      return false;
+    }
+
+    let by_ref = self
+      .sym_table
+      .args_of(pos)
+      .iter()
+      .map(|row| matches!(row.ty, SymType::Ref))
+      .collect::<Vec<_>>();
+    for (arg, is_ref) in args.iter().zip(by_ref) {
+      if is_ref {
+        self.ref_arg(arg);
+      } else {
+        // eval-exp
+        self.exp(arg);
+      }
+    }
+    true
+  }
+
+  /// Address of the variable (or element of an array) `expr`, for an arg
+  /// passed by reference
+  fn ref_arg(&mut self, expr: &ExpExpr) {
+    let (id, index) = match expr.as_factor() {
+      Some(FactorExpr::Id(id)) => (id, None),
+      Some(FactorExpr::Index { id, index, .. }) => (id, Some(index)),
+      _ => {
+        self.has_error = true;
+        let err = CompileErrorBuilder::from(expr.span)
+          .with_code(ErrorCode::NotAReference)
+          .with_info("only a variable could be passed by reference".to_string())
+          .build();
+        self.errors.push(err);
+        return;
+      }
+    };
+
+    // offset of the element, plus LDA (address of the first element)
+    if let Some(index) = index {
+      if let Some(sym) = self.element(id, index) {
+        self
+          .pcode
+          .gen(PcodeType::LDA, self.level - sym.level, sym.addr as i64);
+        self.pcode.gen(PcodeType::OPR, 0, 2);
+      }
+      return;
+    }
+
+    // undefined
+    let name = id.0.to_owned();
+    let Some(tmp_sym) = self.sym_table.try_find_closest_sym(&name, &self.scope_list) else {
+      self.has_error = true;
+      let err = CompileErrorBuilder::from(id.1)
+        .with_code(ErrorCode::Undefined)
+        .with_info(format!("`{}` is undefined", name))
+        .build();
+      self.errors.push(err);
+      return;
+    };
+    let tmp_sym = tmp_sym.to_owned();
+    if !self.check_loop_var(id, &tmp_sym) {
+      return;
+    }
+
+    let (level, addr) = (self.level - tmp_sym.level, tmp_sym.addr as i64);
+    self.pcode.set_location(id.1.location);
+    match tmp_sym.ty {
+      SymType::Var => self.pcode.gen(PcodeType::LDA, level, addr),
+      // already an address, pass it on
+      SymType::Ref => self.pcode.gen(PcodeType::LOD, level, addr),
+      _ => {
+        self.has_error = true;
+        let mut builder = CompileErrorBuilder::from(id.1)
+          .with_code(ErrorCode::NotAReference)
+          .with_info(format!(
+            "`{}` is not a variable, but it's passed by reference",
+            name
+          ));
+        if matches!(tmp_sym.ty, SymType::Array) {
+          builder = builder.with_help(format!("pass an element with `{}[...]`", name));
+        }
+        self.errors.push(builder.build());
+      }
+    }
+  }
+
+  /// Check that `id` is an array, then evaluate `index` into the offset of
+  /// the element (`CHK`), ready for `LDX` / `STX`
+  fn element(&mut self, id: &IdExpr, index: &ExpExpr) -> Option<TableRow> {
+    // undefined
+    let name = id.0.to_owned();
+    let Some(tmp_sym) = self.sym_table.try_find_closest_sym(&name, &self.scope_list) else {
+      self.has_error = true;
+      let err = CompileErrorBuilder::from(id.1)
+        .with_code(ErrorCode::Undefined)
+        .with_info(format!("`{}` is undefined", name))
+        .build();
+      self.errors.push(err);
+      return None;
+    };
+    let tmp_sym = tmp_sym.to_owned();
+    if !matches!(tmp_sym.ty, SymType::Array) {
+      self.has_error = true;
+      let err = CompileErrorBuilder::from(id.1)
+        .with_code(ErrorCode::NotAnArray)
+        .with_info(format!("`{}` is not an array, but it's indexed", name))
+        .build();
+      self.errors.push(err);
+      return None;
+    }
+
+    self.exp(index);
+    self.pcode.set_location(id.1.location);
+    self.pcode.gen(
+      PcodeType::CHK,
+      tmp_sym.val as usize,
+      tmp_sym.val + tmp_sym.size as i64 - 1,
+    );
+    Some(tmp_sym)
+  }
+
+  /// Report `sym` if it's the control variable of an enclosing `for` loop,
+  /// which couldn't be changed inside the loop
+  fn check_loop_var(&mut self, id: &IdExpr, sym: &TableRow) -> bool {
+    let Some((_, location)) = self
+      .loop_vars
+      .iter()
+      .find(|(row, _)| row.name == sym.name && row.scope_list == sym.scope_list)
+    else {
+      return true;
+    };
+    self.has_error = true;
+    let err = CompileErrorBuilder::from(id.1)
+      .with_code(ErrorCode::AssignToLoopVar)
+      .with_info(format!(
+        "`{}` is the control variable of a `for` loop, it's read-only inside the loop",
+        id.0
+      ))
+      .with_label(*location, "the loop is here".to_string())
+      .build();
+    self.errors.push(err);
+    false
+  }
+
+  /// Hint for an array used without an index
+  fn index_help(builder: CompileErrorBuilder, sym: &TableRow) -> CompileErrorBuilder {
+    if matches!(sym.ty, SymType::Array) {
+      builder.with_help(format!("index it with `{}[...]`", sym.name))
+    } else {
+      builder
+    }
+  }
 }
 
 impl Translator {
   fn statement(&mut self, expr: &StatementExpr) {
     match expr {
-      StatementExpr::Id { id, exp } => {
+      StatementExpr::Id {
+        id,
+        index: Some(index),
+        exp,
+        ..
+      } => {
+        // STX (store into an element)
+        if let Some(sym) = self.element(id, index) {
+          self.exp(exp);
+          self.pcode.set_location(id.as_ref().1.location);
+          self
+            .pcode
+            .gen(PcodeType::STX, self.level - sym.level, sym.addr as i64);
+        }
+      }
+      StatementExpr::Id { id, exp, .. } => {
         let name = id.as_ref().0.to_owned();
 
         // undefined
         if !self.sym_table.is_pre_exists(&name, &self.scope_list) {
           self.has_error = true;
-          CompileErrorBuilder::from(id.as_ref().1)
+          let err = CompileErrorBuilder::from(id.as_ref().1)
+            .with_code(ErrorCode::Undefined)
             .with_info(format!("`{}` is undefined", name))
-            .build()
-            .show();
+            .build();
+          self.errors.push(err);
           return;
         }
 
@@ -245,12 +527,47 @@ impl Translator {
           .sym_table
           .find_closest_sym(&name, &self.scope_list)
           .to_owned();
-        if !matches!(tmp_sym.ty, SymType::Var) {
+        // the result of a function, inside its body
+        if matches!(tmp_sym.ty, SymType::Func) && self.is_inside(&tmp_sym) {
+          self.exp(exp);
+          self.pcode.set_location(id.as_ref().1.location);
+          self.pcode.gen(
+            PcodeType::STO,
+            self.level - (tmp_sym.level + 1),
+            (3 + tmp_sym.size) as i64,
+          );
+          return;
+        }
+        if !matches!(tmp_sym.ty, SymType::Var | SymType::Ref) {
           self.has_error = true;
-          CompileErrorBuilder::from(id.as_ref().1)
-            .with_info(format!("`{}` is not a variable", name))
-            .build()
-            .show();
+          let mut builder = CompileErrorBuilder::from(id.as_ref().1)
+            .with_code(ErrorCode::NotAVariable)
+            .with_info(format!("`{}` is not a variable", name));
+          if matches!(tmp_sym.ty, SymType::Func) {
+            builder = builder.with_help(format!(
+              "the result of `{}` could only be assigned inside `{}`",
+              name, name
+            ));
+          }
+          let builder = Self::index_help(builder, &tmp_sym);
+          self.errors.push(builder.build());
+          return;
+        }
+        if !self.check_loop_var(id, &tmp_sym) {
+          return;
+        }
+
+        // STI (store into the address held by a `var` arg)
+        if matches!(tmp_sym.ty, SymType::Ref) {
+          self.pcode.set_location(id.as_ref().1.location);
+          self.pcode.gen(
+            PcodeType::LOD,
+            self.level - tmp_sym.level,
+            tmp_sym.addr as i64,
+          );
+          self.exp(exp);
+          self.pcode.set_location(id.as_ref().1.location);
+          self.pcode.gen(PcodeType::STI, 0, 0);
           return;
         }
 
@@ -258,6 +575,7 @@ impl Translator {
         self.exp(exp);
 
         // STO (store)
+        self.pcode.set_location(id.as_ref().1.location);
         self.pcode.gen(
           PcodeType::STO,
           self.level - tmp_sym.level,
@@ -268,18 +586,17 @@ impl Translator {
         l_exp,
         then_statement,
         else_statement,
+        ..
       } => {
-        // condition
-        self.l_exp(l_exp);
+        // condition, jump to else if false
+        let pos_list = self.l_exp(l_exp, false);
 
         // then
-        let pos1 = self.pcode.get_pcode_ptr();
-        self.pcode.gen(PcodeType::JPC, 0, 0);
         self.statement(then_statement);
         let pos2 = self.pcode.get_pcode_ptr();
         self.pcode.gen(PcodeType::JMP, 0, 0);
         let fixed_a = self.pcode.get_pcode_ptr() as i64;
-        self.pcode.pcode_list[pos1].set_a(fixed_a);
+        self.fix_jumps(&pos_list, fixed_a);
         self.pcode.pcode_list[pos2].set_a(fixed_a);
 
         // else
@@ -289,83 +606,205 @@ impl Translator {
           self.pcode.pcode_list[pos2].set_a(fixed_a);
         }
       }
-      StatementExpr::While { l_exp, statement } => {
+      StatementExpr::While {
+        l_exp, statement, ..
+      } => {
         let pos1 = self.pcode.get_pcode_ptr();
 
-        // condition
-        self.l_exp(l_exp);
+        // infinite loop, no need to check the condition
+        if matches!(l_exp.as_ref(), LExpExpr::Const(true, _)) {
+          self.statement(statement);
+          self.pcode.gen(PcodeType::JMP, 0, pos1 as i64);
+          return;
+        }
+
+        // condition, jump out if false
+        let pos_list = self.l_exp(l_exp, false);
 
         // do(statement)
-        let pos2 = self.pcode.get_pcode_ptr();
-        self.pcode.gen(PcodeType::JPC, 0, 0); // jump out if not condition
         self.statement(statement);
         self.pcode.gen(PcodeType::JMP, 0, pos1 as i64); // jump back to while
         let fixed_a = self.pcode.get_pcode_ptr() as i64;
-        self.pcode.pcode_list[pos2].set_a(fixed_a);
+        self.fix_jumps(&pos_list, fixed_a);
+      }
+      StatementExpr::Repeat {
+        statements, l_exp, ..
+      } => {
+        let pos1 = self.pcode.get_pcode_ptr();
+        for statement in statements {
+          self.statement(statement);
+        }
+
+        // condition, jump back to repeat if false
+        let pos_list = self.l_exp(l_exp, false);
+        self.fix_jumps(&pos_list, pos1 as i64);
       }
-      StatementExpr::Call { id, args } => {
-        let n_args = args.len();
+      StatementExpr::For {
+        id,
+        from,
+        is_downto,
+        to,
+        statement,
+        ..
+      } => {
+        let name = id.as_ref().0.to_owned();
+
+        // undefined
+        let Some(tmp_sym) = self
+          .sym_table
+          .try_find_closest_sym(&name, &self.scope_list)
+          .cloned()
+        else {
+          self.has_error = true;
+          let err = CompileErrorBuilder::from(id.as_ref().1)
+            .with_code(ErrorCode::Undefined)
+            .with_info(format!("`{}` is undefined", name))
+            .build();
+          self.errors.push(err);
+          return;
+        };
+
+        // loop over non-var (a `var` arg included)
+        if !matches!(tmp_sym.ty, SymType::Var) {
+          self.has_error = true;
+          let mut builder = CompileErrorBuilder::from(id.as_ref().1)
+            .with_code(ErrorCode::NotAVariable)
+            .with_info(format!(
+              "`{}` is not a variable, it couldn't be the control variable of a `for` loop",
+              name
+            ));
+          if matches!(tmp_sym.ty, SymType::Ref) {
+            builder = builder.with_help(format!(
+              "`{}` is passed by reference, loop over a local variable instead",
+              name
+            ));
+          }
+          self.errors.push(builder.build());
+          return;
+        }
+        // nested loops over the same variable
+        if !self.check_loop_var(id, &tmp_sym) {
+          return;
+        }
+
+        // both `from` and the bound are evaluated before `id := from`, the
+        // bound is evaluated once, into a hidden slot of the frame
+        let (level, addr) = (self.level - tmp_sym.level, tmp_sym.addr as i64);
+        let bound = self.addr as i64;
+        self.addr += 1;
+        self.frame_size = self.frame_size.max(self.addr);
+        self.exp(from);
+        self.exp(to);
+        self.pcode.set_location(id.as_ref().1.location);
+        self.pcode.gen(PcodeType::STO, 0, bound);
+        self.pcode.gen(PcodeType::STO, level, addr);
+
+        // jump out if the range is empty
+        let (cmp, step) = if *is_downto { (11, 3) } else { (13, 2) };
+        self.pcode.gen(PcodeType::LOD, level, addr);
+        self.pcode.gen(PcodeType::LOD, 0, bound);
+        self.pcode.gen(PcodeType::OPR, 0, cmp);
+        let mut pos_list = self.gen_jump(PcodeType::JPC);
+
+        // do(statement)
+        let pos1 = self.pcode.get_pcode_ptr();
+        self.loop_vars.push((tmp_sym, id.as_ref().1.location));
+        self.statement(statement);
+        self.loop_vars.pop();
+
+        // jump out after the bound is reached (before the step, so that
+        // `id` never overflows), then step and jump back
+        self.pcode.set_location(id.as_ref().1.location);
+        self.pcode.gen(PcodeType::LOD, level, addr);
+        self.pcode.gen(PcodeType::LOD, 0, bound);
+        self.pcode.gen(PcodeType::OPR, 0, 9);
+        pos_list.extend(self.gen_jump(PcodeType::JPC));
+        self.pcode.gen(PcodeType::LOD, level, addr);
+        self.pcode.gen(PcodeType::LIT, 0, 1);
+        self.pcode.gen(PcodeType::OPR, 0, step);
+        self.pcode.gen(PcodeType::STO, level, addr);
+        self.pcode.gen(PcodeType::JMP, 0, pos1 as i64);
+        let fixed_a = self.pcode.get_pcode_ptr() as i64;
+        self.fix_jumps(&pos_list, fixed_a);
+        self.addr -= 1;
+      }
+      StatementExpr::Call { id, args, .. } => {
         let name = id.as_ref().0.to_owned();
 
         // undefined
         if !self.sym_table.is_pre_exists(&name, &self.scope_list) {
           self.has_error = true;
-          CompileErrorBuilder::from(id.as_ref().1)
+          let err = CompileErrorBuilder::from(id.as_ref().1)
+            .with_code(ErrorCode::Undefined)
             .with_info(format!("`{}` is undefined", name))
-            .build()
-            .show();
+            .build();
+          self.errors.push(err);
           return;
         }
 
-        let tmp_sym = self
+        let pos = self
           .sym_table
-          .find_closest_sym(&name, &self.scope_list)
-          .to_owned();
+          .try_find_closest_pos(&name, &self.scope_list)
+          .unwrap();
+        let tmp_sym = self.sym_table.table[pos].to_owned();
         // call non-proc
-        if !matches!(tmp_sym.ty, SymType::Proc) {
+        if !matches!(tmp_sym.ty, SymType::Proc | SymType::Func) {
           self.has_error = true;
-          CompileErrorBuilder::from(id.as_ref().1)
+          let err = CompileErrorBuilder::from(id.as_ref().1)
+            .with_code(ErrorCode::NotAProcedure)
             .with_info(format!("`{}` is not a procedure", name))
-            .build()
-            .show();
+            .build();
+          self.errors.push(err);
           return;
         }
         // unmatchable n_args
-        if tmp_sym.size != n_args {
-          self.has_error = true;
-          CompileErrorBuilder::from(id.as_ref().1)
-            .with_info(format!(
-              "`{}` expects {} args, but received {}",
-              name, tmp_sym.size, n_args
-            ))
-            .build()
-            .show();
+        if !self.args(id, pos, args) {
           return;
         }
 
-        for arg in args {
-          // eval-exp
-          self.exp(arg);
-        }
-
         // CAL
+        self.pcode.set_location(id.as_ref().1.location);
         self
           .pcode
           .gen(PcodeType::CAL, self.level - tmp_sym.level, tmp_sym.val);
+        if matches!(tmp_sym.ty, SymType::Func) {
+          // a function has dropped the args itself, discard the result
+          self.pcode.gen(PcodeType::INT, 0, -1);
+        } else if !args.is_empty() {
+          // drop the args (they've been copied into callee's data slice by `STA`)
+          self.pcode.gen(PcodeType::INT, 0, -(args.len() as i64));
+        }
       }
-      StatementExpr::Body { body } => self.body(body),
-      StatementExpr::Read { id_list } => {
-        for id in id_list {
+      StatementExpr::Body { body, .. } => self.body(body),
+      StatementExpr::Read {
+        id_list,
+        index_list,
+        ..
+      } => {
+        for (id, index) in id_list.iter().zip(index_list) {
+          // STX (read into an element)
+          if let Some(index) = index {
+            let Some(sym) = self.element(id, index) else {
+              continue;
+            };
+            self.pcode.gen(PcodeType::OPR, 0, 16);
+            self
+              .pcode
+              .gen(PcodeType::STX, self.level - sym.level, sym.addr as i64);
+            continue;
+          }
+
           let name = id.as_ref().0.to_owned();
 
           // undefined
           if !self.sym_table.is_pre_exists(&name, &self.scope_list) {
             self.has_error = true;
-            CompileErrorBuilder::from(id.as_ref().1)
+            let err = CompileErrorBuilder::from(id.as_ref().1)
+              .with_code(ErrorCode::Undefined)
               .with_info(format!("`{}` is undefined", name))
-              .build()
-              .show();
-            return;
+              .build();
+            self.errors.push(err);
+            continue;
           }
 
           let tmp_sym = self
@@ -373,15 +812,31 @@ impl Translator {
             .find_closest_sym(&name, &self.scope_list)
             .to_owned();
           // read to non-var
-          if !matches!(tmp_sym.ty, SymType::Var) {
+          if !matches!(tmp_sym.ty, SymType::Var | SymType::Ref) {
             self.has_error = true;
-            CompileErrorBuilder::from(id.as_ref().1)
-              .with_info(format!("`{}` is not a variable", name))
-              .build()
-              .show();
-            return;
+            let builder = CompileErrorBuilder::from(id.as_ref().1)
+              .with_code(ErrorCode::NotAVariable)
+              .with_info(format!("`{}` is not a variable", name));
+            self
+              .errors
+              .push(Self::index_help(builder, &tmp_sym).build());
+            continue;
+          }
+          if !self.check_loop_var(id, &tmp_sym) {
+            continue;
           }
 
+          self.pcode.set_location(id.as_ref().1.location);
+          if matches!(tmp_sym.ty, SymType::Ref) {
+            self.pcode.gen(
+              PcodeType::LOD,
+              self.level - tmp_sym.level,
+              tmp_sym.addr as i64,
+            );
+            self.pcode.gen(PcodeType::OPR, 0, 16);
+            self.pcode.gen(PcodeType::STI, 0, 0);
+            continue;
+          }
           self.pcode.gen(PcodeType::OPR, 0, 16);
           // must gen SPO, because `read` will change sp
           self.pcode.gen(
@@ -391,7 +846,7 @@ impl Translator {
           );
         }
       }
-      StatementExpr::Write { exps } => {
+      StatementExpr::Write { exps, .. } => {
         for exp in exps {
           self.exp(exp);
           self.pcode.gen(PcodeType::OPR, 0, 14);
@@ -412,16 +867,23 @@ impl Translator {
     for exp in expr {
       let id = exp.id.as_ref().0.to_owned();
       let val = exp.integer.as_ref().0;
-      if self.sym_table.is_now_exists(&id, &self.scope_list) {
+      if let Some(prev) = self.sym_table.try_find_now(&id, &self.scope_list) {
         self.has_error = true;
-        CompileErrorBuilder::from(exp.id.as_ref().1)
+        let err = CompileErrorBuilder::from(exp.id.as_ref().1)
+          .with_code(ErrorCode::Redefined)
           .with_info(format!("`{}` is defined before", id))
-          .build()
-          .show();
+          .with_label(prev.location, "first defined here".to_string())
+          .build();
+        self.errors.push(err);
       } else {
-        self
-          .sym_table
-          .load_const(&id, self.level, val, self.addr, self.scope_list.to_owned());
+        self.sym_table.load_const(
+          &id,
+          self.level,
+          val,
+          self.addr,
+          self.scope_list.to_owned(),
+          exp.id.as_ref().1.location,
+        );
       }
     }
   }
@@ -429,19 +891,39 @@ impl Translator {
   fn var_decl(&mut self, expr: &VarDeclExpr) {
     let id_list = &expr.id_list;
     // for each id in id_list, you should consider the updating of addr
-    for id_exp in id_list {
+    for (id_exp, bound) in id_list.iter().zip(&expr.bounds) {
       let id = id_exp.as_ref().0.to_owned();
-      if self.sym_table.is_now_exists(&id, &self.scope_list) {
+      if let Some(prev) = self.sym_table.try_find_now(&id, &self.scope_list) {
         self.has_error = true;
-        CompileErrorBuilder::from(id_exp.as_ref().1)
+        let err = CompileErrorBuilder::from(id_exp.as_ref().1)
+          .with_code(ErrorCode::Redefined)
           .with_info(format!("`{}` is defined before", id))
-          .build()
-          .show();
+          .with_label(prev.location, "first defined here".to_string())
+          .build();
+        self.errors.push(err);
         continue;
+      } else if let Some(bound) = bound {
+        let (lo, len) = self.bound(&id, bound);
+        self.sym_table.load_array(
+          &id,
+          self.level,
+          self.addr,
+          self.scope_list.to_owned(),
+          id_exp.as_ref().1.location,
+        );
+        let pos = self.sym_table.table_ptr - 1;
+        self.sym_table.table[pos].set_val(lo);
+        self.sym_table.table[pos].set_size(len);
+        // update addr, one for each element
+        self.addr += self.addr_increment * len;
       } else {
-        self
-          .sym_table
-          .load_var(&id, self.level, self.addr, self.scope_list.to_owned());
+        self.sym_table.load_var(
+          &id,
+          self.level,
+          self.addr,
+          self.scope_list.to_owned(),
+          id_exp.as_ref().1.location,
+        );
         // update addr
         self.addr += self.addr_increment;
       }
@@ -450,25 +932,116 @@ impl Translator {
 }
 
 impl Translator {
-  fn l_exp(&mut self, expr: &LExpExpr) {
+  /// `(lo, len)` of the array `id`, `len` is `0` if there's no element
+  fn bound(&mut self, id: &str, expr: &BoundExpr) -> (i64, usize) {
+    let (lo, len) = match expr.range() {
+      Some((lo, hi)) => (lo, hi.checked_sub(lo).and_then(|len| len.checked_add(1))),
+      None => (0, Some(0)),
+    };
+    match len.map(usize::try_from) {
+      Some(Ok(len)) if len > 0 => (lo, len),
+      Some(_) => {
+        self.has_error = true;
+        let err = CompileErrorBuilder::from(expr.span())
+          .with_code(ErrorCode::InvalidBounds)
+          .with_info(format!(
+            "`{}` has no elements, its upper bound is less than its lower bound",
+            id
+          ))
+          .build();
+        self.errors.push(err);
+        (lo, 0)
+      }
+      None => {
+        self.has_error = true;
+        let err = CompileErrorBuilder::from(expr.span())
+          .with_code(ErrorCode::InvalidBounds)
+          .with_info(format!("`{}` has too many elements", id))
+          .build();
+        self.errors.push(err);
+        (lo, 0)
+      }
+    }
+  }
+}
+
+impl Translator {
+  /// Jump if `expr` is `jump_if`, fall through otherwise. Targets of the
+  /// jumps are left to the caller, their positions are returned.
+  ///
+  /// No boolean value is left on the stack: `and` / `or` short-circuit by
+  /// chains of `JPC` / `JMP`, and `not` by swapping `jump_if`.
+  fn l_exp(&mut self, expr: &LExpExpr, jump_if: bool) -> Vec<usize> {
     match expr {
-      LExpExpr::Exp { l_exp, lop, r_exp } => {
+      LExpExpr::Exp {
+        l_exp, lop, r_exp, ..
+      } => {
         self.exp(l_exp);
         let lop = self.lop(lop);
         self.exp(r_exp);
-        match lop {
-          LopExpr::Eq(_) => self.pcode.gen(PcodeType::OPR, 0, 8),
-          LopExpr::Ne(_) => self.pcode.gen(PcodeType::OPR, 0, 9),
-          LopExpr::Lt(_) => self.pcode.gen(PcodeType::OPR, 0, 10),
-          LopExpr::Ge(_) => self.pcode.gen(PcodeType::OPR, 0, 11),
-          LopExpr::Gt(_) => self.pcode.gen(PcodeType::OPR, 0, 12),
-          LopExpr::Le(_) => self.pcode.gen(PcodeType::OPR, 0, 13),
-        }
+        self.pcode.set_location(lop.location());
+        // `JPC` jumps if false, so test the opposite to jump if true
+        let opr = match (lop, jump_if) {
+          (LopExpr::Eq(_), false) | (LopExpr::Ne(_), true) => 8,
+          (LopExpr::Ne(_), false) | (LopExpr::Eq(_), true) => 9,
+          (LopExpr::Lt(_), false) | (LopExpr::Ge(_), true) => 10,
+          (LopExpr::Ge(_), false) | (LopExpr::Lt(_), true) => 11,
+          (LopExpr::Gt(_), false) | (LopExpr::Le(_), true) => 12,
+          (LopExpr::Le(_), false) | (LopExpr::Gt(_), true) => 13,
+        };
+        self.pcode.gen(PcodeType::OPR, 0, opr);
+        self.gen_jump(PcodeType::JPC)
       }
-      LExpExpr::Odd { exp } => {
+      LExpExpr::Odd { exp, .. } => {
         self.exp(exp);
         self.pcode.gen(PcodeType::OPR, 0, 6);
+        if jump_if {
+          // odd = 0
+          self.pcode.gen(PcodeType::LIT, 0, 0);
+          self.pcode.gen(PcodeType::OPR, 0, 8);
+        }
+        self.gen_jump(PcodeType::JPC)
+      }
+      LExpExpr::Not { l_exp, .. } => self.l_exp(l_exp, !jump_if),
+      // jump if `l_exp` is false, or both are true
+      LExpExpr::And { l_exp, r_exp, .. } if jump_if => {
+        let pos_list = self.l_exp(l_exp, false);
+        let jumps = self.l_exp(r_exp, true);
+        self.fix_jumps(&pos_list, self.pcode.get_pcode_ptr() as i64);
+        jumps
+      }
+      LExpExpr::And { l_exp, r_exp, .. } => {
+        let mut jumps = self.l_exp(l_exp, false);
+        jumps.extend(self.l_exp(r_exp, false));
+        jumps
       }
+      LExpExpr::Or { l_exp, r_exp, .. } if jump_if => {
+        let mut jumps = self.l_exp(l_exp, true);
+        jumps.extend(self.l_exp(r_exp, true));
+        jumps
+      }
+      // fall through if `l_exp` is true, jump if both are false
+      LExpExpr::Or { l_exp, r_exp, .. } => {
+        let pos_list = self.l_exp(l_exp, true);
+        let jumps = self.l_exp(r_exp, false);
+        self.fix_jumps(&pos_list, self.pcode.get_pcode_ptr() as i64);
+        jumps
+      }
+      LExpExpr::Const(val, _) if *val == jump_if => self.gen_jump(PcodeType::JMP),
+      LExpExpr::Const(..) => vec![],
+    }
+  }
+
+  /// A `JMP` / `JPC` to be fixed later
+  fn gen_jump(&mut self, f: PcodeType) -> Vec<usize> {
+    let pos = self.pcode.get_pcode_ptr();
+    self.pcode.gen(f, 0, 0);
+    vec![pos]
+  }
+
+  fn fix_jumps(&mut self, pos_list: &[usize], target: i64) {
+    for &pos in pos_list {
+      self.pcode.pcode_list[pos].set_a(target);
     }
   }
 
@@ -480,6 +1053,7 @@ impl Translator {
     }
     for (aop, term) in &expr.aop_terms {
       self.term(term);
+      self.pcode.set_location(aop.location());
       match aop.as_ref() {
         // add
         AopExpr::Add(_) => self.pcode.gen(PcodeType::OPR, 0, 2),
@@ -493,6 +1067,7 @@ impl Translator {
     self.factor(&expr.factor);
     for (mop, factor) in &expr.mop_factors {
       self.factor(factor);
+      self.pcode.set_location(mop.location());
       match mop.as_ref() {
         // mul
         MopExpr::Mul(_) => self.pcode.gen(PcodeType::OPR, 0, 4),
@@ -506,23 +1081,35 @@ impl Translator {
     match expr {
       FactorExpr::Integer(expr) => {
         let val = expr.0;
+        self.pcode.set_location(expr.1.location);
         self.pcode.gen(PcodeType::LIT, 0, val);
       }
-      FactorExpr::Exp(expr) => self.exp(expr),
+      FactorExpr::Exp(expr, _) => self.exp(expr),
+      FactorExpr::Call { id, args, .. } => self.call(id, args),
+      // LDX (load an element)
+      FactorExpr::Index { id, index, .. } => {
+        if let Some(sym) = self.element(id, index) {
+          self
+            .pcode
+            .gen(PcodeType::LDX, self.level - sym.level, sym.addr as i64);
+        }
+      }
       FactorExpr::Id(expr) => {
         let id = expr.0.to_owned();
+        self.pcode.set_location(expr.1.location);
         if self.sym_table.is_pre_exists(&id, &self.scope_list) {
           let tmp_sym = self.sym_table.find_closest_sym(&id, &self.scope_list);
           match tmp_sym.ty {
             SymType::Nil => {
               self.has_error = true;
-              CompileErrorBuilder::from(expr.1)
+              let err = CompileErrorBuilder::from(expr.1)
+                .with_code(ErrorCode::NotAnRValue)
                 .with_info(format!(
                   "`{}` has an non-r-value type `nil` (only `var` or `const` appears after `:=`)",
                   id
                 ))
-                .build()
-                .show();
+                .build();
+              self.errors.push(err);
             }
             SymType::Const => self.pcode.gen(PcodeType::LIT, 0, tmp_sym.val),
             SymType::Var => {
@@ -533,24 +1120,99 @@ impl Translator {
                 tmp_sym.addr as i64,
               )
             }
+            // LDI (load from the address held by a `var` arg)
+            SymType::Ref => {
+              self.pcode.gen(
+                PcodeType::LOD,
+                self.level - tmp_sym.level,
+                tmp_sym.addr as i64,
+              );
+              self.pcode.gen(PcodeType::LDI, 0, 0);
+            }
             SymType::Proc => {
               self.has_error = true;
-              CompileErrorBuilder::from(expr.1)
+              let err = CompileErrorBuilder::from(expr.1)
+                .with_code(ErrorCode::NotAnRValue)
                 .with_info(format!(
                   "`{}` has an non-r-value type `procedure` (only `var` or `const` appears after `:=`)",
                    id
                 ))
-                .build()
-                .show();
+                .build();
+              self.errors.push(err);
+            }
+            SymType::Func => {
+              self.has_error = true;
+              let err = CompileErrorBuilder::from(expr.1)
+                .with_code(ErrorCode::NotAnRValue)
+                .with_info(format!("`{}` is a function, but it's not called", id))
+                .with_help(format!("call it with `{}(...)`", id))
+                .build();
+              self.errors.push(err);
+            }
+            SymType::Array => {
+              self.has_error = true;
+              let err = CompileErrorBuilder::from(expr.1)
+                .with_code(ErrorCode::NotAnRValue)
+                .with_info(format!("`{}` is an array, but it's not indexed", id))
+                .with_help(format!("index it with `{}[...]`", id))
+                .build();
+              self.errors.push(err);
             }
           }
         } else {
           self.has_error = true;
-          CompileErrorBuilder::from(expr.1)
+          let err = CompileErrorBuilder::from(expr.1)
+            .with_code(ErrorCode::Undefined)
             .with_info(format!("`{}` is undefined", id))
-            .build()
-            .show();
+            .build();
+          self.errors.push(err);
+        }
+      }
+    }
+  }
+
+  fn call(&mut self, id: &IdExpr, args: &[Box<ExpExpr>]) {
+    let name = id.0.to_owned();
+
+    // undefined
+    let Some(pos) = self.sym_table.try_find_closest_pos(&name, &self.scope_list) else {
+      self.has_error = true;
+      let err = CompileErrorBuilder::from(id.1)
+        .with_code(ErrorCode::Undefined)
+        .with_info(format!("`{}` is undefined", name))
+        .build();
+      self.errors.push(err);
+      return;
+    };
+
+    let tmp_sym = self.sym_table.table[pos].to_owned();
+    match tmp_sym.ty {
+      SymType::Func => {
+        if !self.args(id, pos, args) {
+          return;
         }
+        // CAL, the result is left on the stack (args dropped by the callee)
+        self.pcode.set_location(id.1.location);
+        self
+          .pcode
+          .gen(PcodeType::CAL, self.level - tmp_sym.level, tmp_sym.val);
+      }
+      SymType::Proc => {
+        self.has_error = true;
+        let err = CompileErrorBuilder::from(id.1)
+          .with_code(ErrorCode::NotAnRValue)
+          .with_info(format!("`{}` is a procedure without a return value", name))
+          .with_help(format!("call it with `call {}(...)` as a statement", name))
+          .build();
+        self.errors.push(err);
+      }
+      SymType::Nil | SymType::Const | SymType::Var | SymType::Ref | SymType::Array => {
+        self.has_error = true;
+        let err = CompileErrorBuilder::from(id.1)
+          .with_code(ErrorCode::NotAFunction)
+          .with_info(format!("`{}` is not a function", name))
+          .build();
+        self.errors.push(err);
       }
     }
   }
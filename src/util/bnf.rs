@@ -1,6 +1,9 @@
 use crate::lexer::token_def::Token;
 use once_cell::sync::Lazy;
-use std::collections::HashMap;
+use std::{
+  collections::{BTreeSet, HashMap, HashSet},
+  fmt::Display,
+};
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
 pub enum VN {
@@ -9,20 +12,94 @@ pub enum VN {
   ConstDecl,
   Const,
   VarDecl,
+  VarDef,
+  Bound,
   Proc,
+  Param,
   Body,
   Statement,
+  Var,
   LExp,
+  LTerm,
+  LFactor,
   Exp,
   Term,
   Factor,
+  Args,
+  Index,
   Lop,
   Aop,
   Mop,
+  Dir,
   Id,
   Integer,
 }
 
+impl VN {
+  pub const ALL: [VN; 26] = [
+    VN::Program,
+    VN::Block,
+    VN::ConstDecl,
+    VN::Const,
+    VN::VarDecl,
+    VN::VarDef,
+    VN::Bound,
+    VN::Proc,
+    VN::Param,
+    VN::Body,
+    VN::Statement,
+    VN::Var,
+    VN::LExp,
+    VN::LTerm,
+    VN::LFactor,
+    VN::Exp,
+    VN::Term,
+    VN::Factor,
+    VN::Args,
+    VN::Index,
+    VN::Lop,
+    VN::Aop,
+    VN::Mop,
+    VN::Dir,
+    VN::Id,
+    VN::Integer,
+  ];
+}
+
+impl Display for VN {
+  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    let name = match self {
+      VN::Program => "prog",
+      VN::Block => "block",
+      VN::ConstDecl => "const-decl",
+      VN::Const => "const",
+      VN::VarDecl => "var-decl",
+      VN::VarDef => "var-def",
+      VN::Bound => "bound",
+      VN::Proc => "proc",
+      VN::Param => "param",
+      VN::Body => "body",
+      VN::Statement => "statement",
+      VN::Var => "var",
+      VN::LExp => "l-exp",
+      VN::LTerm => "l-term",
+      VN::LFactor => "l-factor",
+      VN::Exp => "exp",
+      VN::Term => "term",
+      VN::Factor => "factor",
+      VN::Args => "args",
+      VN::Index => "index",
+      VN::Lop => "lop",
+      VN::Aop => "aop",
+      VN::Mop => "mop",
+      VN::Dir => "dir",
+      VN::Id => "id",
+      VN::Integer => "integer",
+    };
+    write!(f, "<{}>", name)
+  }
+}
+
 #[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
 pub enum Elem {
   VN(VN),
@@ -41,6 +118,8 @@ impl From<Token> for Elem {
   }
 }
 
+/// `elem_list` itself, `[elem_list]` (optional) or `{elem_list}` (repeated,
+/// zero or more times)
 #[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash, Default)]
 pub struct ExElem {
   pub elem_list: Vec<Elem>,
@@ -95,73 +174,522 @@ impl ExElemBuilder {
   }
 }
 
-#[allow(unused)]
-pub static BNF: Lazy<HashMap<VN, Vec<Vec<ExElem>>>> = Lazy::new(|| {
-  unimplemented!();
+/// Alternatives of each `VN`, an alternative is a sequence of `ExElem`
+pub type Grammar = HashMap<VN, Vec<Vec<ExElem>>>;
 
-  let prog_form = vec![vec![
-    ExElemBuilder::new().with_elem(Token::Program.into()).into(),
-    ExElemBuilder::new().with_elem(VN::Id.into()).into(),
-    ExElemBuilder::new()
-      .with_elem(Token::Semicolon.into())
-      .into(),
-    ExElemBuilder::new().with_elem(VN::Block.into()).into(),
-  ]];
-  let block_form = vec![vec![
+/// The [BNF](../../../README.md#bnf) of PL/0, `<id>` and `<integer>` are
+/// produced by the lexer as a whole.
+///
+/// `ExElem` couldn't be nested, so `([<id> {, <id>}])` is written as
+/// `([<id>] {, <id>})`, which has the same FIRST / FOLLOW sets. For the same
+/// reason `<id> [<args> | <index>]` of `<factor>` is written as
+/// `<id> [<args>] [<index>]`.
+pub static BNF: Lazy<Grammar> = Lazy::new(|| {
+  let one = |elem: Elem| -> ExElem { ExElemBuilder::new().with_elem(elem).into() };
+  let opt = |elem_list: Vec<Elem>| -> ExElem {
     ExElemBuilder::new()
-      .with_elem(VN::ConstDecl.into())
+      .with_elem_list(elem_list)
       .optional()
-      .into(),
+      .into()
+  };
+  let rep = |elem_list: Vec<Elem>| -> ExElem {
     ExElemBuilder::new()
-      .with_elem(VN::VarDecl.into())
-      .optional()
-      .into(),
-    ExElemBuilder::new()
-      .with_elem(VN::Proc.into())
-      .optional()
-      .into(),
-    ExElemBuilder::new().with_elem(VN::Body.into()).into(),
+      .with_elem_list(elem_list)
+      .repeated()
+      .into()
+  };
+  let prog_form = vec![vec![
+    one(Token::Program.into()),
+    one(VN::Id.into()),
+    one(Token::Semicolon.into()),
+    one(VN::Block.into()),
+  ]];
+  let block_form = vec![vec![
+    opt(vec![VN::ConstDecl.into()]),
+    opt(vec![VN::VarDecl.into()]),
+    opt(vec![VN::Proc.into()]),
+    one(VN::Body.into()),
   ]];
   let const_decl_form = vec![vec![
-    ExElemBuilder::new().with_elem(Token::Const.into()).into(),
-    ExElemBuilder::new().with_elem(VN::Const.into()).into(),
-    ExElemBuilder::new()
-      .with_elem_list(vec![Token::Comma.into(), VN::Const.into()])
-      .into(),
-    ExElemBuilder::new()
-      .with_elem(Token::Semicolon.into())
-      .into(),
+    one(Token::Const.into()),
+    one(VN::Const.into()),
+    rep(vec![Token::Comma.into(), VN::Const.into()]),
+    one(Token::Semicolon.into()),
   ]];
   let const_form = vec![vec![
-    ExElemBuilder::new().with_elem(VN::Id.into()).into(),
-    ExElemBuilder::new().with_elem(Token::EqSign.into()).into(),
-    ExElemBuilder::new().with_elem(VN::Integer.into()).into(),
+    one(VN::Id.into()),
+    one(Token::EqSign.into()),
+    one(VN::Integer.into()),
   ]];
   let var_decl_form = vec![vec![
-    ExElemBuilder::new().with_elem(Token::Var.into()).into(),
-    ExElemBuilder::new().with_elem(VN::Id.into()).into(),
-    ExElemBuilder::new()
-      .with_elem_list(vec![Token::Comma.into(), VN::Id.into()])
-      .into(),
-    ExElemBuilder::new()
-      .with_elem(Token::Semicolon.into())
-      .into(),
+    one(Token::Var.into()),
+    one(VN::VarDef.into()),
+    rep(vec![Token::Comma.into(), VN::VarDef.into()]),
+    one(Token::Semicolon.into()),
   ]];
-  let proc_form = vec![vec![
-    ExElemBuilder::new()
-      .with_elem(Token::Procedure.into())
-      .into(),
-    ExElemBuilder::new().with_elem(VN::Id.into()).into(),
-    ExElemBuilder::new().with_elem(Token::ParL.into()).into(),
-    ExElemBuilder::new().with_elem(Token::ParR.into()).into(),
+  let var_def_form = vec![vec![one(VN::Id.into()), opt(vec![VN::Bound.into()])]];
+  let bound_form = vec![vec![
+    one(Token::BracketL.into()),
+    one(VN::Integer.into()),
+    opt(vec![Token::Range.into(), VN::Integer.into()]),
+    one(Token::BracketR.into()),
+  ]];
+  let proc_form = [Token::Procedure, Token::Function]
+    .into_iter()
+    .map(|keyword| {
+      vec![
+        one(keyword.into()),
+        one(VN::Id.into()),
+        one(Token::ParL.into()),
+        opt(vec![VN::Param.into()]),
+        rep(vec![Token::Comma.into(), VN::Param.into()]),
+        one(Token::ParR.into()),
+        one(Token::Semicolon.into()),
+        one(VN::Block.into()),
+        rep(vec![Token::Semicolon.into(), VN::Proc.into()]),
+      ]
+    })
+    .collect();
+  let param_form = vec![vec![opt(vec![Token::Var.into()]), one(VN::Id.into())]];
+  let body_form = vec![vec![
+    one(Token::Begin.into()),
+    one(VN::Statement.into()),
+    rep(vec![Token::Semicolon.into(), VN::Statement.into()]),
+    one(Token::End.into()),
+  ]];
+  let statement_form = vec![
+    vec![
+      one(VN::Var.into()),
+      one(Token::EqSign.into()),
+      one(VN::Exp.into()),
+    ],
+    vec![
+      one(Token::If.into()),
+      one(VN::LExp.into()),
+      one(Token::Then.into()),
+      one(VN::Statement.into()),
+      opt(vec![Token::Else.into(), VN::Statement.into()]),
+    ],
+    vec![
+      one(Token::While.into()),
+      one(VN::LExp.into()),
+      one(Token::Do.into()),
+      one(VN::Statement.into()),
+    ],
+    vec![
+      one(Token::Repeat.into()),
+      one(VN::Statement.into()),
+      rep(vec![Token::Semicolon.into(), VN::Statement.into()]),
+      one(Token::Until.into()),
+      one(VN::LExp.into()),
+    ],
+    vec![
+      one(Token::For.into()),
+      one(VN::Id.into()),
+      one(Token::EqSign.into()),
+      one(VN::Exp.into()),
+      one(VN::Dir.into()),
+      one(VN::Exp.into()),
+      one(Token::Do.into()),
+      one(VN::Statement.into()),
+    ],
+    vec![
+      one(Token::Call.into()),
+      one(VN::Id.into()),
+      one(VN::Args.into()),
+    ],
+    vec![one(VN::Body.into())],
+    vec![
+      one(Token::Read.into()),
+      one(Token::ParL.into()),
+      one(VN::Var.into()),
+      rep(vec![Token::Comma.into(), VN::Var.into()]),
+      one(Token::ParR.into()),
+    ],
+    vec![
+      one(Token::Write.into()),
+      one(Token::ParL.into()),
+      one(VN::Exp.into()),
+      rep(vec![Token::Comma.into(), VN::Exp.into()]),
+      one(Token::ParR.into()),
+    ],
+  ];
+  let var_form = vec![vec![one(VN::Id.into()), opt(vec![VN::Index.into()])]];
+  let l_exp_form = vec![vec![
+    one(VN::LTerm.into()),
+    rep(vec![Token::Or.into(), VN::LTerm.into()]),
+  ]];
+  let l_term_form = vec![vec![
+    one(VN::LFactor.into()),
+    rep(vec![Token::And.into(), VN::LFactor.into()]),
+  ]];
+  let l_factor_form = vec![
+    vec![one(Token::Not.into()), one(VN::LFactor.into())],
+    vec![
+      one(Token::ParL.into()),
+      one(VN::LExp.into()),
+      one(Token::ParR.into()),
+    ],
+    vec![
+      one(VN::Exp.into()),
+      one(VN::Lop.into()),
+      one(VN::Exp.into()),
+    ],
+    vec![one(Token::Odd.into()), one(VN::Exp.into())],
+  ];
+  let exp_form = vec![vec![
+    opt(vec![VN::Aop.into()]),
+    one(VN::Term.into()),
+    rep(vec![VN::Aop.into(), VN::Term.into()]),
+  ]];
+  let term_form = vec![vec![
+    one(VN::Factor.into()),
+    rep(vec![VN::Mop.into(), VN::Factor.into()]),
+  ]];
+  let factor_form = vec![
+    vec![
+      one(VN::Id.into()),
+      opt(vec![VN::Args.into()]),
+      opt(vec![VN::Index.into()]),
+    ],
+    vec![one(VN::Integer.into())],
+    vec![
+      one(Token::ParL.into()),
+      one(VN::Exp.into()),
+      one(Token::ParR.into()),
+    ],
+  ];
+  let args_form = vec![vec![
+    one(Token::ParL.into()),
+    opt(vec![VN::Exp.into()]),
+    rep(vec![Token::Comma.into(), VN::Exp.into()]),
+    one(Token::ParR.into()),
   ]];
+  let index_form = vec![vec![
+    one(Token::BracketL.into()),
+    one(VN::Exp.into()),
+    one(Token::BracketR.into()),
+  ]];
+  let tokens = |tokens: Vec<Token>| tokens.into_iter().map(|t| vec![one(t.into())]).collect();
+  let lop_form = tokens(vec![
+    Token::Eq,
+    Token::Ne,
+    Token::Lt,
+    Token::Le,
+    Token::Gt,
+    Token::Ge,
+  ]);
+  let aop_form = tokens(vec![Token::Add, Token::Sub]);
+  let mop_form = tokens(vec![Token::Mul, Token::Div]);
+  let dir_form = tokens(vec![Token::To, Token::Downto]);
+  let id_form = vec![vec![one(Token::Identifier(Default::default()).into())]];
+  let integer_form = vec![vec![one(Token::Integer(Default::default()).into())]];
   let bnf = vec![
     (VN::Program, prog_form),
     (VN::Block, block_form),
     (VN::ConstDecl, const_decl_form),
     (VN::Const, const_form),
     (VN::VarDecl, var_decl_form),
+    (VN::VarDef, var_def_form),
+    (VN::Bound, bound_form),
     (VN::Proc, proc_form),
+    (VN::Param, param_form),
+    (VN::Body, body_form),
+    (VN::Statement, statement_form),
+    (VN::Var, var_form),
+    (VN::LExp, l_exp_form),
+    (VN::LTerm, l_term_form),
+    (VN::LFactor, l_factor_form),
+    (VN::Exp, exp_form),
+    (VN::Term, term_form),
+    (VN::Factor, factor_form),
+    (VN::Args, args_form),
+    (VN::Index, index_form),
+    (VN::Lop, lop_form),
+    (VN::Aop, aop_form),
+    (VN::Mop, mop_form),
+    (VN::Dir, dir_form),
+    (VN::Id, id_form),
+    (VN::Integer, integer_form),
   ];
   bnf.into_iter().collect()
 });
+
+/// FIRST / FOLLOW sets of `BNF`, what the parser synchronizes with
+pub static FIRST_FOLLOW: Lazy<FirstFollow> = Lazy::new(|| FirstFollow::new(&BNF));
+
+type Set = HashSet<Token>;
+
+/// Nullable, FIRST and FOLLOW sets of a grammar, computed by fixed-point
+/// iteration.
+///
+/// There's no token for the end of the file, so it's not in any FOLLOW set
+/// (e.g. FOLLOW(`<prog>`) is empty).
+#[derive(Debug, Clone, Default)]
+pub struct FirstFollow {
+  pub nullable: HashSet<VN>,
+  pub first: HashMap<VN, Set>,
+  pub follow: HashMap<VN, Set>,
+  /// Tokens that could appear right after each kind of token
+  pub token_follow: HashMap<Token, Set>,
+}
+
+impl FirstFollow {
+  pub fn new(grammar: &Grammar) -> Self {
+    let mut sets = Self::default();
+    for &vn in grammar.keys() {
+      sets.first.insert(vn, Set::default());
+      sets.follow.insert(vn, Set::default());
+    }
+
+    // nullable & FIRST
+    let mut changed = true;
+    while changed {
+      changed = false;
+      for (&vn, alternatives) in grammar {
+        for alternative in alternatives {
+          let (first, nullable) = sets.first_of(alternative);
+          if nullable && sets.nullable.insert(vn) {
+            changed = true;
+          }
+          let set = sets.first.get_mut(&vn).unwrap();
+          let len = set.len();
+          set.extend(first);
+          changed |= set.len() != len;
+        }
+      }
+    }
+
+    // FOLLOW
+    let mut changed = true;
+    while changed {
+      changed = false;
+      for (&vn, alternatives) in grammar {
+        for alternative in alternatives {
+          for (i, ex_elem) in alternative.iter().enumerate() {
+            for (j, elem) in ex_elem.elem_list.iter().enumerate() {
+              let Elem::VN(target) = elem else {
+                continue;
+              };
+              let follow = sets.follow_at(vn, alternative, i, j);
+              let set = sets.follow.get_mut(target).unwrap();
+              let len = set.len();
+              set.extend(follow);
+              changed |= set.len() != len;
+            }
+          }
+        }
+      }
+    }
+
+    // FOLLOW of tokens
+    for (&vn, alternatives) in grammar {
+      for alternative in alternatives {
+        for (i, ex_elem) in alternative.iter().enumerate() {
+          for (j, elem) in ex_elem.elem_list.iter().enumerate() {
+            if let Elem::VT(token) = elem {
+              let follow = sets.follow_at(vn, alternative, i, j);
+              sets
+                .token_follow
+                .entry(token.clone())
+                .or_default()
+                .extend(follow);
+            }
+          }
+        }
+      }
+    }
+
+    sets
+  }
+
+  /// FIRST of the sequence, and whether it's nullable
+  pub fn first_of(&self, seq: &[ExElem]) -> (Set, bool) {
+    let mut first = Set::default();
+    for ex_elem in seq {
+      let (elem_first, nullable) = self.first_of_elems(&ex_elem.elem_list);
+      first.extend(elem_first);
+      if !(nullable || ex_elem.is_optional || ex_elem.is_repeated) {
+        return (first, false);
+      }
+    }
+    (first, true)
+  }
+
+  fn first_of_elems(&self, elem_list: &[Elem]) -> (Set, bool) {
+    let mut first = Set::default();
+    for elem in elem_list {
+      match elem {
+        Elem::VT(token) => {
+          first.insert(token.clone());
+          return (first, false);
+        }
+        Elem::VN(vn) => {
+          first.extend(self.first[vn].iter().cloned());
+          if !self.nullable.contains(vn) {
+            return (first, false);
+          }
+        }
+      }
+    }
+    (first, true)
+  }
+
+  /// What could follow `alternative[i].elem_list[j]` of `vn`
+  fn follow_at(&self, vn: VN, alternative: &[ExElem], i: usize, j: usize) -> Set {
+    let ex_elem = &alternative[i];
+    let (mut follow, nullable) = self.first_of_elems(&ex_elem.elem_list[j + 1..]);
+    if !nullable {
+      return follow;
+    }
+    if ex_elem.is_repeated {
+      follow.extend(self.first_of_elems(&ex_elem.elem_list).0);
+    }
+    let (rest, nullable) = self.first_of(&alternative[i + 1..]);
+    follow.extend(rest);
+    if nullable {
+      follow.extend(self.follow[&vn].iter().cloned());
+    }
+    follow
+  }
+
+  /// Everything that prevents `grammar` from being LL(1), empty if it's
+  /// LL(1):
+  ///
+  /// 1. no left recursion
+  /// 2. the alternatives of a `VN` start with different tokens
+  /// 3. an optional / repeated part starts with tokens which couldn't follow
+  ///    it
+  pub fn conflicts(&self, grammar: &Grammar) -> Vec<Conflict> {
+    let mut conflicts = vec![];
+    let mut vns = grammar.keys().copied().collect::<Vec<_>>();
+    vns.sort();
+
+    for &vn in &vns {
+      if self.is_left_recursive(grammar, vn) {
+        conflicts.push(Conflict {
+          vn,
+          kind: ConflictKind::LeftRecursion,
+          tokens: BTreeSet::default(),
+        });
+      }
+
+      let alternatives = &grammar[&vn];
+      let predicts = alternatives
+        .iter()
+        .map(|alternative| match self.first_of(alternative) {
+          (first, true) => first.union(&self.follow[&vn]).cloned().collect(),
+          (first, false) => first,
+        })
+        .collect::<Vec<Set>>();
+      for i in 0..predicts.len() {
+        for j in i + 1..predicts.len() {
+          let tokens = predicts[i].intersection(&predicts[j]).cloned().collect();
+          push_conflict(&mut conflicts, vn, ConflictKind::Alternatives(i, j), tokens);
+        }
+      }
+
+      for (i, alternative) in alternatives.iter().enumerate() {
+        for (j, ex_elem) in alternative.iter().enumerate() {
+          if !(ex_elem.is_optional || ex_elem.is_repeated) {
+            continue;
+          }
+          let (first, _) = self.first_of_elems(&ex_elem.elem_list);
+          let (mut follow, nullable) = self.first_of(&alternative[j + 1..]);
+          if nullable {
+            follow.extend(self.follow[&vn].iter().cloned());
+          }
+          let tokens = first.intersection(&follow).cloned().collect();
+          push_conflict(&mut conflicts, vn, ConflictKind::Optional(i, j), tokens);
+        }
+      }
+    }
+    conflicts
+  }
+
+  /// Whether `vn` could derive a sequence starting with itself
+  fn is_left_recursive(&self, grammar: &Grammar, vn: VN) -> bool {
+    let mut visited = HashSet::new();
+    let mut stack = vec![vn];
+    while let Some(current) = stack.pop() {
+      for alternative in &grammar[&current] {
+        'seq: for ex_elem in alternative {
+          for elem in &ex_elem.elem_list {
+            let Elem::VN(next) = elem else {
+              break 'seq;
+            };
+            if *next == vn {
+              return true;
+            }
+            if visited.insert(*next) {
+              stack.push(*next);
+            }
+            if !self.nullable.contains(next) {
+              break 'seq;
+            }
+          }
+          if !(ex_elem.is_optional || ex_elem.is_repeated) {
+            break;
+          }
+        }
+      }
+    }
+    false
+  }
+}
+
+fn push_conflict(conflicts: &mut Vec<Conflict>, vn: VN, kind: ConflictKind, tokens: Set) {
+  if !tokens.is_empty() {
+    let tokens = tokens.into_iter().collect();
+    conflicts.push(Conflict { vn, kind, tokens });
+  }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ConflictKind {
+  LeftRecursion,
+  /// Indices of the two alternatives
+  Alternatives(usize, usize),
+  /// Index of the alternative, and the index of the optional / repeated
+  /// part in it
+  Optional(usize, usize),
+}
+
+/// A violation of LL(1) in `vn`, `tokens` are where the parser couldn't
+/// decide with one token of lookahead
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Conflict {
+  pub vn: VN,
+  pub kind: ConflictKind,
+  pub tokens: BTreeSet<Token>,
+}
+
+impl Display for Conflict {
+  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    let tokens = self
+      .tokens
+      .iter()
+      .map(|token| format!("`{}`", token))
+      .collect::<Vec<_>>()
+      .join(", ");
+    match self.kind {
+      ConflictKind::LeftRecursion => write!(f, "{} is left recursive", self.vn),
+      ConflictKind::Alternatives(i, j) => write!(
+        f,
+        "alternatives #{} and #{} of {} could both start with {}",
+        i + 1,
+        j + 1,
+        self.vn,
+        tokens
+      ),
+      ConflictKind::Optional(i, j) => write!(
+        f,
+        "part #{} of alternative #{} of {} is optional, but {} could both start and follow it",
+        j + 1,
+        i + 1,
+        self.vn,
+        tokens
+      ),
+    }
+  }
+}
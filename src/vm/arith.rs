@@ -0,0 +1,152 @@
+use std::{fmt::Display, str::FromStr};
+
+/// Width of the integers of the target machine
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Default)]
+pub enum IntWidth {
+  I16,
+  I32,
+  #[default]
+  I64,
+}
+
+impl IntWidth {
+  pub fn bits(self) -> u32 {
+    match self {
+      Self::I16 => 16,
+      Self::I32 => 32,
+      Self::I64 => 64,
+    }
+  }
+
+  pub fn min(self) -> i64 {
+    i64::MIN >> (64 - self.bits())
+  }
+
+  pub fn max(self) -> i64 {
+    i64::MAX >> (64 - self.bits())
+  }
+
+  pub fn contains(self, val: i64) -> bool {
+    (self.min()..=self.max()).contains(&val)
+  }
+}
+
+impl Display for IntWidth {
+  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    write!(f, "i{}", self.bits())
+  }
+}
+
+impl FromStr for IntWidth {
+  type Err = String;
+
+  fn from_str(s: &str) -> Result<Self, Self::Err> {
+    match s {
+      "i16" => Ok(Self::I16),
+      "i32" => Ok(Self::I32),
+      "i64" => Ok(Self::I64),
+      _ => Err(format!("unknown integer width `{}`", s)),
+    }
+  }
+}
+
+/// What happens if the result doesn't fit in the width
+///
+/// - Checked: a runtime error (`ArithmeticOverflow`)
+/// - Wrapping: wraps around (two's complement)
+/// - Saturating: clamped to the min / max value
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Default)]
+pub enum Overflow {
+  #[default]
+  Checked,
+  Wrapping,
+  Saturating,
+}
+
+impl Display for Overflow {
+  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    let overflow = match self {
+      Self::Checked => "checked",
+      Self::Wrapping => "wrapping",
+      Self::Saturating => "saturating",
+    };
+    write!(f, "{}", overflow)
+  }
+}
+
+impl FromStr for Overflow {
+  type Err = String;
+
+  fn from_str(s: &str) -> Result<Self, Self::Err> {
+    match s {
+      "checked" => Ok(Self::Checked),
+      "wrapping" => Ok(Self::Wrapping),
+      "saturating" => Ok(Self::Saturating),
+      _ => Err(format!("unknown overflow behavior `{}`", s)),
+    }
+  }
+}
+
+/// Integer semantics of the target machine, shared by the lexer (range of
+/// literals), `ConstFolder` and `VM`, so that folded constants always equal
+/// what the VM would compute.
+///
+/// Operands are expected to be in the range of `width`. `None` means the
+/// operation overflows under `Overflow::Checked` (or divides by zero).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub struct IntSemantics {
+  pub width: IntWidth,
+  pub overflow: Overflow,
+}
+
+impl IntSemantics {
+  pub fn new(width: IntWidth, overflow: Overflow) -> Self {
+    Self { width, overflow }
+  }
+
+  pub fn add(&self, l: i64, r: i64) -> Option<i64> {
+    self.fit(l as i128 + r as i128)
+  }
+
+  pub fn sub(&self, l: i64, r: i64) -> Option<i64> {
+    self.fit(l as i128 - r as i128)
+  }
+
+  pub fn mul(&self, l: i64, r: i64) -> Option<i64> {
+    self.fit(l as i128 * r as i128)
+  }
+
+  /// Truncated towards zero, `None` if `r` is zero
+  pub fn div(&self, l: i64, r: i64) -> Option<i64> {
+    if r == 0 {
+      return None;
+    }
+    self.fit(l as i128 / r as i128)
+  }
+
+  pub fn neg(&self, val: i64) -> Option<i64> {
+    self.fit(-(val as i128))
+  }
+
+  /// The exact result, applied `overflow` if it's out of range
+  fn fit(&self, val: i128) -> Option<i64> {
+    let (min, max) = (self.width.min() as i128, self.width.max() as i128);
+    if (min..=max).contains(&val) {
+      return Some(val as i64);
+    }
+    match self.overflow {
+      Overflow::Checked => None,
+      Overflow::Wrapping => {
+        let shift = 64 - self.width.bits();
+        Some(((val as i64) << shift) >> shift)
+      }
+      Overflow::Saturating => Some(if val < min { min } else { max } as i64),
+    }
+  }
+}
+
+impl Display for IntSemantics {
+  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    write!(f, "{} ({})", self.width, self.overflow)
+  }
+}
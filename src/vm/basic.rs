@@ -1,10 +1,19 @@
-use std::io::Write;
-
-use crate::pcode::{PCodeManager, PcodeType};
+use super::{
+  arith::IntSemantics,
+  io::{StdIo, VmIo},
+  verifier::Verifier,
+};
+use crate::{
+  error::runtime_error::{RuntimeError, RuntimeErrorType},
+  pcode::{PCodeManager, Pcode, PcodeType},
+};
 
 const STACK_SIZE: usize = 4096;
 const SEP: &str = "  ";
 
+/// boxed, to keep the hot path of `interpret` small
+type VmResult<T> = Result<T, Box<RuntimeError>>;
+
 /// ## Format
 ///
 /// each data slice:
@@ -14,27 +23,212 @@ const SEP: &str = "  ";
 /// - DL: Dynamic Link (old_sp)
 /// - SL: Static Link (use this to find direct outer level's DL)
 /// - RA: Return Address (pc)
+///
+/// `read` / `write` go through `IO` (console by default), and arithmetic
+/// follows `IntSemantics` (checked `i64` by default).
 #[derive(Debug, Clone)]
-pub struct VM {
+pub struct VM<IO = StdIo> {
   data: [i64; STACK_SIZE],
   code: PCodeManager,
+  io: IO,
+  /// index of the pcode being executed (used to locate runtime errors)
+  curr_pc: usize,
+  int: IntSemantics,
 }
 
 impl VM {
   pub fn new(code: PCodeManager) -> Self {
+    Self::with_io(code, StdIo::default())
+  }
+
+  /// Same as `new`, but `code` must pass `Verifier` first (e.g. pcode loaded
+  /// from disk or written by hand)
+  pub fn try_new(code: PCodeManager) -> Result<Self, Vec<RuntimeError>> {
+    Self::try_with_io(code, StdIo::default())
+  }
+}
+
+impl<IO: VmIo> VM<IO> {
+  pub fn with_io(code: PCodeManager, io: IO) -> Self {
     Self {
       data: [0; STACK_SIZE],
       code,
+      io,
+      curr_pc: 0,
+      int: IntSemantics::default(),
     }
   }
 
-  fn get_base(&self, base: usize, level: usize) -> usize {
+  /// Should be the same as the one the pcode is compiled with
+  pub fn with_int_semantics(mut self, int: IntSemantics) -> Self {
+    self.int = int;
+    self
+  }
+
+  /// Same as `with_io`, but `code` must pass `Verifier` first
+  pub fn try_with_io(code: PCodeManager, io: IO) -> Result<Self, Vec<RuntimeError>> {
+    Verifier::new(&code).verify()?;
+    Ok(Self::with_io(code, io))
+  }
+
+  pub fn io(&self) -> &IO {
+    &self.io
+  }
+
+  pub fn into_io(self) -> IO {
+    self.io
+  }
+
+  #[cold]
+  fn error(&self, error_type: RuntimeErrorType, info: String) -> Box<RuntimeError> {
+    let line_info = self.code.line_table.lookup(self.curr_pc);
+    let info = match line_info {
+      Some(line_info) => format!("{} (pc: {}, {})", info, self.curr_pc, line_info),
+      None => format!("{} (pc: {})", info, self.curr_pc),
+    };
+    Box::new(RuntimeError::new(error_type, self.curr_pc, line_info, info))
+  }
+
+  fn load(&self, addr: usize) -> VmResult<i64> {
+    self.data.get(addr).copied().ok_or_else(|| {
+      self.error(
+        RuntimeErrorType::StackOverflow,
+        format!("address `{}` is out of the data stack", addr),
+      )
+    })
+  }
+
+  fn store(&mut self, addr: usize, val: i64) -> VmResult<()> {
+    match self.data.get_mut(addr) {
+      Some(slot) => {
+        *slot = val;
+        Ok(())
+      }
+      None => Err(self.error(
+        RuntimeErrorType::StackOverflow,
+        format!("address `{}` is out of the data stack", addr),
+      )),
+    }
+  }
+
+  fn push(&mut self, top: &mut usize, val: i64) -> VmResult<()> {
+    self.store(*top, val)?;
+    *top += 1;
+    Ok(())
+  }
+
+  fn pop(&mut self, top: &mut usize) -> VmResult<i64> {
+    *top = top.checked_sub(1).ok_or_else(|| {
+      self.error(
+        RuntimeErrorType::StackUnderflow,
+        "pop from an empty data stack".to_string(),
+      )
+    })?;
+    self.load(*top)
+  }
+
+  fn to_addr(&self, val: i64) -> VmResult<usize> {
+    usize::try_from(val).map_err(|_| {
+      self.error(
+        RuntimeErrorType::StackUnderflow,
+        format!("address `{}` is out of the data stack", val),
+      )
+    })
+  }
+
+  fn get_base(&self, base: usize, level: usize) -> VmResult<usize> {
     let mut upper_base = base;
     for _ in 0..level {
-      upper_base = self.data[upper_base + 1] as usize;
+      upper_base = self.to_addr(self.load(upper_base + 1)?)?;
     }
-    upper_base
+    Ok(upper_base)
+  }
+
+  fn fetch(&self, pc: usize) -> VmResult<Pcode> {
+    self.code.pcode_list.get(pc).copied().ok_or_else(|| {
+      self.error(
+        RuntimeErrorType::InvalidInstruction,
+        format!("pc `{}` is out of the pcode list", pc),
+      )
+    })
   }
+
+  fn read_integer(&mut self) -> VmResult<i64> {
+    let input = match self.io.read_line() {
+      Ok(Some(input)) => input,
+      Ok(None) => {
+        return Err(self.error(
+          RuntimeErrorType::InvalidInput,
+          "unexpected end of input".to_string(),
+        ))
+      }
+      Err(err) => {
+        return Err(self.error(
+          RuntimeErrorType::IoError,
+          format!("failed to read input: {}", err),
+        ))
+      }
+    };
+    let input = input.split_whitespace().next().unwrap_or_default();
+    let val = input.parse::<i64>().map_err(|_| {
+      self.error(
+        RuntimeErrorType::InvalidInput,
+        format!("`{}` is not a valid integer", input),
+      )
+    })?;
+    if !self.int.width.contains(val) {
+      return Err(self.error(
+        RuntimeErrorType::InvalidInput,
+        format!("`{}` is out of range for `{}`", input, self.int.width),
+      ));
+    }
+    Ok(val)
+  }
+
+  fn write_str(&mut self, s: &str) -> VmResult<()> {
+    self.io.write_str(s).map_err(|err| {
+      self.error(
+        RuntimeErrorType::IoError,
+        format!("failed to write output: {}", err),
+      )
+    })
+  }
+
+  /// `OPR 2 ~ 5` and `OPR 8 ~ 13`
+  fn binary_op(&self, opr: i64, l: i64, r: i64) -> VmResult<i64> {
+    let val = match opr {
+      2 => self.int.add(l, r).ok_or_else(|| self.overflow(l, "+", r))?,
+      3 => self.int.sub(l, r).ok_or_else(|| self.overflow(l, "-", r))?,
+      4 => self.int.mul(l, r).ok_or_else(|| self.overflow(l, "*", r))?,
+      5 if r == 0 => {
+        return Err(self.error(
+          RuntimeErrorType::DivisionByZero,
+          format!("attempt to compute `{} / 0`", l),
+        ))
+      }
+      5 => self.int.div(l, r).ok_or_else(|| self.overflow(l, "/", r))?,
+      8 => (l == r) as i64,
+      9 => (l != r) as i64,
+      10 => (l < r) as i64,
+      11 => (l >= r) as i64,
+      12 => (l > r) as i64,
+      13 => (l <= r) as i64,
+      _ => unreachable!(),
+    };
+    Ok(val)
+  }
+
+  #[cold]
+  fn overflow(&self, l: i64, op: &str, r: i64) -> Box<RuntimeError> {
+    self.error(
+      RuntimeErrorType::ArithmeticOverflow,
+      format!(
+        "attempt to compute `{} {} {}` with overflow ({})",
+        l, op, r, self.int.width
+      ),
+    )
+  }
+
   /// ## Format
   ///
   /// each data slice:
@@ -44,97 +238,102 @@ impl VM {
   /// - DL: Dynamic Link (old_sp / base)
   /// - SL: Static Link (use this to find direct outer level's DL)
   /// - RA: Return Address (pc)
-  pub fn interpret(&mut self) {
+  pub fn interpret(&mut self) -> Result<(), RuntimeError> {
+    self.run().map_err(|err| *err)
+  }
+
+  fn run(&mut self) -> VmResult<()> {
     let mut pc = 0;
     let mut base = 0;
     let mut top = 0;
     loop {
-      let inst = self.code.pcode_list[pc];
+      self.curr_pc = pc;
+      let inst = self.fetch(pc)?;
       pc += 1;
       match inst.f {
-        PcodeType::NIL => panic!("invalid instruction"),
-        PcodeType::LIT => {
-          self.data[top] = inst.a;
-          top += 1;
+        PcodeType::NIL => {
+          return Err(self.error(
+            RuntimeErrorType::InvalidInstruction,
+            "`NIL` is not an executable instruction".to_string(),
+          ))
         }
-        PcodeType::OPR => match inst.a as usize {
+        PcodeType::LIT => self.push(&mut top, inst.a)?,
+        PcodeType::OPR => match inst.a {
           0 => {
             top = base;
-            pc = self.data[top + 2] as usize;
-            base = self.data[top] as usize;
+            pc = self.to_addr(self.load(top + 2)?)?;
+            base = self.to_addr(self.load(top)?)?;
           }
-          1 => self.data[top - 1] = -self.data[top - 1],
-          2 => {
-            top -= 1;
-            self.data[top - 1] += self.data[top];
+          1 => {
+            let val = self.pop(&mut top)?;
+            let val = self.int.neg(val).ok_or_else(|| {
+              self.error(
+                RuntimeErrorType::ArithmeticOverflow,
+                format!(
+                  "attempt to negate `{}` with overflow ({})",
+                  val, self.int.width
+                ),
+              )
+            })?;
+            self.push(&mut top, val)?;
           }
-          3 => {
-            top -= 1;
-            self.data[top - 1] -= self.data[top];
+          2..=5 | 8..=13 => {
+            let r = self.pop(&mut top)?;
+            let l = self.pop(&mut top)?;
+            let val = self.binary_op(inst.a, l, r)?;
+            self.push(&mut top, val)?;
           }
-          4 => {
-            top -= 1;
-            self.data[top - 1] *= self.data[top];
+          6 => {
+            let val = self.pop(&mut top)?;
+            self.push(&mut top, val % 2)?;
           }
-          5 => {
-            top -= 1;
-            self.data[top - 1] /= self.data[top];
-          }
-          6 => self.data[top - 1] %= 2,
           7 => continue,
-          8 => {
-            top -= 1;
-            self.data[top - 1] = (self.data[top - 1] == self.data[top]) as i64;
-          }
-          9 => {
-            top -= 1;
-            self.data[top - 1] = (self.data[top - 1] != self.data[top]) as i64;
-          }
-          10 => {
-            top -= 1;
-            self.data[top - 1] = (self.data[top - 1] < self.data[top]) as i64;
-          }
-          11 => {
-            top -= 1;
-            self.data[top - 1] = (self.data[top - 1] >= self.data[top]) as i64;
+          14 => {
+            let val = self.pop(&mut top)?;
+            self.write_str(&format!("{}{}", val, SEP))?;
           }
-          12 => {
-            top -= 1;
-            self.data[top - 1] = (self.data[top - 1] > self.data[top]) as i64;
+          15 => self.write_str("\n")?,
+          16 => {
+            let input = self.read_integer()?;
+            self.push(&mut top, input)?;
           }
-          13 => {
-            top -= 1;
-            self.data[top - 1] = (self.data[top - 1] <= self.data[top]) as i64;
+          // return of a function: drop `l` args of the caller, then push the result
+          17 => {
+            let val = self.pop(&mut top)?;
+            let old_base = base;
+            pc = self.to_addr(self.load(old_base + 2)?)?;
+            base = self.to_addr(self.load(old_base)?)?;
+            top = old_base.checked_sub(inst.l).ok_or_else(|| {
+              self.error(
+                RuntimeErrorType::StackUnderflow,
+                format!(
+                  "`OPR {} 17` drops more args than the data stack has",
+                  inst.l
+                ),
+              )
+            })?;
+            self.push(&mut top, val)?;
           }
-          14 => {
-            print!("{}{}", self.data[top - 1], SEP);
+          // duplicate the top, see `PeepholeOptimizer`
+          18 => {
+            let val = self.pop(&mut top)?;
+            self.push(&mut top, val)?;
+            self.push(&mut top, val)?;
           }
-          15 => println!(),
-          16 => {
-            print!("<== Please input: ");
-            // immediate output
-            std::io::stdout().flush().unwrap();
-            let mut input = String::new();
-            std::io::stdin().read_line(&mut input).unwrap();
-            let input = input
-              .trim()
-              .split(char::is_whitespace)
-              .nth(0)
-              .unwrap()
-              .parse::<i64>()
-              .unwrap();
-            self.data[top] = input;
-            top += 1;
+          _ => {
+            return Err(self.error(
+              RuntimeErrorType::InvalidInstruction,
+              format!("`OPR {}` is not a valid operator", inst.a),
+            ))
           }
-          _ => panic!("invalid operator"),
         },
         PcodeType::LOD => {
-          self.data[top] = self.data[self.get_base(base, inst.l) + inst.a as usize];
-          top += 1;
+          let val = self.load(self.get_base(base, inst.l)? + self.to_addr(inst.a)?)?;
+          self.push(&mut top, val)?;
         }
         PcodeType::STO => {
-          top -= 1;
-          self.data[self.get_base(base, inst.l) + inst.a as usize] = self.data[top];
+          let val = self.pop(&mut top)?;
+          self.store(self.get_base(base, inst.l)? + self.to_addr(inst.a)?, val)?;
         }
         PcodeType::STA => {
           // if you've used STA, current `top` is the `base` of
@@ -143,57 +342,109 @@ impl VM {
           // `top - 1` could find the latest data in caller proc
           //
           // `inst.l` shows how much steps back to caller's specific proc
-          self.data[top + inst.a as usize] = self.data[top - inst.l];
+          let src = top.checked_sub(inst.l).ok_or_else(|| {
+            self.error(
+              RuntimeErrorType::StackUnderflow,
+              "read from an empty data stack".to_string(),
+            )
+          })?;
+          self.store(top + self.to_addr(inst.a)?, self.load(src)?)?;
         }
         PcodeType::CAL => {
           // new: DL
-          self.data[top] = base as i64;
+          self.store(top, base as i64)?;
           // new: SL
-          self.data[top + 1] = self.get_base(base, inst.l) as i64;
+          self.store(top + 1, self.get_base(base, inst.l)? as i64)?;
           // new: RA(ip)
-          self.data[top + 2] = pc as i64;
+          self.store(top + 2, pc as i64)?;
           base = top;
-          pc = inst.a as usize;
+          pc = self.to_addr(inst.a)?;
         }
-        PcodeType::INT => top += inst.a as usize,
-        PcodeType::JMP => pc = inst.a as usize,
+        PcodeType::INT => {
+          // negative: drop the args pushed before `CAL`
+          top = top.checked_add_signed(inst.a as isize).ok_or_else(|| {
+            self.error(
+              RuntimeErrorType::StackUnderflow,
+              format!("`INT 0 {}` is out of the data stack", inst.a),
+            )
+          })?;
+          if top > STACK_SIZE {
+            return Err(self.error(
+              RuntimeErrorType::StackOverflow,
+              format!("data stack (size: {}) overflowed", STACK_SIZE),
+            ));
+          }
+        }
+        PcodeType::JMP => pc = self.to_addr(inst.a)?,
         PcodeType::JPC => {
-          if self.data[top - 1] == 0 {
-            pc = inst.a as usize
+          if self.pop(&mut top)? == 0 {
+            pc = self.to_addr(inst.a)?
           }
         }
         PcodeType::RED => {
-          print!("<== Please input: ");
-          // immediate output
-          std::io::stdout().flush().unwrap();
-          let mut input = String::new();
-          std::io::stdin().read_line(&mut input).unwrap();
-          let input = input
-            .trim()
-            .split(char::is_whitespace)
-            .nth(0)
-            .unwrap()
-            .parse::<i64>()
-            .unwrap();
-          self.data[self.get_base(base, inst.l) + inst.a as usize] = input;
+          let input = self.read_integer()?;
+          self.store(self.get_base(base, inst.l)? + self.to_addr(inst.a)?, input)?;
           top += 1;
         }
         PcodeType::WRT => {
-          print!("{}{}", self.data[top - 1], SEP);
+          let val = self.pop(&mut top)?;
+          self.write_str(&format!("{}{}", val, SEP))?;
+        }
+        PcodeType::LDA => {
+          let addr = self.get_base(base, inst.l)? + self.to_addr(inst.a)?;
+          self.push(&mut top, addr as i64)?;
+        }
+        PcodeType::LDI => {
+          let addr = self.pop(&mut top)?;
+          let val = self.load(self.to_addr(addr)?)?;
+          self.push(&mut top, val)?;
+        }
+        PcodeType::STI => {
+          let val = self.pop(&mut top)?;
+          let addr = self.pop(&mut top)?;
+          self.store(self.to_addr(addr)?, val)?;
+        }
+        PcodeType::CHK => {
+          let index = self.pop(&mut top)?;
+          let lo = inst.l as i64;
+          if !(lo..=inst.a).contains(&index) {
+            return Err(self.error(
+              RuntimeErrorType::IndexOutOfBounds,
+              format!(
+                "index `{}` is out of the bounds `{} .. {}`",
+                index, lo, inst.a
+              ),
+            ));
+          }
+          self.push(&mut top, index - lo)?;
+        }
+        PcodeType::LDX => {
+          let offset = self.pop(&mut top)?;
+          let offset = self.to_addr(offset)?;
+          let addr = self.get_base(base, inst.l)? + self.to_addr(inst.a)? + offset;
+          let val = self.load(addr)?;
+          self.push(&mut top, val)?;
+        }
+        PcodeType::STX => {
+          let val = self.pop(&mut top)?;
+          let offset = self.pop(&mut top)?;
+          let offset = self.to_addr(offset)?;
+          self.store(
+            self.get_base(base, inst.l)? + self.to_addr(inst.a)? + offset,
+            val,
+          )?;
         }
       }
       if pc == 0 {
         break;
       }
     }
+    Ok(())
   }
 }
 
 impl Default for VM {
   fn default() -> Self {
-    Self {
-      data: [0; STACK_SIZE],
-      code: PCodeManager::default(),
-    }
+    Self::new(PCodeManager::default())
   }
 }
@@ -0,0 +1,107 @@
+use std::io::{self, BufRead, Write};
+
+const PROMPT: &str = "<== Please input: ";
+
+/// Where `read` gets its input and where `write` puts its output.
+pub trait VmIo {
+  /// Read one line of input, `Ok(None)` means there's nothing left to read.
+  fn read_line(&mut self) -> io::Result<Option<String>>;
+
+  /// Write a piece of output.
+  fn write_str(&mut self, s: &str) -> io::Result<()>;
+}
+
+/// Console I/O (stdin / stdout), with an optional input prompt.
+#[derive(Debug, Clone)]
+pub struct StdIo {
+  prompt: Option<String>,
+}
+
+impl Default for StdIo {
+  fn default() -> Self {
+    Self {
+      prompt: Some(PROMPT.to_string()),
+    }
+  }
+}
+
+impl StdIo {
+  pub fn new() -> Self {
+    Self::default()
+  }
+
+  pub fn with_prompt(mut self, prompt: Option<&str>) -> Self {
+    self.prompt = prompt.map(str::to_string);
+    self
+  }
+}
+
+impl VmIo for StdIo {
+  fn read_line(&mut self) -> io::Result<Option<String>> {
+    let mut stdout = io::stdout();
+    if let Some(prompt) = &self.prompt {
+      write!(stdout, "{}", prompt)?;
+    }
+    // immediate output
+    stdout.flush()?;
+    let mut input = String::new();
+    match io::stdin().read_line(&mut input)? {
+      0 => Ok(None),
+      _ => Ok(Some(input)),
+    }
+  }
+
+  fn write_str(&mut self, s: &str) -> io::Result<()> {
+    write!(io::stdout(), "{}", s)
+  }
+}
+
+/// I/O over any `BufRead` / `Write` pair (e.g. scripted input and captured
+/// output), without prompt by default.
+#[derive(Debug, Clone)]
+pub struct BufIo<R: BufRead, W: Write> {
+  reader: R,
+  writer: W,
+  prompt: Option<String>,
+}
+
+impl<R: BufRead, W: Write> BufIo<R, W> {
+  pub fn new(reader: R, writer: W) -> Self {
+    Self {
+      reader,
+      writer,
+      prompt: None,
+    }
+  }
+
+  pub fn with_prompt(mut self, prompt: Option<&str>) -> Self {
+    self.prompt = prompt.map(str::to_string);
+    self
+  }
+
+  pub fn writer(&self) -> &W {
+    &self.writer
+  }
+
+  pub fn into_inner(self) -> (R, W) {
+    (self.reader, self.writer)
+  }
+}
+
+impl<R: BufRead, W: Write> VmIo for BufIo<R, W> {
+  fn read_line(&mut self) -> io::Result<Option<String>> {
+    if let Some(prompt) = &self.prompt {
+      write!(self.writer, "{}", prompt)?;
+    }
+    self.writer.flush()?;
+    let mut input = String::new();
+    match self.reader.read_line(&mut input)? {
+      0 => Ok(None),
+      _ => Ok(Some(input)),
+    }
+  }
+
+  fn write_str(&mut self, s: &str) -> io::Result<()> {
+    write!(self.writer, "{}", s)
+  }
+}
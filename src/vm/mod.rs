@@ -2,5 +2,8 @@
 
 use std::{cell::RefCell, rc::Rc};
 
+pub mod arith;
 pub mod basic;
+pub mod io;
 pub mod lib;
+pub mod verifier;
@@ -0,0 +1,304 @@
+use std::collections::HashMap;
+
+use crate::{
+  error::runtime_error::{RuntimeError, RuntimeErrorType},
+  pcode::{PCodeManager, PcodeType},
+};
+
+/// Static checks of pcode, before it's accepted by the VM:
+///
+/// - every `JMP` / `JPC` / `CAL` target is inside `pcode_list`
+/// - no `NIL`, no `OPR` other than `0 ~ 18`, no `CHK` without any index in
+///   bounds
+/// - `LOD` / `STO` / `RED` / `CAL` / `LDA` / `LDX` / `STX` never go up further
+///   than the static chain
+/// - each procedure keeps its data stack balanced (same depth on every path
+///   reaching a pcode, never pop from an empty slice)
+///
+/// Procedures are found by following `CAL` from the entry (pc `0`, level `0`),
+/// a callee of `CAL l a` at level `d` is at level `d - l + 1`. Every return
+/// of a callee must agree on its effect on the caller's data slice: none for
+/// `OPR 0 0`, and `n` args dropped + the result pushed for `OPR n 17`.
+#[derive(Debug)]
+pub struct Verifier<'a> {
+  code: &'a PCodeManager,
+  /// pc -> (stack depth of the data slice, static level)
+  states: HashMap<usize, (i64, usize)>,
+  /// entry of a callee -> (n_pop, n_push) of `CAL` in the caller
+  effects: HashMap<usize, (i64, i64)>,
+  errors: Vec<RuntimeError>,
+}
+
+impl<'a> Verifier<'a> {
+  pub fn new(code: &'a PCodeManager) -> Self {
+    Self {
+      code,
+      states: HashMap::new(),
+      effects: HashMap::new(),
+      errors: vec![],
+    }
+  }
+
+  /// All errors found will be returned
+  pub fn verify(mut self) -> Result<(), Vec<RuntimeError>> {
+    for pc in 0..self.code.pcode_list.len() {
+      self.check_operands(pc);
+    }
+    if self.errors.is_empty() && !self.code.pcode_list.is_empty() {
+      self.check_flow();
+    }
+
+    if self.errors.is_empty() {
+      Ok(())
+    } else {
+      Err(self.errors)
+    }
+  }
+}
+
+impl Verifier<'_> {
+  fn error(&mut self, error_type: RuntimeErrorType, pc: usize, info: String) {
+    let line_info = self.code.line_table.lookup(pc);
+    let info = match line_info {
+      Some(line_info) => format!("{} (pc: {}, {})", info, pc, line_info),
+      None => format!("{} (pc: {})", info, pc),
+    };
+    self
+      .errors
+      .push(RuntimeError::new(error_type, pc, line_info, info));
+  }
+
+  fn check_operands(&mut self, pc: usize) {
+    let pcode = self.code.pcode_list[pc];
+    let len = self.code.pcode_list.len() as i64;
+    match pcode.f {
+      PcodeType::NIL => self.error(
+        RuntimeErrorType::InvalidInstruction,
+        pc,
+        "`NIL` is not an executable instruction".to_string(),
+      ),
+      PcodeType::OPR if !(0..=18).contains(&pcode.a) => self.error(
+        RuntimeErrorType::InvalidInstruction,
+        pc,
+        format!("`OPR {}` is not a valid operator", pcode.a),
+      ),
+      PcodeType::JMP | PcodeType::JPC | PcodeType::CAL if !(0..len).contains(&pcode.a) => self
+        .error(
+          RuntimeErrorType::InvalidJumpTarget,
+          pc,
+          format!(
+            "`{} {} {}` jumps out of the pcode list (length: {})",
+            pcode.f, pcode.l, pcode.a, len
+          ),
+        ),
+      PcodeType::LOD
+      | PcodeType::STO
+      | PcodeType::STA
+      | PcodeType::RED
+      | PcodeType::LDA
+      | PcodeType::LDX
+      | PcodeType::STX
+        if pcode.a < 0 =>
+      {
+        self.error(
+          RuntimeErrorType::InvalidInstruction,
+          pc,
+          format!(
+            "`{} {} {}` has a negative address",
+            pcode.f, pcode.l, pcode.a
+          ),
+        )
+      }
+      PcodeType::CHK if pcode.l as i64 > pcode.a => self.error(
+        RuntimeErrorType::InvalidInstruction,
+        pc,
+        format!(
+          "`{} {} {}` has an upper bound less than the lower bound",
+          pcode.f, pcode.l, pcode.a
+        ),
+      ),
+      _ => (),
+    }
+  }
+
+  /// Walk through every path from the entry
+  fn check_flow(&mut self) {
+    let len = self.code.pcode_list.len();
+    let mut work_list = vec![(0, 0, 0)];
+
+    while let Some((pc, depth, level)) = work_list.pop() {
+      if pc >= len {
+        // only reachable by falling through from the last pcode
+        self.error(
+          RuntimeErrorType::InvalidJumpTarget,
+          pc - 1,
+          "execution runs off the end of the pcode list".to_string(),
+        );
+        continue;
+      }
+      match self.states.get(&pc) {
+        Some(&(old_depth, _)) if old_depth != depth => {
+          self.error(
+            RuntimeErrorType::StackImbalance,
+            pc,
+            format!(
+              "data stack depth is {} on one path, but {} on another",
+              old_depth, depth
+            ),
+          );
+          continue;
+        }
+        Some(&(_, old_level)) if old_level != level => {
+          self.error(
+            RuntimeErrorType::InvalidLevel,
+            pc,
+            format!("reached from static level {} and {}", old_level, level),
+          );
+          continue;
+        }
+        Some(_) => continue,
+        None => {
+          self.states.insert(pc, (depth, level));
+        }
+      }
+
+      let pcode = self.code.pcode_list[pc];
+      let (n_pop, n_push) = match pcode.f {
+        PcodeType::LIT | PcodeType::LOD | PcodeType::RED | PcodeType::LDA => (0, 1),
+        PcodeType::STO | PcodeType::JPC | PcodeType::WRT => (1, 0),
+        PcodeType::LDI | PcodeType::CHK | PcodeType::LDX => (1, 1),
+        PcodeType::STI | PcodeType::STX => (2, 0),
+        PcodeType::OPR => match pcode.a {
+          1 | 6 => (1, 1),
+          2..=5 | 8..=13 => (2, 1),
+          14 => (1, 0),
+          16 => (0, 1),
+          17 => (1, 0),
+          18 => (1, 2),
+          _ => (0, 0),
+        },
+        PcodeType::CAL if pcode.a != 0 => match self.call_effect(pc, pcode.a as usize) {
+          Some(effect) => effect,
+          None => continue,
+        },
+        PcodeType::INT if pcode.a < 0 => (-pcode.a, 0),
+        PcodeType::INT => (0, pcode.a),
+        _ => (0, 0),
+      };
+      if depth < n_pop {
+        self.error(
+          RuntimeErrorType::StackImbalance,
+          pc,
+          format!(
+            "`{} {} {}` pops {} values, but the data slice only has {}",
+            pcode.f, pcode.l, pcode.a, n_pop, depth
+          ),
+        );
+        continue;
+      }
+      let next_depth = depth - n_pop + n_push;
+
+      let is_access = matches!(
+        pcode.f,
+        PcodeType::LOD
+          | PcodeType::STO
+          | PcodeType::RED
+          | PcodeType::CAL
+          | PcodeType::LDA
+          | PcodeType::LDX
+          | PcodeType::STX
+      );
+      // `CAL` could go one level deeper (callee nested in caller)
+      if is_access && pcode.l > level {
+        self.error(
+          RuntimeErrorType::InvalidLevel,
+          pc,
+          format!(
+            "`{} {} {}` goes up {} levels, but it's at static level {}",
+            pcode.f, pcode.l, pcode.a, pcode.l, level
+          ),
+        );
+        continue;
+      }
+
+      let target = pcode.a as usize;
+      match pcode.f {
+        // returned / halted
+        PcodeType::OPR if pcode.a == 0 || pcode.a == 17 => (),
+        PcodeType::JMP if target == 0 => (),
+        PcodeType::JMP => work_list.push((target, next_depth, level)),
+        PcodeType::JPC => {
+          if target != 0 {
+            work_list.push((target, next_depth, level));
+          }
+          work_list.push((pc + 1, next_depth, level));
+        }
+        PcodeType::CAL => {
+          if target != 0 {
+            work_list.push((target, 0, level - pcode.l + 1));
+          }
+          work_list.push((pc + 1, next_depth, level));
+        }
+        _ => work_list.push((pc + 1, next_depth, level)),
+      }
+    }
+  }
+
+  /// Effect of `CAL` at `pc` on the caller's data slice, by every return
+  /// reachable from the callee's `entry` (`None` if they don't agree)
+  fn call_effect(&mut self, pc: usize, entry: usize) -> Option<(i64, i64)> {
+    if let Some(&effect) = self.effects.get(&entry) {
+      return Some(effect);
+    }
+
+    let pcode_list = &self.code.pcode_list;
+    let mut visited = vec![false; pcode_list.len()];
+    let mut work_list = vec![entry];
+    let mut effect: Option<(i64, i64)> = None;
+    while let Some(curr) = work_list.pop() {
+      if curr >= pcode_list.len() || visited[curr] {
+        continue;
+      }
+      visited[curr] = true;
+
+      let pcode = pcode_list[curr];
+      let target = pcode.a as usize;
+      let ret = match pcode.f {
+        PcodeType::OPR if pcode.a == 0 => (0, 0),
+        PcodeType::OPR if pcode.a == 17 => (pcode.l as i64, 1),
+        PcodeType::JMP if target == 0 => continue,
+        PcodeType::JMP => {
+          work_list.push(target);
+          continue;
+        }
+        PcodeType::JPC => {
+          work_list.extend([target, curr + 1]);
+          continue;
+        }
+        _ => {
+          work_list.push(curr + 1);
+          continue;
+        }
+      };
+      match effect {
+        Some(prev) if prev != ret => {
+          self.error(
+            RuntimeErrorType::StackImbalance,
+            pc,
+            format!(
+              "callee at pc {} pops {} and pushes {} values on one return, but {} and {} on another",
+              entry, prev.0, prev.1, ret.0, ret.1
+            ),
+          );
+          return None;
+        }
+        _ => effect = Some(ret),
+      }
+    }
+
+    // never returns (e.g. an infinite loop)
+    let effect = effect.unwrap_or((0, 0));
+    self.effects.insert(entry, effect);
+    Some(effect)
+  }
+}